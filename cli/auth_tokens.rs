@@ -0,0 +1,213 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Credentials for remote modules hosted behind authentication, configured
+//! through the `DENO_AUTH_TOKENS` environment variable:
+//!
+//! ```sh
+//! DENO_AUTH_TOKENS=a1b2c3d4@deno.land;username:password@example.com:8080
+//! ```
+//!
+//! Entries are separated by `;`. An entry is `token@host` for a bearer token
+//! or `username:password@host` for basic authentication, where the host may
+//! be followed by a port. Tokens are only sent over HTTPS, or over HTTP to
+//! loopback hosts.
+
+use crate::colors;
+use std::fmt;
+use url::Host;
+use url::Url;
+
+#[derive(Clone, Debug, PartialEq)]
+enum AuthTokenData {
+  Bearer(String),
+  Basic { username: String, password: String },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthToken {
+  host: String,
+  port: Option<u16>,
+  data: AuthTokenData,
+}
+
+/// Returns whether `url` is loopback, where credentials can be sent over
+/// plain HTTP without leaving the machine.
+fn is_loopback(url: &Url) -> bool {
+  match url.host() {
+    Some(Host::Domain(domain)) => domain == "localhost",
+    Some(Host::Ipv4(ip)) => ip.is_loopback(),
+    Some(Host::Ipv6(ip)) => ip.is_loopback(),
+    None => false,
+  }
+}
+
+impl AuthToken {
+  /// A token without a port only applies to the default port of the scheme
+  /// of `url`. Tokens are never sent over plain HTTP to a remote host.
+  fn matches(&self, url: &Url) -> bool {
+    let is_secure = match url.scheme() {
+      "https" => true,
+      "http" => is_loopback(url),
+      _ => false,
+    };
+    let port_matches = match self.port {
+      Some(port) => url.port_or_known_default() == Some(port),
+      None => url.port().is_none(),
+    };
+    is_secure && port_matches && url.host_str() == Some(self.host.as_str())
+  }
+}
+
+/// Formats the token as the value of an `Authorization` header.
+impl fmt::Display for AuthToken {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.data {
+      AuthTokenData::Bearer(token) => write!(f, "Bearer {}", token),
+      AuthTokenData::Basic { username, password } => {
+        let credentials = format!("{}:{}", username, password);
+        write!(f, "Basic {}", base64::encode(credentials))
+      }
+    }
+  }
+}
+
+/// Splits `host[:port]`, where the host may be a bracketed IPv6 address.
+fn parse_host(host: &str) -> Option<(String, Option<u16>)> {
+  let (host, port) = match host.rfind(':') {
+    Some(index) if !host[index..].contains(']') => {
+      let port = host[index + 1..].parse::<u16>().ok()?;
+      (&host[..index], Some(port))
+    }
+    _ => (host, None),
+  };
+  if host.is_empty() {
+    return None;
+  }
+  Some((host.to_lowercase(), port))
+}
+
+fn parse_auth_token(entry: &str) -> Option<AuthToken> {
+  let (credentials, host) = match entry.rfind('@') {
+    Some(index) => (&entry[..index], &entry[index + 1..]),
+    None => return None,
+  };
+  let (host, port) = parse_host(host)?;
+  let data = match credentials.find(':') {
+    Some(index) => AuthTokenData::Basic {
+      username: credentials[..index].to_string(),
+      password: credentials[index + 1..].to_string(),
+    },
+    None if !credentials.is_empty() => {
+      AuthTokenData::Bearer(credentials.to_string())
+    }
+    None => return None,
+  };
+  Some(AuthToken { host, port, data })
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AuthTokens(Vec<AuthToken>);
+
+impl AuthTokens {
+  /// Parses the value of `DENO_AUTH_TOKENS`. Malformed entries are discarded
+  /// with a warning which doesn't echo them, since they may hold secrets.
+  pub fn new(maybe_tokens_str: Option<String>) -> Self {
+    let mut tokens = vec![];
+    if let Some(tokens_str) = maybe_tokens_str {
+      for entry in tokens_str.split(';').map(str::trim) {
+        if entry.is_empty() {
+          continue;
+        }
+        match parse_auth_token(entry) {
+          Some(token) => tokens.push(token),
+          None => eprintln!(
+            "{} Badly formed entry in DENO_AUTH_TOKENS discarded",
+            colors::yellow("Warning")
+          ),
+        }
+      }
+    }
+    Self(tokens)
+  }
+
+  pub fn from_env() -> Self {
+    Self::new(std::env::var("DENO_AUTH_TOKENS").ok())
+  }
+
+  /// Returns the token for the host and port of `url`, if it may be sent
+  /// there.
+  pub fn get(&self, url: &Url) -> Option<AuthToken> {
+    self.0.iter().find(|token| token.matches(url)).cloned()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_auth_token_bearer() {
+    let auth_tokens = AuthTokens::new(Some("abc123@deno.land".to_string()));
+    let url = Url::parse("https://deno.land/x/mod.ts").unwrap();
+    assert_eq!(auth_tokens.get(&url).unwrap().to_string(), "Bearer abc123");
+    let url = Url::parse("https://deno.land:443/x/mod.ts").unwrap();
+    assert!(auth_tokens.get(&url).is_some());
+    let url = Url::parse("https://deno.land:8080/x/mod.ts").unwrap();
+    assert_eq!(auth_tokens.get(&url), None);
+    let url = Url::parse("http://deno.land/x/mod.ts").unwrap();
+    assert_eq!(auth_tokens.get(&url), None);
+    let url = Url::parse("ftp://deno.land/x/mod.ts").unwrap();
+    assert_eq!(auth_tokens.get(&url), None);
+    let url = Url::parse("https://www.deno.land/x/mod.ts").unwrap();
+    assert_eq!(auth_tokens.get(&url), None);
+    let url = Url::parse("https://evildeno.land/x/mod.ts").unwrap();
+    assert_eq!(auth_tokens.get(&url), None);
+  }
+
+  #[test]
+  fn test_auth_token_basic() {
+    let auth_tokens =
+      AuthTokens::new(Some("user:p@ss@Example.com".to_string()));
+    let url = Url::parse("https://example.com/mod.ts").unwrap();
+    assert_eq!(
+      auth_tokens.get(&url).unwrap().to_string(),
+      "Basic dXNlcjpwQHNz"
+    );
+  }
+
+  #[test]
+  fn test_auth_token_port() {
+    let auth_tokens = AuthTokens::new(Some(
+      "abc@localhost:8080;def@[::1]:443;ghi@127.0.0.1:80".to_string(),
+    ));
+    let url = Url::parse("http://localhost:8080/mod.ts").unwrap();
+    assert_eq!(auth_tokens.get(&url).unwrap().to_string(), "Bearer abc");
+    let url = Url::parse("http://localhost/mod.ts").unwrap();
+    assert_eq!(auth_tokens.get(&url), None);
+    let url = Url::parse("https://[::1]/mod.ts").unwrap();
+    assert_eq!(auth_tokens.get(&url).unwrap().to_string(), "Bearer def");
+    let url = Url::parse("http://127.0.0.1/mod.ts").unwrap();
+    assert_eq!(auth_tokens.get(&url).unwrap().to_string(), "Bearer ghi");
+
+    let auth_tokens = AuthTokens::new(Some("abc@example.com:8080".to_string()));
+    let url = Url::parse("https://example.com:8080/mod.ts").unwrap();
+    assert_eq!(auth_tokens.get(&url).unwrap().to_string(), "Bearer abc");
+    let url = Url::parse("http://example.com:8080/mod.ts").unwrap();
+    assert_eq!(auth_tokens.get(&url), None);
+  }
+
+  #[test]
+  fn test_auth_tokens_malformed() {
+    let auth_tokens = AuthTokens::new(Some(
+      "deno.land;@deno.land;abc@;abc@host:port; def@example.com ;".to_string(),
+    ));
+    assert_eq!(
+      auth_tokens,
+      AuthTokens(vec![AuthToken {
+        host: "example.com".to_string(),
+        port: None,
+        data: AuthTokenData::Bearer("def".to_string()),
+      }])
+    );
+  }
+}
@@ -0,0 +1,80 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+use crate::test_runner::prepare_modules_urls;
+use deno_core::ErrBox;
+use std::path::Path;
+use url::Url;
+
+fn is_supported(p: &Path) -> bool {
+  use std::path::Component;
+  if let Some(Component::Normal(basename_os_str)) = p.components().next_back() {
+    let basename = basename_os_str.to_string_lossy();
+    ["ts", "tsx", "js", "mjs", "jsx"].iter().any(|ext| {
+      basename.ends_with(&format!("_bench.{}", ext))
+        || basename.ends_with(&format!(".bench.{}", ext))
+        || basename == format!("bench.{}", ext)
+    })
+  } else {
+    false
+  }
+}
+
+pub fn prepare_bench_modules_urls(
+  include: Vec<String>,
+  root_path: &Path,
+) -> Result<Vec<Url>, ErrBox> {
+  prepare_modules_urls(include, root_path, is_supported)
+}
+
+pub fn render_bench_file(
+  modules: Vec<Url>,
+  filter: Option<String>,
+  json: bool,
+) -> String {
+  let mut bench_file = "".to_string();
+
+  for module in modules {
+    bench_file.push_str(&format!("import \"{}\";\n", module.to_string()));
+  }
+
+  let options = json!({ "filter": filter, "json": json });
+  let run_benchmarks_cmd = format!(
+    "// @ts-ignore\nDeno[Deno.internal].runBenchmarks({});\n",
+    options
+  );
+  bench_file.push_str(&run_benchmarks_cmd);
+
+  bench_file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_is_supported() {
+    assert!(is_supported(Path::new("tests/subdir/foo_bench.ts")));
+    assert!(is_supported(Path::new("tests/subdir/foo_bench.tsx")));
+    assert!(is_supported(Path::new("tests/subdir/foo_bench.js")));
+    assert!(is_supported(Path::new("tests/subdir/foo_bench.mjs")));
+    assert!(is_supported(Path::new("tests/subdir/foo_bench.jsx")));
+    assert!(is_supported(Path::new("bar/foo.bench.ts")));
+    assert!(is_supported(Path::new("bar/foo.bench.js")));
+    assert!(is_supported(Path::new("foo/bar/bench.ts")));
+    assert!(is_supported(Path::new("foo/bar/bench.js")));
+    assert!(!is_supported(Path::new("README.md")));
+    assert!(!is_supported(Path::new("foo/bar_test.ts")));
+    assert!(!is_supported(Path::new("notabench.js")));
+    assert!(!is_supported(Path::new("foo/workbench.ts")));
+  }
+
+  #[test]
+  fn test_render_bench_file() {
+    let module = Url::parse("file:///dev/url_bench.ts").unwrap();
+    assert_eq!(
+      render_bench_file(vec![module], Some("parse".to_string()), true),
+      "import \"file:///dev/url_bench.ts\";\n// @ts-ignore\n\
+       Deno[Deno.internal].runBenchmarks({\"filter\":\"parse\",\"json\":true});\n"
+    );
+  }
+}
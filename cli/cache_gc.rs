@@ -0,0 +1,343 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Inspection, garbage collection and verification of the `$DENO_DIR`
+//! caches, as done by `deno cache --list`, `deno cache --gc` and
+//! `deno cache --check-integrity`.
+
+use crate::colors;
+use crate::global_state::GlobalState;
+use crate::http_cache::CacheEntry;
+use crate::http_cache::Integrity;
+use crate::info::human_size;
+use crate::module_graph::ModuleGraphLoader;
+use crate::permissions::Permissions;
+use deno_core::ErrBox;
+use deno_core::ModuleSpecifier;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Extensions of the files `TsCompiler` emits into the gen cache, appended to
+/// the cache filename of the module they were emitted for.
+const EMITTED_EXTENSIONS: &[&str] = &[".js.map", ".js", ".meta", ".buildinfo"];
+
+/// What `gc()` removed from the caches.
+#[derive(Debug, Default, PartialEq)]
+pub struct GcStats {
+  /// Number of cached remote modules removed.
+  pub modules: usize,
+  /// Number of orphaned metadata and emitted files removed, including the
+  /// files emitted for the removed modules.
+  pub orphans: usize,
+  /// Total size of the removed files in bytes.
+  pub size: u64,
+}
+
+fn days_since(time: SystemTime, now: SystemTime) -> u64 {
+  now
+    .duration_since(time)
+    .map_or(0, |age| age.as_secs() / SECONDS_PER_DAY)
+}
+
+fn format_last_used(entry: &CacheEntry, now: SystemTime) -> String {
+  match days_since(entry.last_used, now) {
+    0 => "today".to_string(),
+    1 => "1 day ago".to_string(),
+    days => format!("{} days ago", days),
+  }
+}
+
+/// Prints the remote modules in the cache with their size and last use.
+pub fn list(global_state: &GlobalState) -> Result<(), ErrBox> {
+  let (mut entries, _) = global_state.file_fetcher.http_cache.entries()?;
+  entries.sort_by(|a, b| a.url.cmp(&b.url));
+  let now = SystemTime::now();
+  for entry in &entries {
+    println!(
+      "{:>10}  {:<12}  {}",
+      human_size(entry.size as f64),
+      format_last_used(entry, now),
+      entry.url
+    );
+  }
+  let total_size: u64 = entries.iter().map(|entry| entry.size).sum();
+  println!(
+    "{}",
+    colors::gray(&format!(
+      "{} cached modules, total {}",
+      entries.len(),
+      human_size(total_size as f64)
+    ))
+  );
+  Ok(())
+}
+
+/// Returns whether a cached module should be collected. A module is kept if
+/// it is reachable from the roots or was used within `max_age`; when neither
+/// criterion is given every module is kept.
+fn should_remove(
+  entry: &CacheEntry,
+  maybe_reachable: Option<&HashSet<String>>,
+  maybe_max_age: Option<Duration>,
+  now: SystemTime,
+) -> bool {
+  if maybe_reachable.is_none() && maybe_max_age.is_none() {
+    return false;
+  }
+  let is_reachable = maybe_reachable
+    .map_or(false, |reachable| reachable.contains(entry.url.as_str()));
+  let is_recent = maybe_max_age.map_or(false, |max_age| {
+    now
+      .duration_since(entry.last_used)
+      .map_or(true, |age| age < max_age)
+  });
+  !is_reachable && !is_recent
+}
+
+/// Returns the source a file in the gen cache was emitted from, given its
+/// path relative to the gen cache.
+fn emitted_source_path(
+  filename: &Path,
+  deps_location: &Path,
+) -> Option<PathBuf> {
+  let file_name = filename.file_name()?.to_str()?;
+  let source_name = EMITTED_EXTENSIONS
+    .iter()
+    .find_map(|extension| file_name.strip_suffix(extension))?;
+  let source_filename = filename.with_file_name(source_name);
+  let mut components = source_filename.components();
+  match components.next()?.as_os_str().to_str()? {
+    "http" | "https" => Some(deps_location.join(&source_filename)),
+    "file" => local_source_path(components.as_path()),
+    _ => None,
+  }
+}
+
+/// Turns a path below the `file` directory of the gen cache back into the
+/// absolute path of the local module, see `DiskCache::get_cache_filename()`.
+fn local_source_path(filename: &Path) -> Option<PathBuf> {
+  if cfg!(windows) {
+    let mut components = filename.components();
+    let disk = components.next()?.as_os_str().to_str()?;
+    // UNC shares are stored with their host and aren't checked.
+    if disk.len() != 1 {
+      return None;
+    }
+    Some(PathBuf::from(format!("{}:\\", disk)).join(components.as_path()))
+  } else {
+    Some(Path::new("/").join(filename))
+  }
+}
+
+fn remove_file(path: &Path, stats: &mut GcStats) -> Result<(), ErrBox> {
+  let size = fs::metadata(path)?.len();
+  fs::remove_file(path)?;
+  stats.orphans += 1;
+  stats.size += size;
+  Ok(())
+}
+
+/// Removes the cached remote modules which aren't reachable from `roots`
+/// (when any are given) and weren't used within the last `max_age_days`
+/// (when given), followed by orphaned metadata files and the files emitted
+/// for modules which are no longer cached or no longer exist.
+pub async fn gc(
+  global_state: &GlobalState,
+  roots: Vec<ModuleSpecifier>,
+  max_age_days: Option<u64>,
+) -> Result<GcStats, ErrBox> {
+  let maybe_reachable: Option<HashSet<String>> = if roots.is_empty() {
+    None
+  } else {
+    let mut module_graph_loader = ModuleGraphLoader::new(
+      global_state.file_fetcher.clone(),
+      global_state.maybe_import_map.clone(),
+      Permissions::allow_all(),
+      false,
+      true,
+    );
+    for root in roots {
+      module_graph_loader.add_to_graph(&root, None).await?;
+    }
+    let graph = module_graph_loader.get_graph();
+    Some(graph.keys().cloned().collect())
+  };
+  let maybe_max_age =
+    max_age_days.map(|days| Duration::from_secs(days * SECONDS_PER_DAY));
+
+  let mut stats = GcStats::default();
+  let http_cache = &global_state.file_fetcher.http_cache;
+  let (entries, orphans) = http_cache.entries()?;
+  let now = SystemTime::now();
+  for entry in entries {
+    if should_remove(&entry, maybe_reachable.as_ref(), maybe_max_age, now) {
+      http_cache.remove(&entry.url)?;
+      stats.modules += 1;
+      stats.size += entry.size;
+    }
+  }
+  for orphan in orphans {
+    remove_file(&orphan, &mut stats)?;
+  }
+
+  let gen_location = &global_state.dir.gen_cache.location;
+  if gen_location.is_dir() {
+    for dir_entry in WalkDir::new(gen_location) {
+      let dir_entry = dir_entry?;
+      if !dir_entry.file_type().is_file() {
+        continue;
+      }
+      let path = dir_entry.path();
+      let filename = path.strip_prefix(gen_location).unwrap();
+      if let Some(source_path) =
+        emitted_source_path(filename, &http_cache.location)
+      {
+        if !source_path.is_file() {
+          remove_file(path, &mut stats)?;
+        }
+      }
+    }
+  }
+
+  Ok(stats)
+}
+
+/// What `check_integrity()` found in the cache.
+#[derive(Debug, Default, PartialEq)]
+pub struct IntegrityStats {
+  /// Number of cached remote modules checked.
+  pub checked: usize,
+  /// Number of modules which don't match their checksum and weren't repaired.
+  pub corrupted: usize,
+  /// Number of corrupted modules which were downloaded again.
+  pub repaired: usize,
+  /// Number of modules cached without a checksum, which can't be checked.
+  pub unknown: usize,
+}
+
+/// Checks every remote module in the cache against the checksum stored in
+/// its metadata, without accessing the network. With `repair`, corrupted
+/// modules are removed from the cache and downloaded again.
+pub async fn check_integrity(
+  global_state: &GlobalState,
+  repair: bool,
+) -> Result<IntegrityStats, ErrBox> {
+  let http_cache = &global_state.file_fetcher.http_cache;
+  let (mut entries, _) = http_cache.entries()?;
+  entries.sort_by(|a, b| a.url.cmp(&b.url));
+  let mut stats = IntegrityStats::default();
+  for entry in entries {
+    stats.checked += 1;
+    match http_cache.check_integrity(&entry.url)? {
+      Integrity::Valid => {}
+      Integrity::Unknown => stats.unknown += 1,
+      Integrity::Corrupted if repair => {
+        http_cache.remove(&entry.url)?;
+        let specifier = ModuleSpecifier::resolve_url(entry.url.as_str())?;
+        match global_state
+          .file_fetcher
+          .fetch_source_file(&specifier, None, Permissions::allow_all())
+          .await
+        {
+          Ok(_) => {
+            println!("{} {}", colors::green("Repaired"), entry.url);
+            stats.repaired += 1;
+          }
+          Err(err) => {
+            println!(
+              "{} {} (removed from the cache: {})",
+              colors::red("Corrupted"),
+              entry.url,
+              err
+            );
+            stats.corrupted += 1;
+          }
+        }
+      }
+      Integrity::Corrupted => {
+        println!("{} {}", colors::red("Corrupted"), entry.url);
+        stats.corrupted += 1;
+      }
+    }
+  }
+  Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use url::Url;
+
+  fn entry(url: &str, days: u64, now: SystemTime) -> CacheEntry {
+    CacheEntry {
+      url: Url::parse(url).unwrap(),
+      filename: PathBuf::new(),
+      size: 0,
+      last_used: now - Duration::from_secs(days * SECONDS_PER_DAY),
+    }
+  }
+
+  #[test]
+  fn test_should_remove() {
+    let now = SystemTime::now();
+    let used = entry("https://deno.land/x/used.ts", 40, now);
+    let recent = entry("https://deno.land/x/recent.ts", 2, now);
+    let reachable: HashSet<String> =
+      vec![used.url.to_string()].into_iter().collect();
+    let max_age = Some(Duration::from_secs(30 * SECONDS_PER_DAY));
+
+    assert!(!should_remove(&used, None, None, now));
+    assert!(!should_remove(&used, Some(&reachable), None, now));
+    assert!(should_remove(&recent, Some(&reachable), None, now));
+    assert!(should_remove(&used, None, max_age, now));
+    assert!(!should_remove(&recent, None, max_age, now));
+    assert!(!should_remove(&used, Some(&reachable), max_age, now));
+    assert!(!should_remove(&recent, Some(&reachable), max_age, now));
+  }
+
+  #[test]
+  fn test_format_last_used() {
+    let now = SystemTime::now();
+    let today = entry("https://deno.land/x/a.ts", 0, now);
+    assert_eq!(format_last_used(&today, now), "today");
+    let yesterday = entry("https://deno.land/x/a.ts", 1, now);
+    assert_eq!(format_last_used(&yesterday, now), "1 day ago");
+    let old = entry("https://deno.land/x/a.ts", 12, now);
+    assert_eq!(format_last_used(&old, now), "12 days ago");
+  }
+
+  #[test]
+  fn test_emitted_source_path() {
+    let deps = PathBuf::from("/deno_dir/deps");
+    let hash =
+      "2c0a064891b9e3fbe386f5d4a833bce5076543f5404613656042107213a7bbc8";
+    for extension in EMITTED_EXTENSIONS {
+      let filename =
+        PathBuf::from(format!("https/deno.land/{}{}", hash, extension));
+      assert_eq!(
+        emitted_source_path(&filename, &deps),
+        Some(deps.join("https/deno.land").join(hash))
+      );
+    }
+    assert_eq!(
+      emitted_source_path(Path::new("https/deno.land/README"), &deps),
+      None
+    );
+    assert_eq!(
+      emitted_source_path(Path::new("wasm/deno.land/mod.js"), &deps),
+      None
+    );
+    if !cfg!(windows) {
+      assert_eq!(
+        emitted_source_path(Path::new("file/home/deno/mod.ts.js.map"), &deps),
+        Some(PathBuf::from("/home/deno/mod.ts"))
+      );
+    }
+  }
+}
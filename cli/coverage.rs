@@ -0,0 +1,395 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+use crate::colors;
+use crate::inspector::DenoInspector;
+use crate::inspector::InspectorSession;
+use crate::source_maps::SourceMapGetter;
+use deno_core::ErrBox;
+use serde::Deserialize;
+use sourcemap::SourceMap;
+use std::collections::BTreeMap;
+use std::fmt::Write as FmtWrite;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use url::Url;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageRange {
+  /// Start UTF-16 code unit offset (inclusive).
+  pub start_offset: usize,
+  /// End UTF-16 code unit offset (exclusive).
+  pub end_offset: usize,
+  pub count: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionCoverage {
+  pub function_name: String,
+  pub ranges: Vec<CoverageRange>,
+  pub is_block_coverage: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptCoverage {
+  pub script_id: String,
+  pub url: String,
+  pub functions: Vec<FunctionCoverage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TakePreciseCoverageResult {
+  result: Vec<ScriptCoverage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GetScriptSourceResult {
+  script_source: String,
+}
+
+/// Raw coverage reported by V8 for a single script together with the source
+/// code V8 executed.
+#[derive(Debug)]
+pub struct Coverage {
+  pub script_coverage: ScriptCoverage,
+  pub script_source: String,
+}
+
+/// Collects precise (block level) coverage using a local inspector session.
+pub struct CoverageCollector {
+  session: Box<InspectorSession>,
+}
+
+impl CoverageCollector {
+  pub fn new(inspector_ptr: *mut DenoInspector) -> Self {
+    let session = InspectorSession::new(inspector_ptr);
+    Self { session }
+  }
+
+  pub async fn start_collecting(&mut self) -> Result<(), ErrBox> {
+    self.session.post_message("Debugger.enable", None).await?;
+    // Nobody is there to resume execution, so `debugger` statements in the
+    // code under test must not pause it.
+    self
+      .session
+      .post_message("Debugger.setSkipAllPauses", Some(json!({ "skip": true })))
+      .await?;
+    self.session.post_message("Profiler.enable", None).await?;
+    self
+      .session
+      .post_message(
+        "Profiler.startPreciseCoverage",
+        Some(json!({ "callCount": true, "detailed": true })),
+      )
+      .await?;
+    Ok(())
+  }
+
+  pub async fn collect(&mut self) -> Result<Vec<Coverage>, ErrBox> {
+    let result = self
+      .session
+      .post_message("Profiler.takePreciseCoverage", None)
+      .await?;
+    let take_coverage_result: TakePreciseCoverageResult =
+      serde_json::from_value(result)?;
+
+    let mut coverages: Vec<Coverage> = Vec::new();
+    for script_coverage in take_coverage_result.result {
+      let result = self
+        .session
+        .post_message(
+          "Debugger.getScriptSource",
+          Some(json!({ "scriptId": script_coverage.script_id })),
+        )
+        .await?;
+      let get_script_source_result: GetScriptSourceResult =
+        serde_json::from_value(result)?;
+
+      coverages.push(Coverage {
+        script_coverage,
+        script_source: get_script_source_result.script_source,
+      });
+    }
+
+    Ok(coverages)
+  }
+
+  pub async fn stop_collecting(&mut self) -> Result<(), ErrBox> {
+    self
+      .session
+      .post_message("Profiler.stopPreciseCoverage", None)
+      .await?;
+    self.session.post_message("Profiler.disable", None).await?;
+    self.session.post_message("Debugger.disable", None).await?;
+    Ok(())
+  }
+}
+
+/// Keeps only coverage of local modules that are not test modules
+/// themselves.
+pub fn filter_script_coverages(
+  coverages: Vec<Coverage>,
+  test_file_url: Url,
+  test_modules: &[Url],
+) -> Vec<Coverage> {
+  coverages
+    .into_iter()
+    .filter(|e| {
+      let url = match Url::parse(&e.script_coverage.url) {
+        Ok(url) => url,
+        Err(_) => return false,
+      };
+      url.scheme() == "file"
+        && url != test_file_url
+        && !test_modules.contains(&url)
+        && !url.path().ends_with("/__anonymous__")
+    })
+    .collect()
+}
+
+/// Line coverage of a single module, in terms of its original source.
+#[derive(Debug, PartialEq)]
+pub struct FileCoverage {
+  pub url: String,
+  /// Hit count keyed by 1-based line number. Lines that don't contain
+  /// executable code are absent.
+  pub lines: BTreeMap<usize, usize>,
+}
+
+impl FileCoverage {
+  /// Computes line coverage for `coverage`. If a source map is available
+  /// for the script (ie. it was compiled from TypeScript) the lines are
+  /// mapped back to the original source.
+  pub fn new<G: SourceMapGetter>(coverage: &Coverage, getter: &G) -> Self {
+    let url = coverage.script_coverage.url.clone();
+    let compiled_lines = compiled_line_counts(coverage);
+    let maybe_source_map = getter
+      .get_source_map(&url)
+      .and_then(|raw| SourceMap::from_slice(&raw).ok());
+
+    let mut lines = BTreeMap::new();
+    match maybe_source_map {
+      Some(source_map) => {
+        for token in source_map.tokens() {
+          if token.get_source().is_none() {
+            continue;
+          }
+          let compiled_line = token.get_dst_line() as usize;
+          if let Some(Some(count)) = compiled_lines.get(compiled_line) {
+            let line = token.get_src_line() as usize + 1;
+            let entry = lines.entry(line).or_insert(*count);
+            *entry = (*entry).max(*count);
+          }
+        }
+      }
+      None => {
+        for (index, maybe_count) in compiled_lines.iter().enumerate() {
+          if let Some(count) = maybe_count {
+            lines.insert(index + 1, *count);
+          }
+        }
+      }
+    }
+
+    Self { url, lines }
+  }
+
+  pub fn lines_found(&self) -> usize {
+    self.lines.len()
+  }
+
+  pub fn lines_hit(&self) -> usize {
+    self.lines.values().filter(|count| **count > 0).count()
+  }
+
+  pub fn uncovered_lines(&self) -> Vec<usize> {
+    self
+      .lines
+      .iter()
+      .filter(|(_, count)| **count == 0)
+      .map(|(line, _)| *line)
+      .collect()
+  }
+}
+
+/// Returns the hit count of every line of the executed script, `None` for
+/// lines without code. The count of a line is the count of the innermost
+/// range that spans the whole line.
+fn compiled_line_counts(coverage: &Coverage) -> Vec<Option<usize>> {
+  let ranges: Vec<&CoverageRange> = coverage
+    .script_coverage
+    .functions
+    .iter()
+    .flat_map(|f| f.ranges.iter())
+    .collect();
+
+  let mut counts = Vec::new();
+  let mut line_start_offset = 0;
+  for line in coverage.script_source.split('\n') {
+    let line_len = line.encode_utf16().count();
+    let trimmed = line.trim_start();
+    if trimmed.trim_end().is_empty() {
+      counts.push(None);
+    } else {
+      let indent = line_len - trimmed.encode_utf16().count();
+      let start = line_start_offset + indent;
+      let end = line_start_offset + line.trim_end().encode_utf16().count();
+      let count = ranges
+        .iter()
+        .filter(|range| range.start_offset <= start && range.end_offset >= end)
+        .min_by_key(|range| range.end_offset - range.start_offset)
+        .map(|range| range.count);
+      counts.push(count);
+    }
+    // Account for the '\n' separator.
+    line_start_offset += line_len + 1;
+  }
+
+  counts
+}
+
+/// Prints a line coverage summary for every file to stdout.
+pub fn print_summary(file_coverages: &[FileCoverage]) {
+  for file_coverage in file_coverages {
+    let found = file_coverage.lines_found();
+    let hit = file_coverage.lines_hit();
+    let percent = if found == 0 {
+      100.0
+    } else {
+      (hit as f32 / found as f32) * 100.0
+    };
+    let summary = format!("{:.3}% ({}/{})", percent, hit, found);
+    let summary = if percent >= 90.0 {
+      colors::green(&summary).to_string()
+    } else if percent >= 50.0 {
+      colors::yellow(&summary).to_string()
+    } else {
+      colors::red(&summary).to_string()
+    };
+    println!("cover {} ... {}", file_coverage.url, summary);
+
+    let uncovered = format_line_ranges(&file_coverage.uncovered_lines());
+    if !uncovered.is_empty() {
+      println!("{}", colors::gray(&format!("  uncovered: {}", uncovered)));
+    }
+  }
+}
+
+/// Formats a sorted list of line numbers as compact ranges, eg. "1-3, 7".
+fn format_line_ranges(lines: &[usize]) -> String {
+  let mut out = String::new();
+  let mut iter = lines.iter().peekable();
+  while let Some(start) = iter.next() {
+    let mut end = *start;
+    while iter.peek() == Some(&&(end + 1)) {
+      end = *iter.next().unwrap();
+    }
+    if !out.is_empty() {
+      out.push_str(", ");
+    }
+    if end == *start {
+      write!(out, "{}", start).unwrap();
+    } else {
+      write!(out, "{}-{}", start, end).unwrap();
+    }
+  }
+  out
+}
+
+/// Writes the coverage in the lcov tracefile format.
+pub fn write_lcov(
+  file_coverages: &[FileCoverage],
+  out_file: &Path,
+) -> Result<(), ErrBox> {
+  let mut out = BufWriter::new(File::create(out_file)?);
+  for file_coverage in file_coverages {
+    let source_file = Url::parse(&file_coverage.url)
+      .ok()
+      .and_then(|url| url.to_file_path().ok())
+      .map(|path| path.to_string_lossy().to_string())
+      .unwrap_or_else(|| file_coverage.url.clone());
+    writeln!(out, "SF:{}", source_file)?;
+    for (line, count) in &file_coverage.lines {
+      writeln!(out, "DA:{},{}", line, count)?;
+    }
+    writeln!(out, "LH:{}", file_coverage.lines_hit())?;
+    writeln!(out, "LF:{}", file_coverage.lines_found())?;
+    writeln!(out, "end_of_record")?;
+  }
+  out.flush()?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::Value;
+
+  struct MockSourceMapGetter {}
+
+  impl SourceMapGetter for MockSourceMapGetter {
+    fn get_source_map(&self, _file_name: &str) -> Option<Vec<u8>> {
+      None
+    }
+
+    fn get_source_line(
+      &self,
+      _file_name: &str,
+      _line_number: usize,
+    ) -> Option<String> {
+      None
+    }
+  }
+
+  fn range(start_offset: usize, end_offset: usize, count: usize) -> Value {
+    json!({ "startOffset": start_offset, "endOffset": end_offset, "count": count })
+  }
+
+  #[test]
+  fn line_coverage_without_source_map() {
+    let script_source =
+      "function a() {\n  return 1;\n}\n\nif (false) {\n  a();\n}\n";
+    let script_coverage: ScriptCoverage = serde_json::from_value(json!({
+      "scriptId": "1",
+      "url": "file:///a.js",
+      "functions": [
+        {
+          "functionName": "",
+          "ranges": [range(0, 52, 1)],
+          "isBlockCoverage": true,
+        },
+        {
+          "functionName": "a",
+          "ranges": [range(0, 28, 0)],
+          "isBlockCoverage": true,
+        },
+        {
+          "functionName": "",
+          "ranges": [range(41, 51, 0)],
+          "isBlockCoverage": true,
+        },
+      ],
+    }))
+    .unwrap();
+    let coverage = Coverage {
+      script_coverage,
+      script_source: script_source.to_string(),
+    };
+    let file_coverage = FileCoverage::new(&coverage, &MockSourceMapGetter {});
+    assert_eq!(file_coverage.lines_found(), 6);
+    assert_eq!(file_coverage.lines_hit(), 1);
+    assert_eq!(file_coverage.uncovered_lines(), vec![1, 2, 3, 6, 7]);
+  }
+
+  #[test]
+  fn format_ranges() {
+    assert_eq!(format_line_ranges(&[]), "");
+    assert_eq!(format_line_ranges(&[3]), "3");
+    assert_eq!(format_line_ranges(&[1, 2, 3, 7, 9, 10]), "1-3, 7, 9-10");
+  }
+}
@@ -0,0 +1,465 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Code blocks of JSDoc comments and Markdown files, which `deno test --doc`
+//! type-checks and optionally runs as tests.
+//!
+//! Each code block is saved in the file fetcher cache as a module of its own,
+//! next to the file it was found in, so relative imports in examples resolve
+//! the same way as in the documented module.
+
+use crate::file_fetcher::map_file_extension;
+use crate::file_fetcher::SourceFile;
+use crate::file_fetcher::TextDocument;
+use crate::fs as deno_fs;
+use crate::global_state::GlobalState;
+use crate::installer::is_remote_url;
+use crate::msg::MediaType;
+use crate::swc_util;
+use crate::swc_util::AstParser;
+use crate::test_runner::save_test_file;
+use crate::tsc::TargetLib;
+use deno_core::ErrBox;
+use deno_core::ModuleSpecifier;
+use deno_doc as doc;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use swc_common::comments::CommentKind;
+use swc_common::BytePos;
+use url::Url;
+
+/// A fenced code block of a documentation comment or a Markdown file.
+#[derive(Debug, PartialEq)]
+pub struct CodeBlock {
+  pub media_type: MediaType,
+  /// Line of the opening fence, starting at 0.
+  pub start_line: usize,
+  /// Line of the closing fence, starting at 0.
+  pub end_line: usize,
+  pub source: String,
+}
+
+/// A code block saved as a module of its own.
+pub struct DocTest {
+  pub name: String,
+  pub specifier: ModuleSpecifier,
+}
+
+fn is_documented(p: &Path) -> bool {
+  match p.extension().and_then(|ext| ext.to_str()) {
+    Some("ts") | Some("tsx") | Some("js") | Some("mjs") | Some("jsx")
+    | Some("md") => true,
+    _ => false,
+  }
+}
+
+/// Lists the local modules and Markdown files whose code blocks are checked
+/// by `deno test --doc`. Remote modules are skipped.
+pub fn prepare_doc_modules_urls(
+  include: Vec<String>,
+  root_path: &Path,
+) -> Vec<Url> {
+  let mut prepared = vec![];
+
+  for path in include.into_iter().filter(|n| !is_remote_url(n)) {
+    let p = deno_fs::normalize_path(&root_path.join(path));
+    if p.is_dir() {
+      let files = deno_fs::files_in_subtree(p, is_documented);
+      prepared.extend(files.iter().map(|f| Url::from_file_path(f).unwrap()));
+    } else {
+      prepared.push(Url::from_file_path(p).unwrap());
+    }
+  }
+
+  prepared
+}
+
+fn media_type_for_language(language: &str) -> Option<MediaType> {
+  match language {
+    "ts" | "typescript" => Some(MediaType::TypeScript),
+    "tsx" => Some(MediaType::TSX),
+    "js" | "javascript" => Some(MediaType::JavaScript),
+    "jsx" => Some(MediaType::JSX),
+    _ => None,
+  }
+}
+
+fn extension_for_media_type(media_type: MediaType) -> &'static str {
+  match media_type {
+    MediaType::TSX => "tsx",
+    MediaType::JavaScript => "js",
+    MediaType::JSX => "jsx",
+    _ => "ts",
+  }
+}
+
+/// Extracts the ```ts, ```tsx, ```js and ```jsx code blocks of `text`.
+/// Blocks of other languages, and blocks marked as ```ts ignore, are skipped.
+pub fn extract_code_blocks(text: &str) -> Vec<CodeBlock> {
+  let mut blocks = vec![];
+  // Media type (`None` if the block is skipped), indentation and line of the
+  // opening fence of the current block.
+  let mut open_fence: Option<(Option<MediaType>, usize, usize)> = None;
+  let mut lines: Vec<&str> = vec![];
+
+  for (line_number, line) in text.lines().enumerate() {
+    let trimmed = line.trim_start();
+    match open_fence {
+      None => {
+        if let Some(info) = trimmed.strip_prefix("```") {
+          let mut words = info.split_whitespace();
+          let media_type = words.next().and_then(media_type_for_language);
+          let ignored = words.any(|word| word == "ignore");
+          let indent = line.len() - trimmed.len();
+          open_fence = Some((
+            if ignored { None } else { media_type },
+            indent,
+            line_number,
+          ));
+          lines.clear();
+        }
+      }
+      Some((media_type, indent, start_line)) => {
+        if trimmed.starts_with("```") {
+          if let Some(media_type) = media_type {
+            blocks.push(CodeBlock {
+              media_type,
+              start_line,
+              end_line: line_number,
+              source: lines.join("\n") + "\n",
+            });
+          }
+          open_fence = None;
+        } else {
+          let unindented = if line.len() >= indent
+            && line[..indent].chars().all(char::is_whitespace)
+          {
+            &line[indent..]
+          } else {
+            trimmed
+          };
+          lines.push(unindented);
+        }
+      }
+    }
+  }
+
+  blocks
+}
+
+/// Collects the lines of the declarations of `nodes` and their members which
+/// have a JSDoc comment. Lines start at 1.
+fn collect_js_doc_lines(nodes: &[doc::DocNode], lines: &mut Vec<usize>) {
+  for node in nodes {
+    if node.js_doc.is_some() {
+      lines.push(node.location.line);
+    }
+    if let Some(class_def) = &node.class_def {
+      for constructor in &class_def.constructors {
+        if constructor.js_doc.is_some() {
+          lines.push(constructor.location.line);
+        }
+      }
+      for property in &class_def.properties {
+        if property.js_doc.is_some() {
+          lines.push(property.location.line);
+        }
+      }
+      for method in &class_def.methods {
+        if method.js_doc.is_some() {
+          lines.push(method.location.line);
+        }
+      }
+    }
+    if let Some(interface_def) = &node.interface_def {
+      for property in &interface_def.properties {
+        if property.js_doc.is_some() {
+          lines.push(property.location.line);
+        }
+      }
+      for method in &interface_def.methods {
+        if method.js_doc.is_some() {
+          lines.push(method.location.line);
+        }
+      }
+    }
+    if let Some(namespace_def) = &node.namespace_def {
+      collect_js_doc_lines(&namespace_def.elements, lines);
+    }
+  }
+}
+
+/// A `/** */` comment of a module. The leading `*` of each line is removed,
+/// so that line `i` of `text` is line `start_line + i` of the module.
+struct JsDocComment {
+  /// Line of the `/**`, starting at 0.
+  start_line: usize,
+  /// Line of the `*/`, starting at 0.
+  end_line: usize,
+  text: String,
+}
+
+fn strip_js_doc_margin(line: &str) -> &str {
+  let line = line.trim_start();
+  let line = line.strip_prefix('*').unwrap_or(line);
+  line.strip_prefix(' ').unwrap_or(line)
+}
+
+fn line_of(ast_parser: &AstParser, pos: BytePos) -> usize {
+  ast_parser.source_map.lookup_char_pos(pos).line - 1
+}
+
+fn parse_js_doc_comments(
+  url: &Url,
+  media_type: MediaType,
+  source_code: &str,
+) -> Result<Vec<JsDocComment>, ErrBox> {
+  let ast_parser = AstParser::default();
+  ast_parser.parse_module(url.as_str(), media_type, source_code)?;
+  let (leading, _) = ast_parser.comments.clone().take_all();
+  let mut comments: Vec<JsDocComment> = leading
+    .borrow()
+    .values()
+    .flatten()
+    .filter(|comment| {
+      comment.kind == CommentKind::Block && comment.text.starts_with('*')
+    })
+    .map(|comment| JsDocComment {
+      start_line: line_of(&ast_parser, comment.span.lo()),
+      end_line: line_of(&ast_parser, comment.span.hi()),
+      text: comment
+        .text
+        .lines()
+        .map(strip_js_doc_margin)
+        .collect::<Vec<_>>()
+        .join("\n"),
+    })
+    .collect();
+  comments.sort_by_key(|comment| comment.start_line);
+  Ok(comments)
+}
+
+/// Extracts the code blocks of the JSDoc comments of a module. `deno_doc`
+/// finds the documented declarations, and the JSDoc of each one is the last
+/// `/** */` comment ending before it; the lines of the blocks come from the
+/// position of that comment in the module.
+fn extract_js_doc_code_blocks(
+  global_state: &GlobalState,
+  url: &Url,
+  path: &Path,
+  source_code: &str,
+) -> Result<Vec<CodeBlock>, ErrBox> {
+  let media_type = map_file_extension(path);
+  let syntax = if path.to_string_lossy().ends_with(".d.ts") {
+    swc_util::get_syntax_for_dts()
+  } else {
+    swc_util::get_syntax_for_media_type(media_type)
+  };
+  let loader = Box::new(global_state.file_fetcher.clone());
+  let doc_parser = doc::DocParser::new(loader, true);
+  let doc_nodes = doc_parser
+    .parse_source(url.as_str(), syntax, source_code)
+    .map_err(|e| ErrBox::error(e.to_string()))?;
+
+  let mut declaration_lines = vec![];
+  collect_js_doc_lines(&doc_nodes, &mut declaration_lines);
+  let comments = parse_js_doc_comments(url, media_type, source_code)?;
+
+  let mut documenting: Vec<&JsDocComment> = declaration_lines
+    .into_iter()
+    .filter_map(|line| {
+      comments
+        .iter()
+        .filter(|comment| comment.end_line < line)
+        .max_by_key(|comment| comment.end_line)
+    })
+    .collect();
+  documenting.sort_by_key(|comment| comment.start_line);
+  documenting.dedup_by_key(|comment| comment.start_line);
+
+  let mut blocks = vec![];
+  for comment in documenting {
+    for mut block in extract_code_blocks(&comment.text) {
+      block.start_line += comment.start_line;
+      block.end_line += comment.start_line;
+      blocks.push(block);
+    }
+  }
+  Ok(blocks)
+}
+
+/// Extracts the code blocks of `modules` and saves each of them in the file
+/// fetcher cache.
+pub fn collect_doc_tests(
+  global_state: &GlobalState,
+  cwd: &Path,
+  modules: &[Url],
+) -> Result<Vec<DocTest>, ErrBox> {
+  let mut doc_tests = vec![];
+
+  for url in modules {
+    let path = url.to_file_path().unwrap();
+    let source_code = std::fs::read_to_string(&path)?;
+    let blocks = if path.extension().map_or(false, |ext| ext == "md") {
+      extract_code_blocks(&source_code)
+    } else {
+      extract_js_doc_code_blocks(global_state, url, &path, &source_code)?
+    };
+
+    let display_path = path.strip_prefix(cwd).unwrap_or(&path);
+    for block in blocks {
+      let suffix = format!(
+        "${}-{}.{}",
+        block.start_line + 1,
+        block.end_line + 1,
+        extension_for_media_type(block.media_type)
+      );
+      let block_url = Url::parse(&format!("{}{}", url, suffix))?;
+      let specifier = ModuleSpecifier::from(block_url.clone());
+      let source_file = SourceFile {
+        filename: PathBuf::from(format!("{}{}", path.display(), suffix)),
+        url: block_url,
+        types_header: None,
+        media_type: block.media_type,
+        // Blocks without imports or exports would be scripts sharing one
+        // global scope when type-checked, so they're made modules.
+        source_code: TextDocument::new(
+          format!("{}export {{}};\n", block.source).into_bytes(),
+          Some("utf-8"),
+        ),
+      };
+      global_state
+        .file_fetcher
+        .save_source_file_in_cache(&specifier, source_file);
+      doc_tests.push(DocTest {
+        name: format!("{}{}", display_path.display(), suffix),
+        specifier,
+      });
+    }
+  }
+
+  Ok(doc_tests)
+}
+
+/// Type-checks the code blocks without running them, by compiling a module
+/// importing all of them.
+pub async fn check_doc_tests(
+  global_state: &Arc<GlobalState>,
+  cwd: &Path,
+  doc_tests: &[DocTest],
+) -> Result<(), ErrBox> {
+  let mut check_file = "".to_string();
+  for doc_test in doc_tests {
+    check_file.push_str(&format!("import \"{}\";\n", doc_test.specifier));
+  }
+  let check_file_url = Url::from_file_path(cwd.join(".deno.doc.ts"))
+    .expect("Should be valid file url");
+  let check_module = save_test_file(global_state, &check_file_url, check_file);
+  global_state
+    .prepare_module_load(
+      check_module,
+      None,
+      TargetLib::Main,
+      global_state.permissions.clone(),
+      false,
+      global_state.maybe_import_map.clone(),
+    )
+    .await
+}
+
+/// Saves a test module registering a test for each code block, which runs
+/// the block by importing it. Examples are not expected to clean up after
+/// themselves, so the sanitizers are disabled.
+pub fn save_doc_test_module(
+  global_state: &GlobalState,
+  cwd: &Path,
+  doc_tests: &[DocTest],
+) -> Url {
+  let mut test_file = "".to_string();
+  for doc_test in doc_tests {
+    test_file.push_str(&format!(
+      "Deno.test({{ name: {}, sanitizeOps: false, sanitizeResources: false, \
+       async fn() {{ await import(\"{}\"); }} }});\n",
+      serde_json::to_string(&doc_test.name).unwrap(),
+      doc_test.specifier
+    ));
+  }
+  let test_file_url = Url::from_file_path(cwd.join(".deno.doc_test.ts"))
+    .expect("Should be valid file url");
+  save_test_file(global_state, &test_file_url, test_file);
+  test_file_url
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_extract_code_blocks() {
+    let text = r#"# Example
+
+```ts
+import { foo } from "./mod.ts";
+foo();
+```
+
+```sh
+deno run mod.ts
+```
+
+- In a list:
+  ```js
+  console.log("indented");
+  ```
+
+```ts ignore
+notChecked();
+```
+"#;
+    assert_eq!(
+      extract_code_blocks(text),
+      vec![
+        CodeBlock {
+          media_type: MediaType::TypeScript,
+          start_line: 2,
+          end_line: 5,
+          source: "import { foo } from \"./mod.ts\";\nfoo();\n".to_string(),
+        },
+        CodeBlock {
+          media_type: MediaType::JavaScript,
+          start_line: 12,
+          end_line: 14,
+          source: "console.log(\"indented\");\n".to_string(),
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_extract_code_blocks_unclosed() {
+    assert_eq!(extract_code_blocks("```ts\nfoo();\n"), vec![]);
+  }
+
+  #[test]
+  fn test_parse_js_doc_comments() {
+    let source =
+      "// Not JSDoc.\n\n/** Says hi.\n *\n * ```ts\n * hi();\n * ```\n */\n\
+                  export function hi() {}\n";
+    let url = Url::parse("file:///dev/mod.ts").unwrap();
+    let comments =
+      parse_js_doc_comments(&url, MediaType::TypeScript, source).unwrap();
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0].start_line, 2);
+    assert_eq!(comments[0].end_line, 7);
+    assert_eq!(comments[0].text, "Says hi.\n\n```ts\nhi();\n```\n");
+  }
+
+  #[test]
+  fn test_is_documented() {
+    assert!(is_documented(Path::new("mod.ts")));
+    assert!(is_documented(Path::new("README.md")));
+    assert!(is_documented(Path::new("lib.d.ts")));
+    assert!(!is_documented(Path::new("data.json")));
+    assert!(!is_documented(Path::new("Makefile")));
+  }
+}
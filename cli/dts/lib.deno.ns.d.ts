@@ -108,6 +108,21 @@ declare namespace Deno {
      * after the test has exactly the same contents as before the test. Defaults
      * to true. */
     sanitizeResources?: boolean;
+    /** Fail the test if it doesn't finish within the given number of
+     * milliseconds. A test that timed out is abandoned and the next test
+     * starts; synchronous code can't be interrupted. The op and resource
+     * sanitizers of other tests are skipped while an abandoned test is still
+     * running. */
+    timeout?: number;
+    /** UNSTABLE: New API. Requires `--unstable` flag.
+     *
+     * Run the test with a narrower set of permissions, which are restored
+     * once the test finishes. If the test times out, no further tests are
+     * run, since it would keep running with the restored permissions.
+     * Omitted permissions are inherited from the process; asking for
+     * permissions the process doesn't have fails the test with a
+     * `PermissionDenied` error. */
+    permissions?: WorkerDenoOptions["permissions"];
   }
 
   /** Register a test which will be run when `deno test` is used on the command
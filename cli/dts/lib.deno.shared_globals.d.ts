@@ -1238,6 +1238,27 @@ interface ProgressEventInit extends EventInit {
   total?: number;
 }
 
+/** A permission of a worker. `"inherit"` keeps the permission of the main
+ * thread. Lists restrict access to the given paths or hosts. */
+type WorkerPermission<T> = "inherit" | boolean | T[];
+
+interface WorkerDenoOptions {
+  /** Whether the `Deno` namespace is available inside the worker. */
+  namespace?: boolean;
+  /** Permissions of the worker, omitted permissions are inherited. */
+  permissions?: "inherit" | {
+    read?: WorkerPermission<string | URL>;
+    write?: WorkerPermission<string | URL>;
+    /** Hosts in the same format as `--allow-net`, or URLs like
+     * `"https://deno.land"`. */
+    net?: WorkerPermission<string>;
+    env?: "inherit" | boolean;
+    run?: "inherit" | boolean;
+    plugin?: "inherit" | boolean;
+    hrtime?: "inherit" | boolean;
+  };
+}
+
 declare class Worker extends EventTarget {
   onerror?: (e: ErrorEvent) => void;
   onmessage?: (e: MessageEvent) => void;
@@ -1254,9 +1275,25 @@ declare class Worker extends EventTarget {
        * Set to `true` to make `Deno` namespace and all of its methods
        * available to worker thread.
        *
-       * Currently worker inherits permissions from main thread (permissions
-       * given using `--allow-*` flags).
-       * Configurable permissions are on the roadmap to be implemented.
+       * By default worker inherits permissions from main thread (permissions
+       * given using `--allow-*` flags). Passing an object with a
+       * `permissions` field instead of `true` starts the worker with a
+       * narrower set of permissions; asking for permissions the main thread
+       * doesn't have throws a `PermissionDenied` error. This is an unstable
+       * API which requires `--unstable` flag.
+       *
+       * ```ts
+       * const worker = new Worker(
+       *   new URL("deno_worker.ts", import.meta.url).href,
+       *   {
+       *     type: "module",
+       *     deno: {
+       *       namespace: true,
+       *       permissions: { read: ["./data"], net: false },
+       *     },
+       *   }
+       * );
+       * ```
        *
        * Example:
        *
@@ -1292,9 +1329,12 @@ declare class Worker extends EventTarget {
        * hello world2
        *
        */
-      deno?: boolean;
+      deno?: boolean | WorkerDenoOptions;
     },
   );
+  /** Sends a structured clone of `message` to the worker. The ArrayBuffers
+   * in the transfer list are moved to the worker instead of being copied, and
+   * are detached on this side. */
   postMessage(message: any, transfer: ArrayBuffer[]): void;
   postMessage(message: any, options?: PostMessageOptions): void;
   terminate(): void;
@@ -4,6 +4,54 @@
 /// <reference lib="deno.ns" />
 
 declare namespace Deno {
+  /** **UNSTABLE**: New API, yet to be vetted. */
+  export interface BenchDefinition {
+    fn: () => void | Promise<void>;
+    name: string;
+    ignore?: boolean;
+    /** If at least one benchmark has `only` set to true, only run benchmarks
+     * that have `only` set to true. */
+    only?: boolean;
+    /** Number of runs before the measured iterations. Defaults to 10. */
+    warmup?: number;
+    /** Number of measured runs. Defaults to 100. */
+    iterations?: number;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Register a benchmark which will be run when `deno bench` is used on the
+   * command line and the containing module looks like a benchmark module.
+   * `fn` can be async if required. `deno bench` reports the mean, min, max,
+   * 75th and 99th percentile of the duration of an iteration. Iterations are
+   * timed with a high resolution clock, `--allow-hrtime` isn't needed.
+   *
+   * ```ts
+   * Deno.bench({
+   *   name: "example benchmark",
+   *   iterations: 1000,
+   *   fn(): void {
+   *     new URL("https://deno.land/std/testing/asserts.ts");
+   *   },
+   * });
+   * ```
+   */
+  export function bench(b: BenchDefinition): void;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Register a benchmark which will be run when `deno bench` is used on the
+   * command line and the containing module looks like a benchmark module.
+   * `fn` can be async if required.
+   *
+   * ```ts
+   * Deno.bench("parse URL", (): void => {
+   *   new URL("https://deno.land/std/testing/asserts.ts");
+   * });
+   * ```
+   * */
+  export function bench(name: string, fn: () => void | Promise<void>): void;
+
   /**
    * **UNSTABLE**: New API, yet to be vetted.  This API is under consideration to
    * determine if permissions are required to call it.
@@ -900,11 +948,113 @@ declare namespace Deno {
     options: UnixListenOptions & { transport: "unixpacket" },
   ): DatagramConn;
 
+  export type RecordType =
+    | "A"
+    | "AAAA"
+    | "CNAME"
+    | "MX"
+    | "NS"
+    | "SRV"
+    | "TXT";
+
+  export interface ResolveDnsOptions {
+    /** The nameserver to query instead of the ones configured on the
+     * system. `port` defaults to 53. */
+    nameServer?: {
+      ipAddr: string;
+      port?: number;
+    };
+  }
+
+  export interface MXRecord {
+    preference: number;
+    exchange: string;
+  }
+
+  export interface SRVRecord {
+    priority: number;
+    weight: number;
+    port: number;
+    target: string;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Performs a DNS lookup of the given record type. Names in the result are
+   * fully qualified, ie. they end with a dot. TXT records are returned as
+   * the list of their character strings.
+   *
+   * ```ts
+   * const a = await Deno.resolveDns("example.com", "A");
+   * const mx = await Deno.resolveDns("example.com", "MX", {
+   *   nameServer: { ipAddr: "127.0.0.1", port: 5353 },
+   * });
+   * ```
+   *
+   * Requires `allow-net` permission for the queried name, and for the
+   * nameserver if one is given. */
+  export function resolveDns(
+    query: string,
+    recordType: "A" | "AAAA" | "CNAME" | "NS",
+    options?: ResolveDnsOptions,
+  ): Promise<string[]>;
+
+  export function resolveDns(
+    query: string,
+    recordType: "MX",
+    options?: ResolveDnsOptions,
+  ): Promise<MXRecord[]>;
+
+  export function resolveDns(
+    query: string,
+    recordType: "SRV",
+    options?: ResolveDnsOptions,
+  ): Promise<SRVRecord[]>;
+
+  export function resolveDns(
+    query: string,
+    recordType: "TXT",
+    options?: ResolveDnsOptions,
+  ): Promise<string[][]>;
+
+  export function resolveDns(
+    query: string,
+    recordType: RecordType,
+    options?: ResolveDnsOptions,
+  ): Promise<string[] | MXRecord[] | SRVRecord[] | string[][]>;
+
   export interface UnixConnectOptions {
     transport: "unix";
     path: string;
   }
 
+  export interface ConnectOptions {
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * The local address to bind the socket to before connecting. The
+     * hostname defaults to `0.0.0.0` and the port to `0`, ie. an ephemeral
+     * port. */
+    localAddr?: {
+      hostname?: string;
+      port?: number;
+    };
+  }
+
+  export interface Conn {
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Enables or disables the use of Nagle's algorithm (`TCP_NODELAY`).
+     * Disabling it, which is what `setNoDelay()` does, lowers latency for
+     * small writes. */
+    setNoDelay(nodelay?: boolean): void;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Enables or disables TCP keepalive. `interval` is the time in
+     * milliseconds the connection must be idle before keepalive probes are
+     * sent, defaulting to 2 hours. */
+    setKeepAlive(keepalive?: boolean, interval?: number): void;
+  }
+
   /** **UNSTABLE**:  The unix socket transport is unstable as a new API yet to
    * be vetted.  The TCP transport is considered stable.
    *
@@ -930,6 +1080,36 @@ declare namespace Deno {
     hostname?: string;
     /** Server certificate file. */
     certFile?: string;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * PEM encoded CA certificates used to verify the server, in addition to
+     * `certFile` and Mozilla's root certificates. */
+    caCerts?: string[];
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * File containing the PEM encoded certificate chain to present to the
+     * server, for servers that require client authentication. Must be given
+     * together with `privateKeyFile` or `privateKey`. */
+    certChainFile?: string;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * PEM encoded alternative to `certChainFile`. */
+    certChain?: string;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * File containing the PEM encoded private key of the client
+     * certificate. */
+    privateKeyFile?: string;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * PEM encoded alternative to `privateKeyFile`. */
+    privateKey?: string;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Application-Layer Protocol Negotiation (ALPN) protocols to offer to the
+     * server, in order of preference. The protocol chosen by the server is
+     * available as `TlsConn.alpnProtocol`. */
+    alpnProtocols?: string[];
   }
 
   /** **UNSTABLE**: new API, yet to be vetted.
@@ -951,7 +1131,152 @@ declare namespace Deno {
   export function startTls(
     conn: Conn,
     options?: StartTlsOptions,
-  ): Promise<Conn>;
+  ): Promise<TlsConn>;
+
+  export interface ConnectTlsOptions {
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * PEM encoded CA certificates used to verify the server, in addition to
+     * `certFile` and Mozilla's root certificates. */
+    caCerts?: string[];
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * File containing the PEM encoded certificate chain to present to the
+     * server, for servers that require client authentication. Must be given
+     * together with `privateKeyFile` or `privateKey`. */
+    certChainFile?: string;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * PEM encoded alternative to `certChainFile`. */
+    certChain?: string;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * File containing the PEM encoded private key of the client
+     * certificate. */
+    privateKeyFile?: string;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * PEM encoded alternative to `privateKeyFile`. */
+    privateKey?: string;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Application-Layer Protocol Negotiation (ALPN) protocols to offer to the
+     * server, in order of preference. The protocol chosen by the server is
+     * available as `TlsConn.alpnProtocol`. */
+    alpnProtocols?: string[];
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * A connection secured with TLS, as returned by `Deno.connectTls`,
+   * `Deno.startTls` and the `accept()` method of `Deno.listenTls`
+   * listeners. */
+  export interface TlsConn extends Conn {
+    /** The ALPN protocol agreed on during the handshake, or `null` if none
+     * was negotiated. */
+    readonly alpnProtocol: string | null;
+    /** The PEM encoded certificate chain presented by the peer, leaf first.
+     * Empty if the peer didn't present one, which is always the case for
+     * clients of a listener without `clientCaFile` or `clientCaCerts`. */
+    readonly peerCertificates: string[];
+  }
+
+  export interface ListenTlsOptions {
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Application-Layer Protocol Negotiation (ALPN) protocols to announce to
+     * the client, in order of preference. Include `"h2"` to allow
+     * `Deno.serveHttp` to serve HTTP/2 on accepted connections. */
+    alpnProtocols?: string[];
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * PEM encoded alternative to `certFile`. */
+    cert?: string;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * PEM encoded alternative to `keyFile`. */
+    key?: string;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * File containing PEM encoded CA certificates used to verify client
+     * certificates. When set, clients may authenticate with a certificate,
+     * which is then exposed as `TlsConn.peerCertificates`. */
+    clientCaFile?: string;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * PEM encoded alternative to `clientCaFile`. */
+    clientCaCerts?: string[];
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Reject clients that don't present a certificate signed by one of the
+     * `clientCaFile` or `clientCaCerts` authorities. */
+    requireClientCert?: boolean;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Options of `Deno.listenTls` where the certificate and key may be given
+   * PEM encoded with `cert` and `key` instead of `certFile` and `keyFile`. */
+  export interface ListenTlsPemOptions
+    extends Omit<ListenTlsOptions, "certFile" | "keyFile"> {
+    /** Server certificate file. Required unless `cert` is given. */
+    certFile?: string;
+    /** Server public key file. Required unless `key` is given. */
+    keyFile?: string;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Listen announces on the local transport address over TLS (transport layer
+   * security), with the certificate and key given as files or PEM encoded.
+   *
+   * ```ts
+   * const cert = Deno.readTextFileSync("./server.crt");
+   * const key = Deno.readTextFileSync("./server.key");
+   * const lstnr = Deno.listenTls({ port: 443, cert, key });
+   * ```
+   *
+   * Requires `allow-net` permission. */
+  export function listenTls(options: ListenTlsPemOptions): Listener;
+
+  export interface RequestEvent {
+    readonly request: Request;
+    respondWith(r: Response | Promise<Response>): Promise<void>;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * An HTTP connection served by `Deno.serveHttp`. Yields a `RequestEvent`
+   * for each request received on the connection. */
+  export interface HttpConn extends AsyncIterable<RequestEvent> {
+    readonly rid: number;
+
+    /** Resolves to the next request, or to `null` once the connection has
+     * been closed. */
+    nextRequest(): Promise<RequestEvent | null>;
+    close(): void;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Serves HTTP on an accepted connection using Deno's native HTTP server.
+   * HTTP/1.1 connections are kept alive; TLS connections that negotiated
+   * `"h2"` over ALPN are served with HTTP/2. The connection is taken over by
+   * the returned `HttpConn` and can no longer be read from or written to
+   * directly.
+   *
+   * ```ts
+   * const listener = Deno.listen({ port: 4500 });
+   * for await (const conn of listener) {
+   *   (async () => {
+   *     for await (const { request, respondWith } of Deno.serveHttp(conn)) {
+   *       respondWith(new Response("Hello World"));
+   *     }
+   *   })();
+   * }
+   * ```
+   */
+  export function serveHttp(conn: Conn): HttpConn;
 
   /** **UNSTABLE**: The `signo` argument may change to require the Deno.Signal
    * enum.
@@ -1234,6 +1559,16 @@ declare namespace Deno {
      * Requires `allow-read` permission.
      */
     caFile?: string;
+    /** What to do when a server responds with a redirect, unless overridden
+     * by the `redirect` option of a request. Defaults to `"follow"`. */
+    redirect?: RequestRedirect;
+    /** Maximum time, in milliseconds, to establish a connection. */
+    connectTimeout?: number;
+    /** Maximum time, in milliseconds, for a request to complete, including
+     * redirects but not reading the response body. Can be overridden by the
+     * `timeout` option of a request. The request is rejected with
+     * `Deno.errors.TimedOut` once the timeout elapses. */
+    timeout?: number;
   }
 
   /** **UNSTABLE**: New API, yet to be vetted.
@@ -1285,5 +1620,5 @@ declare namespace Deno {
 
 declare function fetch(
   input: Request | URL | string,
-  init?: RequestInit & { client: Deno.HttpClient },
+  init?: RequestInit & { client?: Deno.HttpClient; timeout?: number },
 ): Promise<Response>;
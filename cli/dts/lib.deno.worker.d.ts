@@ -35,7 +35,8 @@ declare const postMessage: typeof __workerMain.postMessage;
 
 declare namespace __workerMain {
   export let onmessage: (e: { data: any }) => void;
-  export function postMessage(data: any): void;
+  export function postMessage(data: any, transfer: ArrayBuffer[]): void;
+  export function postMessage(data: any, options?: PostMessageOptions): void;
   export function close(): void;
   export const name: string;
 }
@@ -18,6 +18,8 @@ use rustyline::error::ReadlineError;
 use std::env;
 use std::error::Error;
 use std::io;
+use trust_dns_resolver::error::ResolveError;
+use trust_dns_resolver::error::ResolveErrorKind;
 
 fn get_dlopen_error_class(error: &dlopen::Error) -> &'static str {
   use dlopen::Error::*;
@@ -128,6 +130,15 @@ fn get_request_error_class(error: &reqwest::Error) -> &'static str {
     .unwrap_or("Http")
 }
 
+fn get_resolve_error_class(error: &ResolveError) -> &'static str {
+  match error.kind() {
+    ResolveErrorKind::NoRecordsFound { .. } => "NotFound",
+    ResolveErrorKind::Io(e) => get_io_error_class(e),
+    ResolveErrorKind::Timeout => "TimedOut",
+    _ => "Error",
+  }
+}
+
 fn get_serde_json_error_class(
   error: &serde_json::error::Error,
 ) -> &'static str {
@@ -204,6 +215,10 @@ pub(crate) fn get_error_class_name(e: &ErrBox) -> &'static str {
       .map(get_request_error_class)
   })
   .or_else(|| e.downcast_ref::<regex::Error>().map(get_regex_error_class))
+  .or_else(|| {
+    e.downcast_ref::<ResolveError>()
+      .map(get_resolve_error_class)
+  })
   .or_else(|| {
     e.downcast_ref::<serde_json::error::Error>()
       .map(get_serde_json_error_class)
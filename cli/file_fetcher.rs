@@ -1,6 +1,8 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
+use crate::auth_tokens::AuthTokens;
 use crate::colors;
+use crate::http_cache;
 use crate::http_cache::HttpCache;
 use crate::http_util;
 use crate::http_util::create_http_client;
@@ -110,6 +112,7 @@ pub struct SourceFileFetcher {
   no_remote: bool,
   cached_only: bool,
   http_client: reqwest::Client,
+  auth_tokens: AuthTokens,
   // This field is public only to expose it's location
   pub http_cache: HttpCache,
 }
@@ -131,6 +134,7 @@ impl SourceFileFetcher {
       no_remote,
       cached_only,
       http_client: create_http_client(ca_file)?,
+      auth_tokens: AuthTokens::from_env(),
     };
 
     Ok(file_fetcher)
@@ -387,7 +391,7 @@ impl SourceFileFetcher {
       return Err(ErrBox::new("Http", "too many redirects"));
     }
 
-    let result = self.http_cache.get(&module_url);
+    let result = self.http_cache.get_with_metadata(&module_url);
     let result = match result {
       Err(e) => {
         if let Some(e) = e.downcast_ref::<std::io::Error>() {
@@ -400,8 +404,8 @@ impl SourceFileFetcher {
       Ok(c) => c,
     };
 
-    let (mut source_file, headers) = result;
-    if let Some(redirect_to) = headers.get("location") {
+    let (mut source_file, metadata) = result;
+    if let Some(redirect_to) = metadata.headers.get("location") {
       let redirect_url = match Url::parse(redirect_to) {
         Ok(redirect_url) => redirect_url,
         Err(url::ParseError::RelativeUrlWithoutBase) => {
@@ -419,6 +423,14 @@ impl SourceFileFetcher {
 
     let mut source_code = Vec::new();
     source_file.read_to_end(&mut source_code)?;
+    if let Err(e) = metadata.verify(module_url, &source_code) {
+      if http_cache::is_corrupted_error(&e) {
+        eprintln!("{} {}, ignoring it", colors::yellow("Warning"), e);
+        return Ok(None);
+      }
+      return Err(e);
+    }
+    let headers = metadata.headers;
 
     let cache_filename = self.http_cache.get_cache_filename(module_url);
     let fake_filepath = PathBuf::from(module_url.path());
@@ -495,9 +507,18 @@ impl SourceFileFetcher {
     };
     let permissions = permissions.clone();
     let http_client = self.http_client.clone();
+    // The token is looked up for each URL, so credentials are never sent to
+    // another host when following a redirect.
+    let maybe_auth_token = self.auth_tokens.get(&module_url);
     // Single pass fetch, either yields code or yields redirect.
     let f = async move {
-      match http_util::fetch_once(http_client, &module_url, module_etag).await?
+      match http_util::fetch_once(
+        http_client,
+        &module_url,
+        module_etag,
+        maybe_auth_token,
+      )
+      .await?
       {
         FetchOnceResult::NotModified => {
           let source_file =
@@ -1401,6 +1422,84 @@ mod tests {
     assert_eq!(&(r2.media_type), &msg::MediaType::JavaScript);
   }
 
+  #[tokio::test]
+  async fn test_fetch_source_corrupted_cache() {
+    let _http_server_guard = test_util::http_server();
+    let (_temp_dir, fetcher) = test_setup();
+    let module_url =
+      Url::parse("http://127.0.0.1:4545/cli/tests/subdir/mt_video_mp2t.t3.ts")
+        .unwrap();
+    let result = fetcher
+      .fetch_remote_source(
+        &module_url,
+        false,
+        false,
+        10,
+        &Permissions::allow_all(),
+      )
+      .await;
+    assert!(result.is_ok());
+
+    // A source which doesn't match its checksum isn't read from the cache.
+    let cache_filename = fetcher.http_cache.get_cache_filename(&module_url);
+    fs::write(&cache_filename, "export const loaded = false;\n").unwrap();
+    let result2 = fetcher.fetch_cached_remote_source(&module_url, 1);
+    assert!(result2.unwrap().is_none());
+
+    let result3 = fetcher
+      .fetch_remote_source(
+        &module_url,
+        true,
+        false,
+        10,
+        &Permissions::allow_all(),
+      )
+      .await;
+    assert!(result3.is_ok());
+    let r3 = result3.unwrap();
+    assert_eq!(r3.source_code.bytes, b"export const loaded = true;\n");
+    assert_eq!(
+      fs::read(&cache_filename).unwrap(),
+      b"export const loaded = true;\n"
+    );
+  }
+
+  #[tokio::test]
+  async fn test_fetch_remote_source_auth_token_redirect() {
+    let _http_server_guard = test_util::http_server();
+    let (_temp_dir, mut fetcher) = test_setup();
+    // Redirects to http://localhost:4545/auth_script.ts, which responds with
+    // 404 unless the token is sent.
+    let module_url =
+      Url::parse("http://localhost:4546/auth_script.ts").unwrap();
+
+    fetcher.auth_tokens =
+      AuthTokens::new(Some("abcdef123456789@localhost:4546".to_string()));
+    let result = fetcher
+      .fetch_remote_source(
+        &module_url,
+        false,
+        false,
+        10,
+        &Permissions::allow_all(),
+      )
+      .await;
+    assert!(result.is_err());
+
+    fetcher.auth_tokens =
+      AuthTokens::new(Some("abcdef123456789@localhost:4545".to_string()));
+    let result = fetcher
+      .fetch_remote_source(
+        &module_url,
+        false,
+        false,
+        10,
+        &Permissions::allow_all(),
+      )
+      .await;
+    assert_eq!(result.unwrap().source_code.bytes, b"console.log('auth')");
+  }
+
   #[tokio::test]
   async fn fetch_remote_source_no_ext() {
     let _g = test_util::http_server();
@@ -0,0 +1,165 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+use crate::colors;
+use deno_core::ErrBox;
+use futures::Future;
+use notify::event::Event as NotifyEvent;
+use notify::event::EventKind;
+use notify::Error as NotifyError;
+use notify::RecommendedWatcher;
+use notify::RecursiveMode;
+use notify::Watcher;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+/// Editors often save a file in several steps (write to a temporary file,
+/// rename, touch metadata); changes that arrive within this interval of each
+/// other are treated as a single change.
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(200);
+
+pub type WatchFuture = Pin<Box<dyn Future<Output = Result<(), ErrBox>>>>;
+pub type TargetFuture =
+  Pin<Box<dyn Future<Output = Result<Vec<PathBuf>, ErrBox>>>>;
+
+async fn error_handler(watch_future: WatchFuture) {
+  let result = watch_future.await;
+  if let Err(err) = result {
+    let msg = format!("{}: {}", colors::red_bold("error"), err.to_string(),);
+    eprintln!("{}", msg);
+  }
+}
+
+/// Runs `operation` and restarts it every time one of the files returned by
+/// `target_resolver` changes.
+///
+/// `target_resolver` is invoked before each (re)start so that files added to
+/// or removed from the watched set (eg. a new import) are picked up. If it
+/// fails, the error is printed and the previously resolved files are watched
+/// instead, so fixing the offending file triggers a restart.
+pub async fn watch_func<F, G>(
+  target_resolver: F,
+  operation: G,
+) -> Result<(), ErrBox>
+where
+  F: Fn() -> TargetFuture,
+  G: Fn() -> WatchFuture,
+{
+  let mut paths: Vec<PathBuf> = vec![];
+  loop {
+    let operation_future: WatchFuture = match target_resolver().await {
+      Ok(resolved_paths) => {
+        paths = resolved_paths;
+        operation()
+      }
+      Err(err) => {
+        if paths.is_empty() {
+          return Err(err);
+        }
+        Box::pin(async move { Err(err) })
+      }
+    };
+
+    let func = error_handler(operation_future);
+    let mut is_file_changed = false;
+    select! {
+      result = file_watcher(&paths) => {
+        result?;
+        is_file_changed = true;
+      },
+      _ = func => {},
+    };
+
+    if !is_file_changed {
+      info!(
+        "{} Process terminated! Restarting on file change...",
+        colors::intense_blue("Watcher")
+      );
+      file_watcher(&paths).await?;
+    }
+
+    info!(
+      "{} File change detected! Restarting!",
+      colors::intense_blue("Watcher")
+    );
+  }
+}
+
+/// Resolves once any of `paths` is created, modified or removed, after no
+/// further change was reported for `DEBOUNCE_INTERVAL`. Returns the paths
+/// which changed during that burst.
+pub async fn file_watcher(paths: &[PathBuf]) -> Result<Vec<PathBuf>, ErrBox> {
+  file_and_dir_watcher(paths, &[]).await
+}
+
+/// Like `file_watcher()`, but also watches the files in the directory trees
+/// `dirs`, including files created in them.
+pub async fn file_and_dir_watcher(
+  paths: &[PathBuf],
+  dirs: &[PathBuf],
+) -> Result<Vec<PathBuf>, ErrBox> {
+  let (sender, mut receiver) = mpsc::channel::<Result<NotifyEvent, ErrBox>>(16);
+  let sender = std::sync::Mutex::new(sender);
+
+  let mut watcher: RecommendedWatcher =
+    Watcher::new_immediate(move |res: Result<NotifyEvent, NotifyError>| {
+      let res2 = res.map_err(ErrBox::from);
+      let mut sender = sender.lock().unwrap();
+      // Ignore result, if send failed it means that watcher was already closed,
+      // but not all messages have been flushed.
+      let _ = sender.try_send(res2);
+    })?;
+
+  // A file which doesn't exist (eg. a dependency that was just deleted) can't
+  // be watched, its directory is watched instead so that restoring the file
+  // counts as a change. Changes to other files of that directory don't.
+  let mut missing_parents = HashSet::new();
+  for path in paths {
+    if path.exists() {
+      watcher.watch(path, RecursiveMode::NonRecursive)?;
+    } else if let Some(parent) = path.parent().filter(|p| p.is_dir()) {
+      if missing_parents.insert(parent) {
+        watcher.watch(parent, RecursiveMode::NonRecursive)?;
+      }
+    }
+  }
+  for dir in dirs {
+    watcher.watch(dir, RecursiveMode::Recursive)?;
+  }
+  let is_watched = |path: &PathBuf| {
+    paths.contains(path)
+      || dirs.iter().any(|dir| path.starts_with(dir))
+      || !path.parent().map_or(false, |p| missing_parents.contains(p))
+  };
+
+  let mut changed_paths = vec![];
+  while let Some(result) = receiver.recv().await {
+    let event = result?;
+    if is_file_changed(&event) && event.paths.iter().any(is_watched) {
+      changed_paths.extend(event.paths);
+      break;
+    }
+  }
+
+  // Collect the rest of the burst.
+  while let Ok(Some(result)) = timeout(DEBOUNCE_INTERVAL, receiver.recv()).await
+  {
+    let event = result?;
+    if is_file_changed(&event) && event.paths.iter().any(is_watched) {
+      changed_paths.extend(event.paths);
+    }
+  }
+
+  Ok(changed_paths)
+}
+
+fn is_file_changed(event: &NotifyEvent) -> bool {
+  matches!(
+    event.kind,
+    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+  )
+}
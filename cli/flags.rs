@@ -7,6 +7,7 @@ use clap::ArgMatches;
 use clap::ArgSettings;
 use clap::SubCommand;
 use log::Level;
+use rand::Rng;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 
@@ -15,12 +16,41 @@ macro_rules! svec {
     ($($x:expr),*) => (vec![$($x.to_string()),*]);
 }
 
+/// The seed of a shuffled test run is passed to V8's `--random-seed`, an
+/// `int` where 0 stands for a random seed.
+const MAX_SHUFFLE_SEED: u64 = i32::MAX as u64;
+
+/// Output format of `deno test`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TestReporterKind {
+  Pretty,
+  Junit,
+  Json,
+}
+
+/// How `deno test --doc` handles the code blocks of documentation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DocTestMode {
+  Check,
+  Run,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum DenoSubcommand {
+  Bench {
+    include: Option<Vec<String>>,
+    filter: Option<String>,
+    json: bool,
+    allow_none: bool,
+  },
   Bundle {
     source_file: String,
     out_file: Option<PathBuf>,
   },
+  Compile {
+    source_file: String,
+    out_file: Option<PathBuf>,
+  },
   Completions {
     buf: Box<[u8]>,
   },
@@ -37,6 +67,11 @@ pub enum DenoSubcommand {
   },
   Cache {
     files: Vec<String>,
+    list: bool,
+    gc: bool,
+    older_than: Option<u64>,
+    check_integrity: bool,
+    repair: bool,
   },
   Fmt {
     check: bool,
@@ -71,6 +106,12 @@ pub enum DenoSubcommand {
     allow_none: bool,
     include: Option<Vec<String>>,
     filter: Option<String>,
+    lcov: Option<PathBuf>,
+    jobs: usize,
+    reporter: TestReporterKind,
+    reporter_output: Option<PathBuf>,
+    doc: Option<DocTestMode>,
+    shuffle: Option<u64>,
   },
   Types,
   Upgrade {
@@ -80,6 +121,11 @@ pub enum DenoSubcommand {
     output: Option<PathBuf>,
     ca_file: Option<String>,
   },
+  Vendor {
+    specifiers: Vec<String>,
+    output: Option<PathBuf>,
+    force: bool,
+  },
 }
 
 impl Default for DenoSubcommand {
@@ -106,11 +152,13 @@ pub struct Flags {
   pub ca_file: Option<String>,
   pub cached_only: bool,
   pub config_path: Option<String>,
+  pub coverage: bool,
   pub ignore: Vec<String>,
   pub import_map_path: Option<String>,
   pub inspect: Option<SocketAddr>,
   pub inspect_brk: Option<SocketAddr>,
   pub lock: Option<String>,
+  pub lock_frozen: bool,
   pub lock_write: bool,
   pub log_level: Option<Level>,
   pub net_allowlist: Vec<String>,
@@ -123,6 +171,7 @@ pub struct Flags {
   pub unstable: bool,
   pub v8_flags: Option<Vec<String>>,
   pub version: bool,
+  pub watch: bool,
   pub write_allowlist: Vec<PathBuf>,
 }
 
@@ -189,6 +238,9 @@ impl Flags {
 }
 
 static ENV_VARIABLES_HELP: &str = "ENVIRONMENT VARIABLES:
+    DENO_AUTH_TOKENS     Semicolon-separated list of credentials and hosts
+                         used when downloading modules from private servers
+                         (e.g. \"abcde12345@deno.land;user:pass@example.com\")
     DENO_DIR             Set the cache directory
     DENO_INSTALL_ROOT    Set deno install's output directory
                          (defaults to $HOME/.deno/bin)
@@ -255,6 +307,8 @@ pub fn flags_from_vec_safe(args: Vec<String>) -> clap::Result<Flags> {
 
   if let Some(m) = matches.subcommand_matches("run") {
     run_parse(&mut flags, m);
+  } else if let Some(m) = matches.subcommand_matches("bench") {
+    bench_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("fmt") {
     fmt_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("types") {
@@ -271,6 +325,8 @@ pub fn flags_from_vec_safe(args: Vec<String>) -> clap::Result<Flags> {
     bundle_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("install") {
     install_parse(&mut flags, m);
+  } else if let Some(m) = matches.subcommand_matches("compile") {
+    compile_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("completions") {
     completions_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("test") {
@@ -281,6 +337,8 @@ pub fn flags_from_vec_safe(args: Vec<String>) -> clap::Result<Flags> {
     doc_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("lint") {
     lint_parse(&mut flags, m);
+  } else if let Some(m) = matches.subcommand_matches("vendor") {
+    vendor_parse(&mut flags, m);
   } else {
     repl_parse(&mut flags, &matches);
   }
@@ -322,8 +380,10 @@ If the flag is set, restrict these messages to errors.",
         )
         .global(true),
     )
+    .subcommand(bench_subcommand())
     .subcommand(bundle_subcommand())
     .subcommand(cache_subcommand())
+    .subcommand(compile_subcommand())
     .subcommand(completions_subcommand())
     .subcommand(doc_subcommand())
     .subcommand(eval_subcommand())
@@ -336,6 +396,7 @@ If the flag is set, restrict these messages to errors.",
     .subcommand(test_subcommand())
     .subcommand(types_subcommand())
     .subcommand(upgrade_subcommand())
+    .subcommand(vendor_subcommand())
     .long_about(DENO_HELP)
     .after_help(ENV_VARIABLES_HELP)
 }
@@ -420,6 +481,24 @@ fn bundle_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   };
 }
 
+fn compile_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  permission_args_parse(flags, matches);
+  ca_file_arg_parse(flags, matches);
+  config_arg_parse(flags, matches);
+  reload_arg_parse(flags, matches);
+  importmap_arg_parse(flags, matches);
+  unstable_arg_parse(flags, matches);
+  lock_args_parse(flags, matches);
+
+  let source_file = matches.value_of("source_file").unwrap().to_string();
+  let out_file = matches.value_of("out_file").map(PathBuf::from);
+
+  flags.subcommand = DenoSubcommand::Compile {
+    source_file,
+    out_file,
+  };
+}
+
 fn completions_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   let shell: &str = matches.value_of("shell").unwrap();
   let mut buf: Vec<u8> = vec![];
@@ -493,12 +572,21 @@ fn cache_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   no_remote_arg_parse(flags, matches);
   ca_file_arg_parse(flags, matches);
   unstable_arg_parse(flags, matches);
-  let files = matches
-    .values_of("file")
-    .unwrap()
-    .map(String::from)
-    .collect();
-  flags.subcommand = DenoSubcommand::Cache { files };
+  let files = match matches.values_of("file") {
+    Some(f) => f.map(String::from).collect(),
+    None => vec![],
+  };
+  let older_than = matches
+    .value_of("older-than")
+    .map(|days| days.parse::<u64>().unwrap());
+  flags.subcommand = DenoSubcommand::Cache {
+    files,
+    list: matches.is_present("list"),
+    gc: matches.is_present("gc"),
+    older_than,
+    check_integrity: matches.is_present("check-integrity"),
+    repair: matches.is_present("repair"),
+  };
 }
 
 fn lock_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
@@ -509,6 +597,9 @@ fn lock_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   if matches.is_present("lock-write") {
     flags.lock_write = true;
   }
+  if matches.is_present("frozen") {
+    flags.lock_frozen = true;
+  }
 }
 
 // Shared between the run and test subcommands. They both take similar options.
@@ -532,23 +623,28 @@ fn run_test_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   if matches.is_present("seed") {
     let seed_string = matches.value_of("seed").unwrap();
     let seed = seed_string.parse::<u64>().unwrap();
-    flags.seed = Some(seed);
+    set_seed(flags, seed);
+  }
+}
 
-    let v8_seed_flag = format!("--random-seed={}", seed);
+fn set_seed(flags: &mut Flags, seed: u64) {
+  flags.seed = Some(seed);
 
-    match flags.v8_flags {
-      Some(ref mut v8_flags) => {
-        v8_flags.push(v8_seed_flag);
-      }
-      None => {
-        flags.v8_flags = Some(svec![v8_seed_flag]);
-      }
+  let v8_seed_flag = format!("--random-seed={}", seed);
+
+  match flags.v8_flags {
+    Some(ref mut v8_flags) => {
+      v8_flags.push(v8_seed_flag);
+    }
+    None => {
+      flags.v8_flags = Some(svec![v8_seed_flag]);
     }
   }
 }
 
 fn run_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   run_test_args_parse(flags, matches);
+  watch_arg_parse(flags, matches);
 
   let mut script: Vec<String> = matches
     .values_of("script_arg")
@@ -567,11 +663,49 @@ fn run_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
 
 fn test_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   run_test_args_parse(flags, matches);
+  watch_arg_parse(flags, matches);
 
   let failfast = matches.is_present("failfast");
   let allow_none = matches.is_present("allow_none");
   let quiet = matches.is_present("quiet");
   let filter = matches.value_of("filter").map(String::from);
+  let coverage = matches.is_present("coverage");
+  let lcov = matches.value_of("lcov").map(PathBuf::from);
+  let jobs = matches
+    .value_of("jobs")
+    .map(|val| val.parse::<usize>().unwrap())
+    .unwrap_or(1);
+  let reporter = match matches.value_of("reporter") {
+    Some("junit") => TestReporterKind::Junit,
+    Some("json") => TestReporterKind::Json,
+    _ => TestReporterKind::Pretty,
+  };
+  let reporter_output = matches.value_of("reporter-output").map(PathBuf::from);
+  let doc = if matches.is_present("doc") {
+    match matches.value_of("doc") {
+      Some("run") => Some(DocTestMode::Run),
+      _ => Some(DocTestMode::Check),
+    }
+  } else {
+    None
+  };
+  // The seed of a shuffled run also seeds Math.random(), so printing it is
+  // enough to replay the run. Without a value, the --seed flag or a random
+  // seed is used.
+  let shuffle = if matches.is_present("shuffle") {
+    let seed = match matches.value_of("shuffle") {
+      Some(seed) => seed.parse::<u64>().unwrap(),
+      None => flags.seed.unwrap_or_else(|| {
+        rand::thread_rng().gen_range(1, MAX_SHUFFLE_SEED + 1)
+      }),
+    };
+    if flags.seed != Some(seed) {
+      set_seed(flags, seed);
+    }
+    Some(seed)
+  } else {
+    None
+  };
   let include = if matches.is_present("files") {
     let files: Vec<String> = matches
       .values_of("files")
@@ -583,12 +717,44 @@ fn test_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     None
   };
 
+  flags.coverage = coverage;
   flags.subcommand = DenoSubcommand::Test {
     fail_fast: failfast,
     quiet,
     include,
     filter,
     allow_none,
+    lcov,
+    jobs,
+    reporter,
+    reporter_output,
+    doc,
+    shuffle,
+  };
+}
+
+fn bench_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  run_test_args_parse(flags, matches);
+
+  let allow_none = matches.is_present("allow_none");
+  let filter = matches.value_of("filter").map(String::from);
+  let json = matches.is_present("json");
+  let include = if matches.is_present("files") {
+    let files: Vec<String> = matches
+      .values_of("files")
+      .unwrap()
+      .map(String::from)
+      .collect();
+    Some(files)
+  } else {
+    None
+  };
+
+  flags.subcommand = DenoSubcommand::Bench {
+    include,
+    filter,
+    json,
+    allow_none,
   };
 }
 
@@ -614,6 +780,25 @@ fn upgrade_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   };
 }
 
+fn vendor_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  reload_arg_parse(flags, matches);
+  ca_file_arg_parse(flags, matches);
+  unstable_arg_parse(flags, matches);
+
+  let specifiers = matches
+    .values_of("specifiers")
+    .unwrap()
+    .map(String::from)
+    .collect();
+  let output = matches.value_of("output").map(PathBuf::from);
+  let force = matches.is_present("force");
+  flags.subcommand = DenoSubcommand::Vendor {
+    specifiers,
+    output,
+    force,
+  };
+}
+
 fn doc_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   reload_arg_parse(flags, matches);
   unstable_arg_parse(flags, matches);
@@ -775,6 +960,7 @@ fn bundle_subcommand<'a, 'b>() -> App<'a, 'b> {
   SubCommand::with_name("bundle")
     .arg(lock_arg())
     .arg(lock_write_arg())
+    .arg(frozen_arg())
     .arg(
       Arg::with_name("source_file")
         .takes_value(true)
@@ -796,6 +982,38 @@ If no output file is given, the output is written to standard output:
     )
 }
 
+fn compile_subcommand<'a, 'b>() -> App<'a, 'b> {
+  permission_args(SubCommand::with_name("compile"))
+    .arg(lock_arg())
+    .arg(lock_write_arg())
+    .arg(frozen_arg())
+    .arg(
+      Arg::with_name("source_file")
+        .takes_value(true)
+        .required(true),
+    )
+    .arg(Arg::with_name("out_file").takes_value(true).required(false))
+    .arg(ca_file_arg())
+    .arg(reload_arg())
+    .arg(importmap_arg())
+    .arg(unstable_arg())
+    .arg(config_arg())
+    .about("Compile the script into a self contained executable")
+    .long_about(
+      "Compiles the given script into a self contained executable.
+  deno compile --unstable --allow-net https://deno.land/std/http/file_server.ts
+  deno compile --unstable https://deno.land/std/examples/colors.ts colors
+
+The module is bundled together with its dependencies and embedded into a copy
+of the deno binary. Permission flags given to this command are recorded and
+applied when the executable is run; arguments passed to the executable are
+forwarded to the script as 'Deno.args'.
+
+If no output file is given, the executable name is inferred from the module
+URL the same way 'deno install' does.",
+    )
+}
+
 fn completions_subcommand<'a, 'b>() -> App<'a, 'b> {
   SubCommand::with_name("completions")
     .setting(AppSettings::DisableHelpSubcommand)
@@ -887,6 +1105,7 @@ fn cache_subcommand<'a, 'b>() -> App<'a, 'b> {
     .arg(reload_arg())
     .arg(lock_arg())
     .arg(lock_write_arg())
+    .arg(frozen_arg())
     .arg(importmap_arg())
     .arg(unstable_arg())
     .arg(config_arg())
@@ -895,10 +1114,45 @@ fn cache_subcommand<'a, 'b>() -> App<'a, 'b> {
     .arg(
       Arg::with_name("file")
         .takes_value(true)
-        .required(true)
+        .required_unless_one(&["list", "gc", "check-integrity"])
         .min_values(1),
     )
     .arg(ca_file_arg())
+    .arg(
+      Arg::with_name("list")
+        .long("list")
+        .help("UNSTABLE: List the cached remote modules")
+        .conflicts_with_all(&["file", "gc"]),
+    )
+    .arg(Arg::with_name("gc").long("gc").help(
+      "UNSTABLE: Remove unused modules and orphaned files from the cache",
+    ))
+    .arg(
+      Arg::with_name("older-than")
+        .long("older-than")
+        .value_name("DAYS")
+        .help("With --gc, remove modules which weren't used for DAYS days")
+        .takes_value(true)
+        .requires("gc")
+        .validator(|val: String| match val.parse::<u64>() {
+          Ok(_) => Ok(()),
+          Err(_) => Err("Days should be a number".to_string()),
+        }),
+    )
+    .arg(
+      Arg::with_name("check-integrity")
+        .long("check-integrity")
+        .help(
+          "UNSTABLE: Check the cached remote modules against their checksums",
+        )
+        .conflicts_with_all(&["file", "list"]),
+    )
+    .arg(
+      Arg::with_name("repair")
+        .long("repair")
+        .help("With --check-integrity, download corrupted modules again")
+        .requires("check-integrity"),
+    )
     .about("Cache the dependencies")
     .long_about(
       "Cache and compile remote dependencies recursively.
@@ -908,7 +1162,20 @@ in the local cache, without running any code:
   deno cache https://deno.land/std/http/file_server.ts
 
 Future runs of this module will trigger no downloads or compilation unless
---reload is specified.",
+--reload is specified.
+
+List the cached remote modules with their size and last use:
+  deno cache --unstable --list
+
+Remove the cached modules which aren't used by the given modules or weren't
+used in the last 30 days, along with orphaned files:
+  deno cache --unstable --gc --older-than=30 main.ts
+
+Without a module or --older-than, --gc only removes orphaned files.
+
+Check the cached remote modules for corruption, downloading corrupted modules
+again:
+  deno cache --unstable --check-integrity --repair",
     )
 }
 
@@ -953,6 +1220,46 @@ update to a different location, use the --output flag
     .arg(ca_file_arg())
 }
 
+fn vendor_subcommand<'a, 'b>() -> App<'a, 'b> {
+  SubCommand::with_name("vendor")
+    .arg(reload_arg())
+    .arg(ca_file_arg())
+    .arg(unstable_arg())
+    .arg(
+      Arg::with_name("output")
+        .long("output")
+        .help("The directory to output the vendored modules to")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("force")
+        .long("force")
+        .short("f")
+        .help("Write to the output directory even if it is not empty"),
+    )
+    .arg(
+      Arg::with_name("specifiers")
+        .takes_value(true)
+        .required(true)
+        .min_values(1),
+    )
+    .about("UNSTABLE: Vendor remote modules into a local directory")
+    .long_about(
+      "UNSTABLE: Vendor remote modules into a local directory.
+
+Download the remote dependencies of the given modules into ./vendor/, along
+with an import map redirecting their URLs to the local copies:
+  deno vendor --unstable main.ts
+
+Run the modules with the import map to use the local copies. The modules
+themselves are not modified:
+  deno run --unstable --importmap vendor/import_map.json main.ts
+
+Use --output to vendor into another directory:
+  deno vendor --unstable --output third_party/ main.ts",
+    )
+}
+
 fn doc_subcommand<'a, 'b>() -> App<'a, 'b> {
   SubCommand::with_name("doc")
     .arg(unstable_arg())
@@ -1133,6 +1440,7 @@ fn run_test_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
     .arg(config_arg())
     .arg(lock_arg())
     .arg(lock_write_arg())
+    .arg(frozen_arg())
     .arg(no_check_arg())
     .arg(no_remote_arg())
     .arg(v8_flags_arg())
@@ -1157,6 +1465,7 @@ fn run_test_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
 
 fn run_subcommand<'a, 'b>() -> App<'a, 'b> {
   run_test_args(SubCommand::with_name("run"))
+    .arg(watch_arg())
     .setting(AppSettings::TrailingVarArg)
     .arg(script_arg())
     .about("Run a program given a filename or url to the module. Use '-' as a filename to read from stdin.")
@@ -1176,6 +1485,9 @@ Grant permission to read from disk and listen to network:
 Grant permission to read allow-listed files from disk:
   deno run --allow-read=/etc https://deno.land/std/http/file_server.ts
 
+Restart the program whenever one of its local modules changes:
+  deno run --watch --unstable main.ts
+
 Deno allows specifying the filename '-' to read the file from stdin.
   curl https://deno.land/std/examples/welcome.ts | target/debug/deno run -",
     )
@@ -1202,6 +1514,86 @@ fn test_subcommand<'a, 'b>() -> App<'a, 'b> {
         .takes_value(true)
         .help("Run tests with this string or pattern in the test name"),
     )
+    .arg(
+      Arg::with_name("coverage")
+        .long("coverage")
+        .requires("unstable")
+        .help("UNSTABLE: Collect coverage information")
+        .takes_value(false),
+    )
+    .arg(
+      Arg::with_name("lcov")
+        .long("lcov")
+        .value_name("FILE")
+        .requires("coverage")
+        .help("Write coverage information in the lcov format to FILE")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("jobs")
+        .long("jobs")
+        .short("j")
+        .value_name("N")
+        .conflicts_with("coverage")
+        .help("Run test modules in N parallel threads")
+        .takes_value(true)
+        .validator(|val: String| match val.parse::<usize>() {
+          Ok(jobs) if jobs > 0 => Ok(()),
+          _ => Err("Jobs should be a positive number".to_string()),
+        }),
+    )
+    .arg(
+      Arg::with_name("reporter")
+        .long("reporter")
+        .value_name("FORMAT")
+        .possible_values(&["pretty", "junit", "json"])
+        .conflicts_with("coverage")
+        .help("Report test results in the given format")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("reporter-output")
+        .long("reporter-output")
+        .value_name("FILE")
+        .requires("reporter")
+        .help("Write the report to FILE instead of stdout")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::with_name("shuffle")
+        .long("shuffle")
+        .value_name("NUMBER")
+        .min_values(0)
+        .max_values(1)
+        .require_equals(true)
+        .help("Shuffle the order of test modules and tests")
+        .takes_value(true)
+        .validator(|val: String| match val.parse::<u64>() {
+          Ok(seed) if (1..=MAX_SHUFFLE_SEED).contains(&seed) => Ok(()),
+          _ => Err(format!(
+            "Shuffle seed should be a number between 1 and {}",
+            MAX_SHUFFLE_SEED
+          )),
+        }),
+    )
+    .arg(
+      Arg::with_name("doc")
+        .long("doc")
+        .value_name("MODE")
+        .min_values(0)
+        .max_values(1)
+        .require_equals(true)
+        .possible_values(&["check", "run"])
+        .help("Type-check code blocks in JSDoc comments and Markdown files")
+        .takes_value(true),
+    )
+    .arg(watch_arg().conflicts_with_all(&[
+      "coverage",
+      "doc",
+      "lcov",
+      "reporter",
+      "reporter-output",
+    ]))
     .arg(
       Arg::with_name("files")
         .help("List of file names to run")
@@ -1218,7 +1610,77 @@ report results to standard output:
 
 Directory arguments are expanded to all contained files matching the glob
 {*_,*.,}test.{js,mjs,ts,jsx,tsx}:
-  deno test src/",
+  deno test src/
+
+Collect line coverage of the modules under test (unstable):
+  deno test --unstable --coverage --lcov=cov.lcov src/
+
+Run test modules in 4 threads, each module in its own isolate:
+  deno test --jobs=4 src/
+
+Write a JUnit XML report, in addition to the results printed to stdout:
+  deno test --reporter=junit --reporter-output=report.xml src/
+
+Print results as JSON lines, one per test:
+  deno test --reporter=json src/
+
+Rerun the test modules affected by changed files (unstable):
+  deno test --unstable --watch src/
+
+Run the tests in a random order, printing the seed which replays it:
+  deno test --shuffle src/
+  deno test --shuffle=42 src/
+
+Type-check the ```ts and ```js code blocks in JSDoc comments of the modules
+and in Markdown files, or also run each of them as a test with --doc=run.
+Blocks marked as ```ts ignore are skipped:
+  deno test --doc src/ README.md
+  deno test --doc=run src/",
+    )
+}
+
+fn bench_subcommand<'a, 'b>() -> App<'a, 'b> {
+  run_test_args(SubCommand::with_name("bench"))
+    .arg(
+      Arg::with_name("allow_none")
+        .long("allow-none")
+        .help("Don't return error code if no benchmark files are found")
+        .takes_value(false),
+    )
+    .arg(
+      Arg::with_name("filter")
+        .set(ArgSettings::AllowLeadingHyphen)
+        .long("filter")
+        .takes_value(true)
+        .help("Run benchmarks with this string or pattern in the name"),
+    )
+    .arg(
+      Arg::with_name("json")
+        .long("json")
+        .help("Output the results in JSON format")
+        .takes_value(false),
+    )
+    .arg(
+      Arg::with_name("files")
+        .help("List of file names to run")
+        .takes_value(true)
+        .multiple(true),
+    )
+    .about("Run benchmarks")
+    .long_about(
+      "Run benchmarks using Deno's built-in benchmark runner.
+
+Evaluate the given modules, run all benchmarks declared with 'Deno.bench()'
+and report the mean, min, max, 75th and 99th percentile of the duration of an
+iteration. Timings are rounded to 2ms unless --allow-hrtime is passed:
+  deno bench --unstable --allow-hrtime src/url_bench.ts
+
+Directory arguments are expanded to all contained files matching the glob
+{*_,*.,}bench.{js,mjs,ts,jsx,tsx}:
+  deno bench --unstable --allow-hrtime src/
+
+Print the results as JSON, e.g. to track regressions:
+  deno bench --unstable --allow-hrtime --json src/ > bench.json",
     )
 }
 
@@ -1234,7 +1696,7 @@ fn lock_arg<'a, 'b>() -> Arg<'a, 'b> {
   Arg::with_name("lock")
     .long("lock")
     .value_name("FILE")
-    .help("Check the specified lock file")
+    .help("Check the specified lock file, adding new entries to it")
     .takes_value(true)
 }
 
@@ -1245,6 +1707,14 @@ fn lock_write_arg<'a, 'b>() -> Arg<'a, 'b> {
     .help("Write lock file. Use with --lock.")
 }
 
+fn frozen_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("frozen")
+    .long("frozen")
+    .requires("lock")
+    .conflicts_with("lock-write")
+    .help("Error out if the lock file is missing entries. Use with --lock.")
+}
+
 fn config_arg<'a, 'b>() -> Arg<'a, 'b> {
   Arg::with_name("config")
     .short("c")
@@ -1409,6 +1879,23 @@ fn v8_flags_arg_parse(flags: &mut Flags, matches: &ArgMatches) {
   }
 }
 
+fn watch_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("watch")
+    .requires("unstable")
+    .long("watch")
+    .help("UNSTABLE: Watch for file changes and restart process automatically")
+    .long_help(
+      "UNSTABLE: Watch for file changes and restart process automatically.
+Only local files from entry point module graph are watched.",
+    )
+}
+
+fn watch_arg_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  if matches.is_present("watch") {
+    flags.watch = true;
+  }
+}
+
 fn no_check_arg<'a, 'b>() -> Arg<'a, 'b> {
   Arg::with_name("no-check")
     .long("no-check")
@@ -1536,6 +2023,32 @@ mod tests {
     );
   }
 
+  #[test]
+  fn vendor() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "vendor",
+      "--unstable",
+      "--output",
+      "third_party",
+      "--force",
+      "main.ts",
+      "worker.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Vendor {
+          specifiers: svec!["main.ts", "worker.ts"],
+          output: Some(PathBuf::from("third_party")),
+          force: true,
+        },
+        unstable: true,
+        ..Flags::default()
+      }
+    );
+  }
+
   #[test]
   fn version() {
     let r = flags_from_vec_safe(svec!["deno", "--version"]);
@@ -1561,12 +2074,35 @@ mod tests {
   }
 
   #[test]
-  fn run_reload_allow_write() {
+  fn run_watch() {
     let r = flags_from_vec_safe(svec![
       "deno",
       "run",
-      "-r",
-      "--allow-write",
+      "--unstable",
+      "--watch",
+      "script.ts"
+    ]);
+    let flags = r.unwrap();
+    assert_eq!(
+      flags,
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        watch: true,
+        unstable: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_reload_allow_write() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "-r",
+      "--allow-write",
       "script.ts"
     ]);
     assert_eq!(
@@ -1878,10 +2414,105 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Cache {
           files: svec!["script.ts"],
+          list: false,
+          gc: false,
+          older_than: None,
+          check_integrity: false,
+          repair: false,
+        },
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn cache_list() {
+    let r = flags_from_vec_safe(svec!["deno", "cache", "--unstable", "--list"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        unstable: true,
+        subcommand: DenoSubcommand::Cache {
+          files: vec![],
+          list: true,
+          gc: false,
+          older_than: None,
+          check_integrity: false,
+          repair: false,
         },
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec_safe(svec!["deno", "cache", "--list", "script.ts"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn cache_gc() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "cache",
+      "--unstable",
+      "--gc",
+      "--older-than=30",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        unstable: true,
+        subcommand: DenoSubcommand::Cache {
+          files: svec!["script.ts"],
+          list: false,
+          gc: true,
+          older_than: Some(30),
+          check_integrity: false,
+          repair: false,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "cache", "--gc"]);
+    assert!(r.is_ok());
+    let r = flags_from_vec_safe(svec!["deno", "cache", "--older-than=30"]);
+    assert!(r.is_err());
+    let r =
+      flags_from_vec_safe(svec!["deno", "cache", "--gc", "--older-than=x"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn cache_check_integrity() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "cache",
+      "--unstable",
+      "--check-integrity",
+      "--repair"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        unstable: true,
+        subcommand: DenoSubcommand::Cache {
+          files: vec![],
+          list: false,
+          gc: false,
+          older_than: None,
+          check_integrity: true,
+          repair: true,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "cache", "--repair"]);
+    assert!(r.is_err());
+    let r =
+      flags_from_vec_safe(svec!["deno", "cache", "--check-integrity", "--gc"]);
+    assert!(r.is_err());
   }
 
   #[test]
@@ -1894,6 +2525,11 @@ mod tests {
         unstable: true,
         subcommand: DenoSubcommand::Cache {
           files: svec!["script.ts"],
+          list: false,
+          gc: false,
+          older_than: None,
+          check_integrity: false,
+          repair: false,
         },
         ..Flags::default()
       }
@@ -2318,6 +2954,32 @@ mod tests {
     );
   }
 
+  #[test]
+  fn compile() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "compile",
+      "--unstable",
+      "--allow-net",
+      "--allow-read=/tmp",
+      "source.ts",
+      "out"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Compile {
+          source_file: "source.ts".to_string(),
+          out_file: Some(PathBuf::from("out")),
+        },
+        unstable: true,
+        allow_net: true,
+        read_allowlist: vec![PathBuf::from("/tmp")],
+        ..Flags::default()
+      }
+    );
+  }
+
   #[test]
   fn bundle_with_reload() {
     let r =
@@ -2368,6 +3030,11 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Cache {
           files: svec!["script.ts"],
+          list: false,
+          gc: false,
+          older_than: None,
+          check_integrity: false,
+          repair: false,
         },
         import_map_path: Some("importmap.json".to_owned()),
         ..Flags::default()
@@ -2384,6 +3051,11 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Cache {
           files: svec!["script.ts", "script_two.ts"],
+          list: false,
+          gc: false,
+          older_than: None,
+          check_integrity: false,
+          repair: false,
         },
         ..Flags::default()
       }
@@ -2817,6 +3489,43 @@ mod tests {
     );
   }
 
+  #[test]
+  fn lock_frozen() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "cache",
+      "--lock=lock.json",
+      "--frozen",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Cache {
+          files: svec!["script.ts"],
+          list: false,
+          gc: false,
+          older_than: None,
+          check_integrity: false,
+          repair: false,
+        },
+        lock_frozen: true,
+        lock: Some("lock.json".to_string()),
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "cache",
+      "--lock=lock.json",
+      "--lock-write",
+      "--frozen",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
+  }
+
   #[test]
   fn test_with_allow_net() {
     let r = flags_from_vec_safe(svec![
@@ -2836,6 +3545,12 @@ mod tests {
           allow_none: true,
           quiet: false,
           include: Some(svec!["dir1/", "dir2/"]),
+          lcov: None,
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          doc: None,
+          shuffle: None,
         },
         allow_net: true,
         ..Flags::default()
@@ -2855,6 +3570,12 @@ mod tests {
           quiet: false,
           filter: Some("foo".to_string()),
           include: Some(svec!["dir1"]),
+          lcov: None,
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          doc: None,
+          shuffle: None,
         },
         ..Flags::default()
       }
@@ -2874,10 +3595,295 @@ mod tests {
           quiet: false,
           filter: Some("- foo".to_string()),
           include: Some(svec!["dir1"]),
+          lcov: None,
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          doc: None,
+          shuffle: None,
+        },
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn test_coverage() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "test",
+      "--unstable",
+      "--coverage",
+      "--lcov",
+      "cov.lcov",
+      "dir1"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: Some(svec!["dir1"]),
+          lcov: Some(PathBuf::from("cov.lcov")),
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          doc: None,
+          shuffle: None,
+        },
+        coverage: true,
+        unstable: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn test_jobs() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--jobs=4", "dir1"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: Some(svec!["dir1"]),
+          lcov: None,
+          jobs: 4,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          doc: None,
+          shuffle: None,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "test", "--jobs=0", "dir1"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn test_reporter() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "test",
+      "--reporter=junit",
+      "--reporter-output=report.xml",
+      "dir1"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: Some(svec!["dir1"]),
+          lcov: None,
+          jobs: 1,
+          reporter: TestReporterKind::Junit,
+          reporter_output: Some(PathBuf::from("report.xml")),
+          doc: None,
+          shuffle: None,
         },
         ..Flags::default()
       }
     );
+
+    let r =
+      flags_from_vec_safe(svec!["deno", "test", "--reporter=tap", "dir1"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn test_doc() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--doc", "README.md"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: Some(svec!["README.md"]),
+          lcov: None,
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          doc: Some(DocTestMode::Check),
+          shuffle: None,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "test", "--doc=run", "dir1"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: Some(svec!["dir1"]),
+          lcov: None,
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          doc: Some(DocTestMode::Run),
+          shuffle: None,
+        },
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn bench_with_flags() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "bench",
+      "--allow-net",
+      "--filter",
+      "url",
+      "--json",
+      "dir1/",
+      "dir2/"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Bench {
+          include: Some(svec!["dir1/", "dir2/"]),
+          filter: Some("url".to_string()),
+          json: true,
+          allow_none: false,
+        },
+        allow_net: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn test_shuffle() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--shuffle=42", "dir1"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: Some(svec!["dir1"]),
+          lcov: None,
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          doc: None,
+          shuffle: Some(42),
+        },
+        seed: Some(42),
+        v8_flags: Some(svec!["--random-seed=42"]),
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "test", "--seed=7", "--shuffle"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: None,
+          lcov: None,
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          doc: None,
+          shuffle: Some(7),
+        },
+        seed: Some(7),
+        v8_flags: Some(svec!["--random-seed=7"]),
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "test", "--shuffle"]).unwrap();
+    match r.subcommand {
+      DenoSubcommand::Test { shuffle, .. } => {
+        let seed = shuffle.unwrap();
+        assert!((1..=MAX_SHUFFLE_SEED).contains(&seed));
+        assert_eq!(r.seed, shuffle);
+      }
+      _ => unreachable!(),
+    }
+
+    let r = flags_from_vec_safe(svec!["deno", "test", "--shuffle=2147483647"]);
+    assert!(r.is_ok());
+    let r = flags_from_vec_safe(svec!["deno", "test", "--shuffle=2147483648"]);
+    assert!(r.is_err());
+    let r = flags_from_vec_safe(svec!["deno", "test", "--shuffle=0"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn test_watch() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--unstable", "--watch"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: None,
+          lcov: None,
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          doc: None,
+          shuffle: None,
+        },
+        unstable: true,
+        watch: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "test", "--watch"]);
+    assert!(r.is_err());
+
+    for arg in &["--reporter=junit", "--lcov=cov.lcov", "--doc"] {
+      let r = flags_from_vec_safe(svec![
+        "deno",
+        "test",
+        "--unstable",
+        "--watch",
+        arg
+      ]);
+      assert!(r.is_err());
+    }
+  }
+
+  #[test]
+  fn test_coverage_requires_unstable() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--coverage", "dir1"]);
+    assert!(r.is_err());
   }
 
   #[test]
@@ -3017,6 +4023,11 @@ mod tests {
       Flags {
         subcommand: DenoSubcommand::Cache {
           files: svec!["script.ts", "script_two.ts"],
+          list: false,
+          gc: false,
+          older_than: None,
+          check_integrity: false,
+          repair: false,
         },
         ca_file: Some("example.crt".to_owned()),
         ..Flags::default()
@@ -37,11 +37,25 @@ pub fn validator(host_and_port: String) -> Result<(), String> {
 
 /// Expands "bare port" paths (eg. ":8080") into full paths with hosts. It
 /// expands to such paths into 3 paths with following hosts: `0.0.0.0:port`,
-/// `127.0.0.1:port` and `localhost:port`.
+/// `127.0.0.1:port` and `localhost:port`. URLs (eg. "https://deno.land") are
+/// reduced to their host and explicit port.
 pub fn parse(paths: Vec<String>) -> clap::Result<Vec<String>> {
+  parse_allowlist(paths).map_err(|message| {
+    clap::Error::with_description(&message, clap::ErrorKind::InvalidValue)
+  })
+}
+
+/// Same as `parse()`, for allowlists which don't come from the command line.
+pub fn parse_allowlist(paths: Vec<String>) -> Result<Vec<String>, String> {
   let mut out: Vec<String> = vec![];
   for host_and_port in paths.iter() {
-    if Url::parse(&format!("deno://{}", host_and_port)).is_ok()
+    if let Some(url) = Url::parse(host_and_port).ok().filter(Url::has_host) {
+      let host = url.host_str().unwrap();
+      out.push(match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_owned(),
+      })
+    } else if Url::parse(&format!("deno://{}", host_and_port)).is_ok()
       || host_and_port.parse::<IpAddr>().is_ok()
     {
       out.push(host_and_port.to_owned())
@@ -51,10 +65,7 @@ pub fn parse(paths: Vec<String>) -> clap::Result<Vec<String>> {
         out.push(format!("{}:{}", host, port.0));
       }
     } else {
-      return Err(clap::Error::with_description(
-        &format!("Bad host:port pair: {}", host_and_port),
-        clap::ErrorKind::InvalidValue,
-      ));
+      return Err(format!("Bad host:port pair: {}", host_and_port));
     }
   }
   Ok(out)
@@ -169,6 +180,18 @@ mod tests {
     assert_eq!(actual, expected);
   }
 
+  #[test]
+  fn parse_net_args_urls() {
+    let entries = svec![
+      "https://deno.land",
+      "http://localhost:4545/path",
+      "ws://[::1]:8080"
+    ];
+    let expected = svec!["deno.land", "localhost:4545", "[::1]:8080"];
+    let actual = parse(entries).unwrap();
+    assert_eq!(actual, expected);
+  }
+
   #[test]
   fn parse_net_args_ipv6() {
     let entries =
@@ -64,7 +64,11 @@ impl GlobalState {
     )?;
 
     let lockfile = if let Some(filename) = &flags.lock {
-      let lockfile = Lockfile::new(filename.to_string(), flags.lock_write)?;
+      let lockfile = Lockfile::new(
+        filename.to_string(),
+        flags.lock_write,
+        flags.lock_frozen,
+      )?;
       Some(Mutex::new(lockfile))
     } else {
       None
@@ -137,13 +141,10 @@ impl GlobalState {
       let mut g = lockfile.lock().unwrap();
 
       for graph_file in &module_graph_files {
-        let check_passed =
-          g.check_or_insert(&graph_file.url, &graph_file.source_code);
-
-        if !check_passed {
+        if let Err(mismatch) = g.check_or_insert(graph_file) {
           eprintln!(
             "Subresource integrity check failed --lock={}\n{}",
-            g.filename, graph_file.url
+            g.filename, mismatch
           );
           std::process::exit(10);
         }
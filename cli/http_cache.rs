@@ -7,14 +7,19 @@
 use crate::fs as deno_fs;
 use crate::http_util::HeadersMap;
 use deno_core::ErrBox;
+use filetime::FileTime;
 use serde::Serialize;
 use serde_derive::Deserialize;
+use std::error::Error;
+use std::fmt;
 use std::fs;
 use std::fs::File;
 use std::io;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::SystemTime;
 use url::Url;
+use walkdir::WalkDir;
 
 /// Turn base of url (scheme, hostname, port) into a valid filename.
 /// This method replaces port part with a special string token (because
@@ -69,6 +74,16 @@ pub fn url_to_filename(url: &Url) -> PathBuf {
   cache_filename
 }
 
+/// A module stored in the cache, as listed by `HttpCache::entries()`.
+pub struct CacheEntry {
+  pub url: Url,
+  pub filename: PathBuf,
+  /// Size of the cached source in bytes.
+  pub size: u64,
+  /// Time the module was last downloaded or read from the cache.
+  pub last_used: SystemTime,
+}
+
 #[derive(Clone)]
 pub struct HttpCache {
   pub location: PathBuf,
@@ -78,6 +93,19 @@ pub struct HttpCache {
 pub struct Metadata {
   pub headers: HeadersMap,
   pub url: String,
+  /// SHA-256 checksum of the cached source. Entries cached by older versions
+  /// don't have one.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub checksum: Option<String>,
+}
+
+/// Result of checking a cached source against the checksum in its metadata.
+#[derive(Debug, PartialEq)]
+pub enum Integrity {
+  Valid,
+  Corrupted,
+  /// The metadata has no checksum to check the source against.
+  Unknown,
 }
 
 impl Metadata {
@@ -99,6 +127,47 @@ impl Metadata {
   pub fn filename(cache_filename: &Path) -> PathBuf {
     cache_filename.with_extension("metadata.json")
   }
+
+  fn check_integrity(&self, source: &[u8]) -> Integrity {
+    match &self.checksum {
+      Some(checksum) if *checksum == crate::checksum::gen(&[source]) => {
+        Integrity::Valid
+      }
+      Some(_) => Integrity::Corrupted,
+      None => Integrity::Unknown,
+    }
+  }
+
+  /// Fails with a `ChecksumMismatch` error if `source`, the cached source of
+  /// `url`, doesn't match the checksum.
+  pub fn verify(&self, url: &Url, source: &[u8]) -> Result<(), ErrBox> {
+    match self.check_integrity(source) {
+      Integrity::Corrupted => Err(ChecksumMismatch(url.clone()).into()),
+      Integrity::Valid | Integrity::Unknown => Ok(()),
+    }
+  }
+}
+
+/// Error for a cached source which doesn't match the checksum in its metadata.
+#[derive(Debug)]
+pub struct ChecksumMismatch(Url);
+
+impl fmt::Display for ChecksumMismatch {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "Cached source of \"{}\" doesn't match its checksum",
+      self.0
+    )
+  }
+}
+
+impl Error for ChecksumMismatch {}
+
+/// Returns whether `err` was returned by `Metadata::verify()` for a source
+/// which doesn't match its checksum.
+pub fn is_corrupted_error(err: &ErrBox) -> bool {
+  err.downcast_ref::<ChecksumMismatch>().is_some()
 }
 
 impl HttpCache {
@@ -136,14 +205,25 @@ impl HttpCache {
   // and validate against ETAG/Last-modified-as headers.
   // ETAG check is currently done in `cli/file_fetcher.rs`.
   pub fn get(&self, url: &Url) -> Result<(File, HeadersMap), ErrBox> {
-    let cache_filename = self.location.join(url_to_filename(url));
-    let metadata_filename = Metadata::filename(&cache_filename);
-    let file = File::open(cache_filename)?;
-    let metadata = fs::read_to_string(metadata_filename)?;
-    let metadata: Metadata = serde_json::from_str(&metadata)?;
+    let (file, metadata) = self.get_with_metadata(url)?;
     Ok((file, metadata.headers))
   }
 
+  /// Like `get()`, but returns the whole metadata of the cached source so
+  /// it can be verified once read.
+  pub fn get_with_metadata(
+    &self,
+    url: &Url,
+  ) -> Result<(File, Metadata), ErrBox> {
+    let cache_filename = self.location.join(url_to_filename(url));
+    let file = File::open(&cache_filename)?;
+    let metadata = Metadata::read(&cache_filename)?;
+    // The access time tells `entries()` when the module was last used. It is
+    // set explicitly since file systems are often mounted with `noatime`.
+    let _ = filetime::set_file_atime(&cache_filename, FileTime::now());
+    Ok((file, metadata))
+  }
+
   pub fn get_metadata(&self, url: &Url) -> Result<Metadata, ErrBox> {
     let cache_filename = self.location.join(url_to_filename(url));
     let metadata_filename = Metadata::filename(&cache_filename);
@@ -170,9 +250,85 @@ impl HttpCache {
     let metadata = Metadata {
       url: url.to_string(),
       headers: headers_map,
+      checksum: Some(crate::checksum::gen(&[content])),
     };
     metadata.write(&cache_filename)
   }
+
+  /// Checks the cached source of `url` against its checksum.
+  pub fn check_integrity(&self, url: &Url) -> Result<Integrity, ErrBox> {
+    let cache_filename = self.location.join(url_to_filename(url));
+    let metadata = Metadata::read(&cache_filename)?;
+    let source = fs::read(&cache_filename)?;
+    Ok(metadata.check_integrity(&source))
+  }
+
+  /// Removes the cached source and metadata of `url`.
+  pub fn remove(&self, url: &Url) -> Result<(), ErrBox> {
+    let cache_filename = self.location.join(url_to_filename(url));
+    fs::remove_file(Metadata::filename(&cache_filename))?;
+    fs::remove_file(cache_filename)?;
+    Ok(())
+  }
+
+  /// Lists the modules stored in the cache, along with orphaned files: sources
+  /// without valid metadata and metadata without a source.
+  pub fn entries(&self) -> Result<(Vec<CacheEntry>, Vec<PathBuf>), ErrBox> {
+    let mut entries = vec![];
+    let mut orphans = vec![];
+    if !self.location.is_dir() {
+      return Ok((entries, orphans));
+    }
+    for dir_entry in WalkDir::new(&self.location) {
+      let dir_entry = dir_entry?;
+      if !dir_entry.file_type().is_file() {
+        continue;
+      }
+      let path = dir_entry.path();
+      let file_name = dir_entry.file_name().to_string_lossy();
+      if let Some(stem) = file_name.strip_suffix(".metadata.json") {
+        if !path.with_file_name(stem).is_file() {
+          orphans.push(path.to_owned());
+        }
+        continue;
+      }
+      match self.read_entry(path) {
+        Some(entry) => entries.push(entry),
+        None => {
+          orphans.push(path.to_owned());
+          let metadata_filename = Metadata::filename(path);
+          if metadata_filename.is_file() {
+            orphans.push(metadata_filename);
+          }
+        }
+      }
+    }
+    Ok((entries, orphans))
+  }
+
+  /// Returns the entry for the source stored at `cache_filename`, if it has
+  /// metadata for a URL which is actually cached there.
+  fn read_entry(&self, cache_filename: &Path) -> Option<CacheEntry> {
+    let metadata = Metadata::read(cache_filename).ok()?;
+    let url = Url::parse(&metadata.url).ok()?;
+    if !matches!(url.scheme(), "http" | "https")
+      || self.get_cache_filename(&url) != cache_filename
+    {
+      return None;
+    }
+    let file_metadata = fs::metadata(cache_filename).ok()?;
+    let modified = file_metadata.modified().ok()?;
+    let last_used = match file_metadata.accessed() {
+      Ok(accessed) if accessed > modified => accessed,
+      _ => modified,
+    };
+    Some(CacheEntry {
+      url,
+      filename: cache_filename.to_owned(),
+      size: file_metadata.len(),
+      last_used,
+    })
+  }
 }
 
 #[cfg(test)]
@@ -238,6 +394,67 @@ mod tests {
     assert_eq!(headers.get("foobar"), None);
   }
 
+  #[test]
+  fn test_check_integrity() {
+    let dir = TempDir::new().unwrap();
+    let cache = HttpCache::new(dir.path());
+    let url = Url::parse("https://deno.land/x/welcome.ts").unwrap();
+    cache.set(&url, HashMap::new(), b"Hello world").unwrap();
+    assert_eq!(cache.check_integrity(&url).unwrap(), Integrity::Valid);
+    let (_, metadata) = cache.get_with_metadata(&url).unwrap();
+    assert!(metadata.verify(&url, b"Hello world").is_ok());
+
+    let cache_filename = cache.get_cache_filename(&url);
+    fs::write(&cache_filename, "Hello w0rld").unwrap();
+    assert_eq!(cache.check_integrity(&url).unwrap(), Integrity::Corrupted);
+    let err = metadata.verify(&url, b"Hello w0rld").unwrap_err();
+    assert!(is_corrupted_error(&err));
+    assert!(!is_corrupted_error(&ErrBox::from(io::Error::new(
+      io::ErrorKind::InvalidData,
+      "stream did not contain valid UTF-8"
+    ))));
+
+    // Entries cached by older versions can't be checked.
+    let mut metadata = Metadata::read(&cache_filename).unwrap();
+    metadata.checksum = None;
+    metadata.write(&cache_filename).unwrap();
+    assert_eq!(cache.check_integrity(&url).unwrap(), Integrity::Unknown);
+    assert!(metadata.verify(&url, b"Hello w0rld").is_ok());
+  }
+
+  #[test]
+  fn test_entries() {
+    let dir = TempDir::new().unwrap();
+    let cache = HttpCache::new(dir.path());
+    let (entries, orphans) = cache.entries().unwrap();
+    assert!(entries.is_empty());
+    assert!(orphans.is_empty());
+
+    let url = Url::parse("https://deno.land/x/welcome.ts").unwrap();
+    cache.set(&url, HashMap::new(), b"Hello world").unwrap();
+    let orphan_url = Url::parse("https://deno.land/x/orphan.ts").unwrap();
+    cache.set(&orphan_url, HashMap::new(), b"orphan").unwrap();
+    let orphan_filename = cache.get_cache_filename(&orphan_url);
+    fs::remove_file(&orphan_filename).unwrap();
+    let stray_filename = cache.location.join("https/deno.land/stray");
+    fs::write(&stray_filename, "stray").unwrap();
+
+    let (entries, mut orphans) = cache.entries().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].url, url);
+    assert_eq!(entries[0].filename, cache.get_cache_filename(&url));
+    assert_eq!(entries[0].size, 11);
+    orphans.sort();
+    assert_eq!(
+      orphans,
+      vec![Metadata::filename(&orphan_filename), stray_filename]
+    );
+
+    cache.remove(&url).unwrap();
+    assert!(cache.get(&url).is_err());
+    assert!(cache.entries().unwrap().0.is_empty());
+  }
+
   #[test]
   fn test_url_to_filename() {
     let test_cases = [
@@ -1,17 +1,22 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
+use crate::auth_tokens::AuthToken;
 use crate::version;
 use bytes::Bytes;
 use deno_core::ErrBox;
 use reqwest::header::HeaderMap;
 use reqwest::header::HeaderValue;
+use reqwest::header::AUTHORIZATION;
 use reqwest::header::IF_NONE_MATCH;
 use reqwest::header::LOCATION;
 use reqwest::header::USER_AGENT;
 use reqwest::redirect::Policy;
 use reqwest::Client;
+use reqwest::ClientBuilder;
 use reqwest::Response;
 use reqwest::StatusCode;
+use std::cell::Cell;
+use std::cell::RefCell;
 use std::cmp::min;
 use std::collections::HashMap;
 use std::fs::File;
@@ -19,14 +24,26 @@ use std::future::Future;
 use std::io;
 use std::io::Read;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::task::Context;
 use std::task::Poll;
+use std::task::Waker;
 use tokio::io::AsyncRead;
 use url::Url;
 
 /// Create new instance of async reqwest::Client. This client supports
 /// proxies and doesn't follow redirects.
 pub fn create_http_client(ca_file: Option<&str>) -> Result<Client, ErrBox> {
+  http_client_builder(ca_file)?
+    .build()
+    .map_err(|_| ErrBox::error("Unable to build http client"))
+}
+
+/// Returns a builder configured like the one used by `create_http_client`,
+/// for callers that need to tweak additional options.
+pub fn http_client_builder(
+  ca_file: Option<&str>,
+) -> Result<ClientBuilder, ErrBox> {
   let mut headers = HeaderMap::new();
   headers.insert(
     USER_AGENT,
@@ -44,10 +61,9 @@ pub fn create_http_client(ca_file: Option<&str>) -> Result<Client, ErrBox> {
     builder = builder.add_root_certificate(cert);
   }
 
-  builder
-    .build()
-    .map_err(|_| ErrBox::error("Unable to build http client"))
+  Ok(builder)
 }
+
 /// Construct the next uri based on base uri and location header fragment
 /// See <https://tools.ietf.org/html/rfc3986#section-4.2>
 fn resolve_url_from_location(base_url: &Url, location: &str) -> Url {
@@ -92,10 +108,13 @@ pub enum FetchOnceResult {
 /// yields Code(ResultPayload).
 /// If redirect occurs, does not follow and
 /// yields Redirect(url).
+/// `maybe_auth_token` is sent as the `Authorization` header of this request
+/// only; callers following a redirect must look up the token of the new URL.
 pub async fn fetch_once(
   client: Client,
   url: &Url,
   cached_etag: Option<String>,
+  maybe_auth_token: Option<AuthToken>,
 ) -> Result<FetchOnceResult, ErrBox> {
   let url = url.clone();
 
@@ -105,6 +124,12 @@ pub async fn fetch_once(
     let if_none_match_val = HeaderValue::from_str(&etag).unwrap();
     request = request.header(IF_NONE_MATCH, if_none_match_val);
   }
+  if let Some(auth_token) = maybe_auth_token {
+    let mut authorization_val = HeaderValue::from_str(&auth_token.to_string())
+      .map_err(|_| ErrBox::error("Invalid auth token in DENO_AUTH_TOKENS"))?;
+    authorization_val.set_sensitive(true);
+    request = request.header(AUTHORIZATION, authorization_val);
+  }
   let response = request.send().await?;
 
   if response.status() == StatusCode::NOT_MODIFIED {
@@ -159,12 +184,54 @@ pub async fn fetch_once(
   Ok(FetchOnceResult::Code(body, headers_))
 }
 
+/// Shared flag used to abort an in-flight fetch. Clones refer to the same
+/// flag, so the request future and the body of its response can both be
+/// canceled through a single handle.
+#[derive(Clone, Default)]
+pub struct CancelHandle {
+  inner: Rc<CancelHandleInner>,
+}
+
+#[derive(Default)]
+struct CancelHandleInner {
+  canceled: Cell<bool>,
+  waker: RefCell<Option<Waker>>,
+}
+
+impl CancelHandle {
+  pub fn cancel(&self) {
+    self.inner.canceled.set(true);
+    if let Some(waker) = self.inner.waker.borrow_mut().take() {
+      waker.wake();
+    }
+  }
+
+  pub fn is_canceled(&self) -> bool {
+    self.inner.canceled.get()
+  }
+
+  /// Resolves once `cancel()` is called. Only the task that polled last is
+  /// woken up.
+  pub fn poll_canceled(&self, cx: &mut Context) -> Poll<()> {
+    if self.is_canceled() {
+      return Poll::Ready(());
+    }
+    self.inner.waker.borrow_mut().replace(cx.waker().clone());
+    Poll::Pending
+  }
+}
+
+pub fn canceled_error() -> io::Error {
+  io::Error::new(io::ErrorKind::Interrupted, "The request was aborted")
+}
+
 /// Wraps reqwest `Response` so that it can be exposed as an `AsyncRead` and integrated
 /// into resources more easily.
 pub struct HttpBody {
   response: Response,
   chunk: Option<Bytes>,
   pos: usize,
+  cancel_handle: Option<CancelHandle>,
 }
 
 impl HttpBody {
@@ -173,8 +240,15 @@ impl HttpBody {
       response: body,
       chunk: None,
       pos: 0,
+      cancel_handle: None,
     }
   }
+
+  /// Reading the body fails once `cancel_handle` is canceled.
+  pub fn with_cancel_handle(mut self, cancel_handle: CancelHandle) -> Self {
+    self.cancel_handle = Some(cancel_handle);
+    self
+  }
 }
 
 impl AsyncRead for HttpBody {
@@ -184,6 +258,11 @@ impl AsyncRead for HttpBody {
     buf: &mut [u8],
   ) -> Poll<Result<usize, io::Error>> {
     let mut inner = self.get_mut();
+    if let Some(cancel_handle) = &inner.cancel_handle {
+      if cancel_handle.poll_canceled(cx).is_ready() {
+        return Poll::Ready(Err(canceled_error()));
+      }
+    }
     if let Some(chunk) = inner.chunk.take() {
       debug!(
         "HttpBody Fake Read buf {} chunk {} pos {}",
@@ -236,6 +315,7 @@ impl AsyncRead for HttpBody {
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::auth_tokens::AuthTokens;
 
   #[tokio::test]
   async fn test_fetch_string() {
@@ -244,7 +324,7 @@ mod tests {
     let url =
       Url::parse("http://127.0.0.1:4545/cli/tests/fixture.json").unwrap();
     let client = create_http_client(None).unwrap();
-    let result = fetch_once(client, &url, None).await;
+    let result = fetch_once(client, &url, None, None).await;
     if let Ok(FetchOnceResult::Code(body, headers)) = result {
       assert!(!body.is_empty());
       assert_eq!(headers.get("content-type").unwrap(), "application/json");
@@ -264,7 +344,7 @@ mod tests {
     )
     .unwrap();
     let client = create_http_client(None).unwrap();
-    let result = fetch_once(client, &url, None).await;
+    let result = fetch_once(client, &url, None, None).await;
     if let Ok(FetchOnceResult::Code(body, headers)) = result {
       assert_eq!(String::from_utf8(body).unwrap(), "console.log('gzip')");
       assert_eq!(
@@ -283,7 +363,7 @@ mod tests {
     let _http_server_guard = test_util::http_server();
     let url = Url::parse("http://127.0.0.1:4545/etag_script.ts").unwrap();
     let client = create_http_client(None).unwrap();
-    let result = fetch_once(client.clone(), &url, None).await;
+    let result = fetch_once(client.clone(), &url, None, None).await;
     if let Ok(FetchOnceResult::Code(body, headers)) = result {
       assert!(!body.is_empty());
       assert_eq!(String::from_utf8(body).unwrap(), "console.log('etag')");
@@ -297,7 +377,8 @@ mod tests {
     }
 
     let res =
-      fetch_once(client, &url, Some("33a64df551425fcc55e".to_string())).await;
+      fetch_once(client, &url, Some("33a64df551425fcc55e".to_string()), None)
+        .await;
     assert_eq!(res.unwrap(), FetchOnceResult::NotModified);
   }
 
@@ -310,7 +391,7 @@ mod tests {
     )
     .unwrap();
     let client = create_http_client(None).unwrap();
-    let result = fetch_once(client, &url, None).await;
+    let result = fetch_once(client, &url, None, None).await;
     if let Ok(FetchOnceResult::Code(body, headers)) = result {
       assert!(!body.is_empty());
       assert_eq!(String::from_utf8(body).unwrap(), "console.log('brotli');");
@@ -325,6 +406,25 @@ mod tests {
     }
   }
 
+  #[tokio::test]
+  async fn test_fetch_with_auth_token() {
+    let _http_server_guard = test_util::http_server();
+    // Relies on external http server. See target/debug/test_server
+    let url = Url::parse("http://127.0.0.1:4545/auth_script.ts").unwrap();
+    let client = create_http_client(None).unwrap();
+    let result = fetch_once(client.clone(), &url, None, None).await;
+    assert!(result.is_err());
+
+    let auth_tokens =
+      AuthTokens::new(Some("abcdef123456789@127.0.0.1:4545".to_string()));
+    let result = fetch_once(client, &url, None, auth_tokens.get(&url)).await;
+    if let Ok(FetchOnceResult::Code(body, _)) = result {
+      assert_eq!(String::from_utf8(body).unwrap(), "console.log('auth')");
+    } else {
+      panic!();
+    }
+  }
+
   #[tokio::test]
   async fn test_fetch_once_with_redirect() {
     let _http_server_guard = test_util::http_server();
@@ -335,7 +435,7 @@ mod tests {
     let target_url =
       Url::parse("http://localhost:4545/cli/tests/fixture.json").unwrap();
     let client = create_http_client(None).unwrap();
-    let result = fetch_once(client, &url, None).await;
+    let result = fetch_once(client, &url, None, None).await;
     if let Ok(FetchOnceResult::Redirect(url, _)) = result {
       assert_eq!(url, target_url);
     } else {
@@ -395,7 +495,7 @@ mod tests {
         .unwrap(),
     ))
     .unwrap();
-    let result = fetch_once(client, &url, None).await;
+    let result = fetch_once(client, &url, None, None).await;
     if let Ok(FetchOnceResult::Code(body, headers)) = result {
       assert!(!body.is_empty());
       assert_eq!(headers.get("content-type").unwrap(), "application/json");
@@ -421,7 +521,7 @@ mod tests {
         .unwrap(),
     ))
     .unwrap();
-    let result = fetch_once(client, &url, None).await;
+    let result = fetch_once(client, &url, None, None).await;
     if let Ok(FetchOnceResult::Code(body, headers)) = result {
       assert_eq!(String::from_utf8(body).unwrap(), "console.log('gzip')");
       assert_eq!(
@@ -446,7 +546,7 @@ mod tests {
         .unwrap(),
     ))
     .unwrap();
-    let result = fetch_once(client.clone(), &url, None).await;
+    let result = fetch_once(client.clone(), &url, None, None).await;
     if let Ok(FetchOnceResult::Code(body, headers)) = result {
       assert!(!body.is_empty());
       assert_eq!(String::from_utf8(body).unwrap(), "console.log('etag')");
@@ -461,7 +561,8 @@ mod tests {
     }
 
     let res =
-      fetch_once(client, &url, Some("33a64df551425fcc55e".to_string())).await;
+      fetch_once(client, &url, Some("33a64df551425fcc55e".to_string()), None)
+        .await;
     assert_eq!(res.unwrap(), FetchOnceResult::NotModified);
   }
 
@@ -480,7 +581,7 @@ mod tests {
         .unwrap(),
     ))
     .unwrap();
-    let result = fetch_once(client, &url, None).await;
+    let result = fetch_once(client, &url, None, None).await;
     if let Ok(FetchOnceResult::Code(body, headers)) = result {
       assert!(!body.is_empty());
       assert_eq!(String::from_utf8(body).unwrap(), "console.log('brotli');");
@@ -501,7 +602,7 @@ mod tests {
     let url_str = "http://127.0.0.1:4545/bad_redirect";
     let url = Url::parse(url_str).unwrap();
     let client = create_http_client(None).unwrap();
-    let result = fetch_once(client, &url, None).await;
+    let result = fetch_once(client, &url, None, None).await;
     assert!(result.is_err());
     let err = result.unwrap_err();
     // Check that the error message contains the original URL
@@ -6,6 +6,7 @@
 
 use core::convert::Infallible as Never; // Alias for the future `!` type.
 use deno_core::v8;
+use deno_core::ErrBox;
 use futures::channel::mpsc;
 use futures::channel::mpsc::UnboundedReceiver;
 use futures::channel::mpsc::UnboundedSender;
@@ -304,7 +305,7 @@ pub struct DenoInspector {
   flags: RefCell<InspectorFlags>,
   waker: Arc<InspectorWaker>,
   _canary_tx: oneshot::Sender<Never>,
-  pub debugger_url: String,
+  pub debugger_url: Option<String>,
 }
 
 impl Deref for DenoInspector {
@@ -372,9 +373,12 @@ impl Future for DenoInspector {
 impl DenoInspector {
   const CONTEXT_GROUP_ID: i32 = 1;
 
+  /// Creates an inspector for `isolate`. If `host` is given the inspector is
+  /// registered with the inspector server so that remote debuggers can
+  /// connect to it, otherwise only local `InspectorSession`s can be used.
   pub fn new(
     isolate: &mut deno_core::JsRuntime,
-    host: SocketAddr,
+    host: Option<SocketAddr>,
   ) -> Box<Self> {
     let core_state_rc = deno_core::JsRuntime::state(isolate);
     let core_state = core_state_rc.borrow();
@@ -385,13 +389,13 @@ impl DenoInspector {
       mpsc::unbounded::<WebSocketProxy>();
     let (canary_tx, canary_rx) = oneshot::channel::<Never>();
 
-    let info = InspectorInfo {
+    let maybe_info = host.map(|host| InspectorInfo {
       host,
       uuid: Uuid::new_v4(),
       thread_name: thread::current().name().map(|n| n.to_owned()),
       new_websocket_tx,
       canary_rx,
-    };
+    });
 
     // Create DenoInspector instance.
     let mut self_ = new_box_with(|self_ptr| {
@@ -411,7 +415,9 @@ impl DenoInspector {
         flags,
         waker,
         _canary_tx: canary_tx,
-        debugger_url: info.get_websocket_debugger_url(),
+        debugger_url: maybe_info
+          .as_ref()
+          .map(|info| info.get_websocket_debugger_url()),
       }
     });
 
@@ -425,7 +431,9 @@ impl DenoInspector {
     self_.context_created(context, Self::CONTEXT_GROUP_ID, context_name);
 
     // Register this inspector with the server thread.
-    InspectorServer::register_inspector(info);
+    if let Some(info) = maybe_info {
+      InspectorServer::register_inspector(info);
+    }
 
     // Poll the session handler so we will get notified whenever there is
     // new_incoming debugger activity.
@@ -797,6 +805,118 @@ impl Future for DenoInspectorSession {
   }
 }
 
+/// An inspector session that lives on the same thread as the isolate. It is
+/// used by Deno itself (eg. to collect code coverage) to talk to V8 using the
+/// DevTools protocol, without going through the websocket server.
+///
+/// The session must be dropped before the `DenoInspector` it was created
+/// from.
+pub struct InspectorSession {
+  v8_channel: v8::inspector::ChannelBase,
+  v8_session: v8::UniqueRef<v8::inspector::V8InspectorSession>,
+  response_tx_map: HashMap<i32, oneshot::Sender<Value>>,
+  next_message_id: i32,
+}
+
+impl Deref for InspectorSession {
+  type Target = v8::inspector::V8InspectorSession;
+  fn deref(&self) -> &Self::Target {
+    &self.v8_session
+  }
+}
+
+impl DerefMut for InspectorSession {
+  fn deref_mut(&mut self) -> &mut Self::Target {
+    &mut self.v8_session
+  }
+}
+
+impl v8::inspector::ChannelImpl for InspectorSession {
+  fn base(&self) -> &v8::inspector::ChannelBase {
+    &self.v8_channel
+  }
+
+  fn base_mut(&mut self) -> &mut v8::inspector::ChannelBase {
+    &mut self.v8_channel
+  }
+
+  fn send_response(
+    &mut self,
+    call_id: i32,
+    message: v8::UniquePtr<v8::inspector::StringBuffer>,
+  ) {
+    let raw_message = message.unwrap().string().to_string();
+    let message = serde_json::from_str(&raw_message).unwrap();
+    if let Some(response_tx) = self.response_tx_map.remove(&call_id) {
+      let _ = response_tx.send(message);
+    }
+  }
+
+  // Only the responses to messages are used, events are dropped.
+  fn send_notification(
+    &mut self,
+    _message: v8::UniquePtr<v8::inspector::StringBuffer>,
+  ) {
+  }
+
+  fn flush_protocol_notifications(&mut self) {}
+}
+
+impl InspectorSession {
+  const CONTEXT_GROUP_ID: i32 = 1;
+
+  pub fn new(inspector_ptr: *mut DenoInspector) -> Box<Self> {
+    new_box_with(move |self_ptr| {
+      let v8_channel = v8::inspector::ChannelBase::new::<Self>();
+      let v8_session = unsafe { &mut *inspector_ptr }.connect(
+        Self::CONTEXT_GROUP_ID,
+        // Todo(piscisaureus): V8Inspector::connect() should require that
+        // the 'v8_channel' argument cannot move.
+        unsafe { &mut *self_ptr },
+        v8::inspector::StringView::empty(),
+      );
+
+      Self {
+        v8_channel,
+        v8_session,
+        response_tx_map: HashMap::new(),
+        next_message_id: 0,
+      }
+    })
+  }
+
+  /// Sends a DevTools protocol message and resolves with the `result` field
+  /// of the response.
+  pub async fn post_message(
+    &mut self,
+    method: &str,
+    params: Option<Value>,
+  ) -> Result<Value, ErrBox> {
+    let id = self.next_message_id;
+    self.next_message_id += 1;
+
+    let (response_tx, response_rx) = oneshot::channel::<Value>();
+    self.response_tx_map.insert(id, response_tx);
+
+    let message = json!({
+      "id": id,
+      "method": method,
+      "params": params,
+    });
+    let raw_message = serde_json::to_string(&message).unwrap();
+    let raw_message = v8::inspector::StringView::from(raw_message.as_bytes());
+    self.v8_session.dispatch_protocol_message(raw_message);
+
+    let response = response_rx
+      .await
+      .map_err(|_| ErrBox::error("Inspector session closed"))?;
+    if let Some(error) = response.get("error") {
+      return Err(ErrBox::error(error.to_string()));
+    }
+    Ok(response.get("result").cloned().unwrap_or(Value::Null))
+  }
+}
+
 fn new_box_with<T>(new_fn: impl FnOnce(*mut T) -> T) -> Box<T> {
   let b = Box::new(MaybeUninit::<T>::uninit());
   let p = Box::into_raw(b) as *mut T;
@@ -1,40 +1,118 @@
-use serde_json::json;
+use crate::module_graph::ModuleGraphFile;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use serde_json::Value;
 use std::collections::BTreeMap;
+use std::io::Error;
+use std::io::ErrorKind;
 use std::io::Result;
 
+const LOCKFILE_VERSION: &str = "2";
+
+/// Contents of a lock file. Version 1 lock files, which only map module
+/// URLs to checksums, are read into `remote`.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct LockfileContent {
+  version: String,
+  /// Checksums of the source code of remote modules.
+  remote: BTreeMap<String, String>,
+  /// Targets of redirected module URLs.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  redirects: BTreeMap<String, String>,
+  /// Targets of the `X-TypeScript-Types` headers of remote modules.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  types: BTreeMap<String, String>,
+}
+
+impl LockfileContent {
+  fn empty() -> Self {
+    LockfileContent {
+      version: LOCKFILE_VERSION.to_string(),
+      ..Default::default()
+    }
+  }
+
+  fn parse(s: &str) -> Result<(Self, bool)> {
+    let value: Value = serde_json::from_str(s)?;
+    match value.get("version") {
+      None => {
+        let remote: BTreeMap<String, String> = serde_json::from_value(value)?;
+        Ok((
+          LockfileContent {
+            remote,
+            ..Self::empty()
+          },
+          true,
+        ))
+      }
+      Some(Value::String(version)) if version == LOCKFILE_VERSION => {
+        Ok((serde_json::from_value(value)?, false))
+      }
+      Some(version) => Err(Error::new(
+        ErrorKind::InvalidData,
+        format!("Unsupported lock file version: {}", version),
+      )),
+    }
+  }
+}
+
 pub struct Lockfile {
   write: bool,
-  map: BTreeMap<String, String>,
+  frozen: bool,
+  /// Whether the lock file needs to be written to disk.
+  has_changed: bool,
+  content: LockfileContent,
   pub filename: String,
 }
 
 impl Lockfile {
-  pub fn new(filename: String, write: bool) -> Result<Lockfile> {
-    debug!("lockfile \"{}\", write: {}", filename, write);
+  /// Reads the lock file. A missing lock file is created, unless `frozen` is
+  /// set. With `write` set, the lock file is regenerated from scratch.
+  pub fn new(filename: String, write: bool, frozen: bool) -> Result<Lockfile> {
+    debug!(
+      "lockfile \"{}\", write: {}, frozen: {}",
+      filename, write, frozen
+    );
 
-    let map = if write {
-      BTreeMap::new()
+    let content = if write {
+      LockfileContent::empty()
     } else {
-      let s = std::fs::read_to_string(&filename)?;
-      serde_json::from_str(&s)?
+      match std::fs::read_to_string(&filename) {
+        Ok(s) => {
+          let (content, is_outdated) = LockfileContent::parse(&s)?;
+          if is_outdated && frozen {
+            return Err(Error::new(
+              ErrorKind::InvalidData,
+              format!(
+                "Lock file {} uses an outdated format, run without --frozen to update it",
+                filename
+              ),
+            ));
+          }
+          content
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound && !frozen => {
+          LockfileContent::empty()
+        }
+        Err(e) => return Err(e),
+      }
     };
 
     Ok(Lockfile {
       write,
-      map,
+      frozen,
+      has_changed: write,
+      content,
       filename,
     })
   }
 
-  // Synchronize lock file to disk - noop if --lock-write file is not specified.
+  /// Synchronizes the lock file to disk, if entries were added to it.
   pub fn write(&self) -> Result<()> {
-    if !self.write {
+    if !self.has_changed {
       return Ok(());
     }
-    // Will perform sort so output is deterministic
-    let map: BTreeMap<_, _> = self.map.iter().collect();
-    let j = json!(map);
-    let s = serde_json::to_string_pretty(&j).unwrap();
+    let s = serde_json::to_string_pretty(&self.content).unwrap();
     let mut f = std::fs::OpenOptions::new()
       .write(true)
       .create(true)
@@ -46,35 +124,282 @@ impl Lockfile {
     Ok(())
   }
 
-  pub fn check_or_insert(&mut self, specifier: &str, code: &str) -> bool {
-    if self.write {
-      // In case --lock-write is specified check always passes
-      self.insert(specifier, code);
-      true
-    } else {
-      self.check(specifier, code)
+  /// Checks the source code, redirect and types header of a module of the
+  /// module graph against the lock file, adding them if they are missing.
+  /// Returns a description of the mismatch if the check failed.
+  pub fn check_or_insert(
+    &mut self,
+    graph_file: &ModuleGraphFile,
+  ) -> std::result::Result<(), String> {
+    if graph_file.url.starts_with("file:") {
+      return Ok(());
     }
+    if let Some(redirect) = &graph_file.redirect {
+      return self.check_or_insert_redirect(&graph_file.url, redirect);
+    }
+    let is_new_module = !self.content.remote.contains_key(&graph_file.url);
+    self.check_or_insert_remote(&graph_file.url, &graph_file.source_code)?;
+    let types = graph_file
+      .type_headers
+      .first()
+      .map(|type_header| type_header.resolved_specifier.to_string());
+    self.check_or_insert_types(&graph_file.url, types.as_deref(), is_new_module)
+  }
+
+  fn check_or_insert_remote(
+    &mut self,
+    specifier: &str,
+    code: &str,
+  ) -> std::result::Result<(), String> {
+    let checksum = crate::checksum::gen(&[code.as_bytes()]);
+    self.check_or_insert_entry(Entry::Remote, specifier, Some(checksum), true)
+  }
+
+  fn check_or_insert_redirect(
+    &mut self,
+    specifier: &str,
+    target: &str,
+  ) -> std::result::Result<(), String> {
+    self.check_or_insert_entry(
+      Entry::Redirect,
+      specifier,
+      Some(target.to_string()),
+      true,
+    )
   }
 
-  /// Checks the given module is included.
-  /// Returns Ok(true) if check passed.
-  fn check(&mut self, specifier: &str, code: &str) -> bool {
-    if specifier.starts_with("file:") {
-      return true;
+  /// Only modules with a types header are recorded in `types`, so a missing
+  /// entry is a mismatch for modules already in the lock file.
+  fn check_or_insert_types(
+    &mut self,
+    specifier: &str,
+    maybe_types: Option<&str>,
+    is_new_module: bool,
+  ) -> std::result::Result<(), String> {
+    self.check_or_insert_entry(
+      Entry::Types,
+      specifier,
+      maybe_types.map(String::from),
+      is_new_module,
+    )
+  }
+
+  fn check_or_insert_entry(
+    &mut self,
+    entry: Entry,
+    specifier: &str,
+    actual: Option<String>,
+    can_insert: bool,
+  ) -> std::result::Result<(), String> {
+    let frozen = self.frozen;
+    let write = self.write;
+    let map = match entry {
+      Entry::Remote => &mut self.content.remote,
+      Entry::Redirect => &mut self.content.redirects,
+      Entry::Types => &mut self.content.types,
+    };
+    let expected = map.get(specifier).cloned();
+    if expected == actual {
+      return Ok(());
     }
-    if let Some(lockfile_checksum) = self.map.get(specifier) {
-      let compiled_checksum = crate::checksum::gen(&[code.as_bytes()]);
-      lockfile_checksum == &compiled_checksum
-    } else {
-      false
+    if write || (expected.is_none() && can_insert) {
+      if let Some(actual) = actual {
+        if frozen {
+          return Err(format!(
+            "{}\n  {} not found in the lock file, which can't be updated with --frozen",
+            specifier,
+            entry.name()
+          ));
+        }
+        map.insert(specifier.to_string(), actual);
+        self.has_changed = true;
+      } else {
+        map.remove(specifier);
+      }
+      return Ok(());
     }
+    Err(format!(
+      "{}\n  expected {}: {}\n  actual {}:   {}",
+      specifier,
+      entry.name(),
+      expected.as_deref().unwrap_or("(none)"),
+      entry.name(),
+      actual.as_deref().unwrap_or("(none)")
+    ))
   }
+}
+
+#[derive(Clone, Copy)]
+enum Entry {
+  Remote,
+  Redirect,
+  Types,
+}
 
-  fn insert(&mut self, specifier: &str, code: &str) {
-    if specifier.starts_with("file:") {
-      return;
+impl Entry {
+  fn name(self) -> &'static str {
+    match self {
+      Entry::Remote => "checksum",
+      Entry::Redirect => "redirect",
+      Entry::Types => "types",
     }
-    let checksum = crate::checksum::gen(&[code.as_bytes()]);
-    self.map.insert(specifier.to_string(), checksum);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  const MOD_URL: &str = "https://deno.land/std@0.67.0/mod.ts";
+  const MOD_CHECKSUM: &str =
+    "1ea1b3b6d1a6bd9b0b1ba52e1de1a2d10c5b2cd0e3c29d0e0e0e0cfe5ffa6a1f";
+
+  fn lockfile_path(t: &TempDir) -> String {
+    t.path().join("lock.json").to_string_lossy().to_string()
+  }
+
+  #[test]
+  fn test_new_missing_lockfile() {
+    let t = TempDir::new().expect("tempdir fail");
+    let filename = lockfile_path(&t);
+    assert!(Lockfile::new(filename.clone(), false, false).is_ok());
+    assert!(Lockfile::new(filename, false, true).is_err());
+  }
+
+  #[test]
+  fn test_insert_and_write() {
+    let t = TempDir::new().expect("tempdir fail");
+    let filename = lockfile_path(&t);
+    let mut lockfile = Lockfile::new(filename.clone(), false, false).unwrap();
+    lockfile
+      .check_or_insert_redirect("https://deno.land/std/mod.ts", MOD_URL)
+      .unwrap();
+    lockfile
+      .check_or_insert_remote(MOD_URL, "export {};\n")
+      .unwrap();
+    lockfile
+      .check_or_insert_types(MOD_URL, Some("https://deno.land/mod.d.ts"), true)
+      .unwrap();
+    lockfile.write().unwrap();
+
+    let s = std::fs::read_to_string(&filename).unwrap();
+    let value: Value = serde_json::from_str(&s).unwrap();
+    assert_eq!(
+      value,
+      json!({
+        "version": "2",
+        "remote": {
+          MOD_URL: crate::checksum::gen(&[b"export {};\n"]),
+        },
+        "redirects": {
+          "https://deno.land/std/mod.ts": MOD_URL,
+        },
+        "types": {
+          MOD_URL: "https://deno.land/mod.d.ts",
+        },
+      })
+    );
+
+    // Checking the same entries again passes without changing the lock file.
+    let mut lockfile = Lockfile::new(filename, false, true).unwrap();
+    lockfile
+      .check_or_insert_redirect("https://deno.land/std/mod.ts", MOD_URL)
+      .unwrap();
+    lockfile
+      .check_or_insert_remote(MOD_URL, "export {};\n")
+      .unwrap();
+    lockfile
+      .check_or_insert_types(MOD_URL, Some("https://deno.land/mod.d.ts"), false)
+      .unwrap();
+    assert!(!lockfile.has_changed);
+  }
+
+  #[test]
+  fn test_check_mismatch() {
+    let t = TempDir::new().expect("tempdir fail");
+    let filename = lockfile_path(&t);
+    std::fs::write(
+      &filename,
+      json!({
+        "version": "2",
+        "remote": { MOD_URL: MOD_CHECKSUM },
+        "redirects": { "https://deno.land/std/mod.ts": MOD_URL },
+        "types": { MOD_URL: "https://deno.land/mod.d.ts" },
+      })
+      .to_string(),
+    )
+    .unwrap();
+    let mut lockfile = Lockfile::new(filename, false, false).unwrap();
+
+    let checksum = crate::checksum::gen(&[b"export {};\n"]);
+    assert_eq!(
+      lockfile.check_or_insert_remote(MOD_URL, "export {};\n"),
+      Err(format!(
+        "{}\n  expected checksum: {}\n  actual checksum:   {}",
+        MOD_URL, MOD_CHECKSUM, checksum
+      ))
+    );
+    assert_eq!(
+      lockfile.check_or_insert_redirect(
+        "https://deno.land/std/mod.ts",
+        "https://deno.land/std@0.68.0/mod.ts"
+      ),
+      Err(
+        "https://deno.land/std/mod.ts\n  \
+         expected redirect: https://deno.land/std@0.67.0/mod.ts\n  \
+         actual redirect:   https://deno.land/std@0.68.0/mod.ts"
+          .to_string()
+      )
+    );
+    assert_eq!(
+      lockfile.check_or_insert_types(MOD_URL, None, false),
+      Err(format!(
+        "{}\n  expected types: https://deno.land/mod.d.ts\n  \
+         actual types:   (none)",
+        MOD_URL
+      ))
+    );
+    assert!(!lockfile.has_changed);
+  }
+
+  #[test]
+  fn test_frozen_missing_entry() {
+    let t = TempDir::new().expect("tempdir fail");
+    let filename = lockfile_path(&t);
+    std::fs::write(&filename, r#"{ "version": "2", "remote": {} }"#).unwrap();
+    let mut lockfile = Lockfile::new(filename, false, true).unwrap();
+    assert_eq!(
+      lockfile.check_or_insert_remote(MOD_URL, "export {};\n"),
+      Err(format!(
+        "{}\n  checksum not found in the lock file, which can't be updated \
+         with --frozen",
+        MOD_URL
+      ))
+    );
+    // Modules without a types header don't need an entry.
+    assert_eq!(lockfile.check_or_insert_types(MOD_URL, None, true), Ok(()));
+  }
+
+  #[test]
+  fn test_read_version_1() {
+    let t = TempDir::new().expect("tempdir fail");
+    let filename = lockfile_path(&t);
+    std::fs::write(&filename, json!({ MOD_URL: MOD_CHECKSUM }).to_string())
+      .unwrap();
+    let lockfile = Lockfile::new(filename.clone(), false, false).unwrap();
+    assert_eq!(
+      lockfile.content.remote.get(MOD_URL).map(String::as_str),
+      Some(MOD_CHECKSUM)
+    );
+    assert!(Lockfile::new(filename, false, true).is_err());
+  }
+
+  #[test]
+  fn test_unsupported_version() {
+    let t = TempDir::new().expect("tempdir fail");
+    let filename = lockfile_path(&t);
+    std::fs::write(&filename, r#"{ "version": "3", "remote": {} }"#).unwrap();
+    assert!(Lockfile::new(filename, false, false).is_err());
   }
 }
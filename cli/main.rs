@@ -22,14 +22,20 @@ extern crate serde_derive;
 extern crate tokio;
 extern crate url;
 
+mod auth_tokens;
+mod bench_runner;
+mod cache_gc;
 mod checksum;
 pub mod colors;
+mod coverage;
 pub mod deno_dir;
 pub mod diagnostics;
 mod diff;
 mod disk_cache;
+mod doc_tests;
 pub mod errors;
 mod file_fetcher;
+mod file_watcher;
 pub mod flags;
 mod flags_allow_net;
 mod fmt;
@@ -56,15 +62,18 @@ mod repl;
 pub mod resolve_addr;
 pub mod signal;
 pub mod source_maps;
+mod standalone;
 mod startup_data;
 pub mod state;
 mod swc_util;
+mod test_reporter;
 mod test_runner;
 mod text_encoding;
 mod tokio_util;
 mod tsc;
 mod tsc_config;
 mod upgrade;
+mod vendor;
 pub mod version;
 mod web_worker;
 pub mod worker;
@@ -84,7 +93,9 @@ use deno_core::ModuleSpecifier;
 use deno_doc as doc;
 use deno_doc::parser::DocFileLoader;
 use flags::DenoSubcommand;
+use flags::DocTestMode;
 use flags::Flags;
+use flags::TestReporterKind;
 use futures::future::FutureExt;
 use futures::Future;
 use log::Level;
@@ -235,6 +246,69 @@ async fn cache_command(flags: Flags, files: Vec<String>) -> Result<(), ErrBox> {
   Ok(())
 }
 
+async fn cache_list_command(flags: Flags) -> Result<(), ErrBox> {
+  if !flags.unstable {
+    exit_unstable("cache --list");
+  }
+  let global_state = GlobalState::new(flags)?;
+  cache_gc::list(&global_state)
+}
+
+async fn cache_gc_command(
+  flags: Flags,
+  files: Vec<String>,
+  older_than: Option<u64>,
+) -> Result<(), ErrBox> {
+  if !flags.unstable {
+    exit_unstable("cache --gc");
+  }
+  let global_state = GlobalState::new(flags)?;
+  let roots = files
+    .iter()
+    .map(|f| ModuleSpecifier::resolve_url_or_path(f))
+    .collect::<Result<Vec<_>, _>>()?;
+  let stats = cache_gc::gc(&global_state, roots, older_than).await?;
+  info!(
+    "{} {} cached modules and {} orphaned files, freeing {}",
+    colors::green("Removed"),
+    stats.modules,
+    stats.orphans,
+    info::human_size(stats.size as f64)
+  );
+  Ok(())
+}
+
+async fn cache_check_integrity_command(
+  flags: Flags,
+  repair: bool,
+) -> Result<(), ErrBox> {
+  if !flags.unstable {
+    exit_unstable("cache --check-integrity");
+  }
+  let global_state = GlobalState::new(flags)?;
+  let stats = cache_gc::check_integrity(&global_state, repair).await?;
+  info!(
+    "{} {} cached modules: {} corrupted, {} repaired, {} without a checksum",
+    colors::green("Checked"),
+    stats.checked,
+    stats.corrupted,
+    stats.repaired,
+    stats.unknown
+  );
+  if stats.corrupted > 0 {
+    let hint = if repair {
+      "they couldn't be downloaded again"
+    } else {
+      "run with --repair to download them again"
+    };
+    return Err(ErrBox::error(format!(
+      "{} cached modules are corrupted, {}",
+      stats.corrupted, hint
+    )));
+  }
+  Ok(())
+}
+
 async fn eval_command(
   flags: Flags,
   code: String,
@@ -318,6 +392,57 @@ async fn bundle_command(
   Ok(())
 }
 
+async fn compile_command(
+  flags: Flags,
+  source_file: String,
+  out_file: Option<PathBuf>,
+) -> Result<(), ErrBox> {
+  if !flags.unstable {
+    exit_unstable("compile");
+  }
+
+  let module_specifier = ModuleSpecifier::resolve_url_or_path(&source_file)?;
+  let out_file = out_file.or_else(|| {
+    installer::infer_name_from_url(module_specifier.as_url()).map(PathBuf::from)
+  });
+  let out_file = match out_file {
+    Some(out_file) => out_file,
+    None => return Err(ErrBox::error(
+      "An executable name was not provided. One could not be inferred from the URL. Aborting.",
+    )),
+  };
+  let out_file = if cfg!(windows) && out_file.extension().is_none() {
+    out_file.with_extension("exe")
+  } else {
+    out_file
+  };
+
+  let global_state = GlobalState::new(flags.clone())?;
+
+  info!(
+    "{} {}",
+    colors::green("Bundle"),
+    module_specifier.to_string()
+  );
+  let bundle_str = global_state
+    .ts_compiler
+    .bundle(&global_state, module_specifier)
+    .await?;
+
+  info!(
+    "{} {}",
+    colors::green("Compile"),
+    out_file.to_string_lossy()
+  );
+  standalone::create_standalone_binary(
+    bundle_str.into_bytes(),
+    &flags,
+    out_file,
+  )?;
+
+  Ok(())
+}
+
 async fn doc_command(
   flags: Flags,
   source_file: Option<String>,
@@ -436,7 +561,71 @@ async fn run_repl(flags: Flags) -> Result<(), ErrBox> {
   }
 }
 
+async fn run_with_watch(flags: Flags, script: String) -> Result<(), ErrBox> {
+  let main_module = ModuleSpecifier::resolve_url_or_path(&script)?;
+
+  let target_flags = flags.clone();
+  let target_module = main_module.clone();
+  let target_resolver = move || {
+    // The graph is rebuilt on every restart so that newly added imports
+    // are watched as well.
+    let flags = target_flags.clone();
+    let main_module = target_module.clone();
+    async move {
+      let global_state = GlobalState::new(flags)?;
+      let mut module_graph_loader = module_graph::ModuleGraphLoader::new(
+        global_state.file_fetcher.clone(),
+        global_state.maybe_import_map.clone(),
+        Permissions::allow_all(),
+        false,
+        false,
+      );
+      module_graph_loader.add_to_graph(&main_module, None).await?;
+      let module_graph = module_graph_loader.get_graph();
+
+      // Find all local files in graph
+      let paths_to_watch: Vec<PathBuf> = module_graph
+        .values()
+        .map(|f| Url::parse(&f.url).unwrap())
+        .filter(|url| url.scheme() == "file")
+        .map(|url| url.to_file_path().unwrap())
+        .collect();
+      Ok(paths_to_watch)
+    }
+    .boxed_local()
+  };
+
+  let operation = move || {
+    let flags = flags.clone();
+    let main_module = main_module.clone();
+    async move {
+      // GlobalState must be created on each restart, otherwise the file
+      // fetcher would serve stale source files from its in-memory cache.
+      let global_state = GlobalState::new(flags)?;
+      let mut worker = MainWorker::create(&global_state, main_module.clone())?;
+      debug!("main_module {}", main_module);
+      worker.execute_module(&main_module).await?;
+      worker.execute("window.dispatchEvent(new Event('load'))")?;
+      (&mut *worker).await?;
+      worker.execute("window.dispatchEvent(new Event('unload'))")?;
+      Ok(())
+    }
+    .boxed_local()
+  };
+
+  file_watcher::watch_func(target_resolver, operation).await
+}
+
 async fn run_command(flags: Flags, script: String) -> Result<(), ErrBox> {
+  if flags.watch && script == "-" {
+    return Err(ErrBox::error(
+      "--watch cannot be used when reading the script from stdin",
+    ));
+  }
+  if flags.watch {
+    return run_with_watch(flags, script).await;
+  }
+
   let global_state = GlobalState::new(flags.clone())?;
   let main_module = if script != "-" {
     ModuleSpecifier::resolve_url_or_path(&script).unwrap()
@@ -472,6 +661,86 @@ async fn run_command(flags: Flags, script: String) -> Result<(), ErrBox> {
   Ok(())
 }
 
+async fn bench_command(
+  flags: Flags,
+  include: Option<Vec<String>>,
+  filter: Option<String>,
+  json: bool,
+  allow_none: bool,
+) -> Result<(), ErrBox> {
+  if !flags.unstable {
+    exit_unstable("bench");
+  }
+  let global_state = GlobalState::new(flags)?;
+  let cwd = std::env::current_dir().expect("No current directory");
+  let include = include.unwrap_or_else(|| vec![".".to_string()]);
+  let bench_modules = bench_runner::prepare_bench_modules_urls(include, &cwd)?;
+
+  if bench_modules.is_empty() {
+    println!("No matching benchmark modules found");
+    if !allow_none {
+      std::process::exit(1);
+    }
+    return Ok(());
+  }
+
+  let bench_file_url = Url::from_file_path(cwd.join(".deno.bench.ts"))
+    .expect("Should be valid file url");
+  let bench_file = bench_runner::render_bench_file(bench_modules, filter, json);
+  let main_module =
+    test_runner::save_test_file(&global_state, &bench_file_url, bench_file);
+  let mut worker = MainWorker::create(&global_state, main_module.clone())?;
+  ops::timers::init_bench_timer(&worker.state);
+
+  worker.execute_module(&main_module).await?;
+  worker.execute("window.dispatchEvent(new Event('load'))")?;
+  (&mut *worker).await?;
+  worker.execute("window.dispatchEvent(new Event('unload'))")?;
+  Ok(())
+}
+
+async fn vendor_command(
+  flags: Flags,
+  specifiers: Vec<String>,
+  output: Option<PathBuf>,
+  force: bool,
+) -> Result<(), ErrBox> {
+  if !flags.unstable {
+    exit_unstable("vendor");
+  }
+
+  let output_dir = output.unwrap_or_else(|| PathBuf::from("vendor"));
+  if !force
+    && output_dir.is_dir()
+    && std::fs::read_dir(&output_dir)?.next().is_some()
+  {
+    return Err(ErrBox::error(format!(
+      "Output directory {} is not empty, use --force to write to it anyway",
+      output_dir.display()
+    )));
+  }
+
+  let global_state = GlobalState::new(flags)?;
+  let specifiers = specifiers
+    .iter()
+    .map(|s| ModuleSpecifier::resolve_url_or_path(s))
+    .collect::<Result<Vec<_>, _>>()?;
+  let count = vendor::vendor(&global_state, specifiers, &output_dir).await?;
+
+  info!(
+    "{} {} remote modules into {}",
+    colors::green("Vendored"),
+    count,
+    output_dir.display()
+  );
+  info!(
+    "\nTo use them, run with: --importmap {}",
+    output_dir.join(vendor::IMPORT_MAP_FILENAME).display()
+  );
+  Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn test_command(
   flags: Flags,
   include: Option<Vec<String>>,
@@ -479,13 +748,41 @@ async fn test_command(
   quiet: bool,
   allow_none: bool,
   filter: Option<String>,
+  lcov: Option<PathBuf>,
+  jobs: usize,
+  reporter: TestReporterKind,
+  reporter_output: Option<PathBuf>,
+  doc: Option<DocTestMode>,
+  shuffle: Option<u64>,
 ) -> Result<(), ErrBox> {
   let global_state = GlobalState::new(flags.clone())?;
   let cwd = std::env::current_dir().expect("No current directory");
   let include = include.unwrap_or_else(|| vec![".".to_string()]);
-  let test_modules = test_runner::prepare_test_modules_urls(include, &cwd)?;
 
-  if test_modules.is_empty() {
+  if flags.watch {
+    let options = test_runner::TestRunOptions {
+      jobs,
+      fail_fast,
+      quiet,
+      filter,
+      shuffle,
+    };
+    return test_runner::run_tests_with_watch(flags, include, &cwd, options)
+      .await;
+  }
+
+  let mut test_modules =
+    test_runner::prepare_test_modules_urls(include.clone(), &cwd)?;
+  let doc_tests = if doc.is_some() {
+    // Markdown files given on the command line only provide code blocks.
+    test_modules.retain(|url| !url.path().ends_with(".md"));
+    let doc_modules = doc_tests::prepare_doc_modules_urls(include, &cwd);
+    doc_tests::collect_doc_tests(&global_state, &cwd, &doc_modules)?
+  } else {
+    vec![]
+  };
+
+  if test_modules.is_empty() && doc_tests.is_empty() {
     println!("No matching test modules found");
     if !allow_none {
       std::process::exit(1);
@@ -493,35 +790,121 @@ async fn test_command(
     return Ok(());
   }
 
+  if !doc_tests.is_empty() {
+    doc_tests::check_doc_tests(&global_state, &cwd, &doc_tests).await?;
+    if doc == Some(DocTestMode::Run) {
+      test_modules.push(doc_tests::save_doc_test_module(
+        &global_state,
+        &cwd,
+        &doc_tests,
+      ));
+    }
+  }
+
+  if test_modules.is_empty() {
+    return Ok(());
+  }
+
+  if let Some(seed) = shuffle {
+    eprintln!("shuffling tests with seed {}", seed);
+    test_runner::shuffle_modules(&mut test_modules, seed);
+  }
+
+  // Keep logs of the tests out of a report written to stdout.
+  let report_to_stdout =
+    reporter != TestReporterKind::Pretty && reporter_output.is_none();
+  let mut reporters: Vec<Box<dyn test_reporter::TestReporter>> =
+    test_reporter::create_reporter(reporter, reporter_output)?
+      .into_iter()
+      .collect();
+
+  if jobs > 1 {
+    if !report_to_stdout {
+      reporters
+        .insert(0, Box::new(test_runner::PrettyTestReporter::new(quiet)));
+    }
+    let options = test_runner::TestRunOptions {
+      jobs,
+      fail_fast,
+      quiet: quiet || report_to_stdout,
+      filter,
+      shuffle,
+    };
+    let passed = test_runner::run_test_modules_in_isolates(
+      &global_state,
+      &cwd,
+      test_modules,
+      options,
+      &mut reporters,
+    )?;
+    if !passed {
+      std::process::exit(1);
+    }
+    return Ok(());
+  }
+
   let test_file_path = cwd.join(".deno.test.ts");
   let test_file_url =
     Url::from_file_path(&test_file_path).expect("Should be valid file url");
-  let test_file =
-    test_runner::render_test_file(test_modules, fail_fast, quiet, filter);
+  let test_file = test_runner::render_test_file(
+    test_modules.clone(),
+    fail_fast,
+    quiet || report_to_stdout,
+    filter,
+    shuffle,
+  );
   let main_module =
-    ModuleSpecifier::resolve_url(&test_file_url.to_string()).unwrap();
+    test_runner::save_test_file(&global_state, &test_file_url, test_file);
   let mut worker = MainWorker::create(&global_state, main_module.clone())?;
-  // Create a dummy source file.
-  let source_file = SourceFile {
-    filename: test_file_url.to_file_path().unwrap(),
-    url: test_file_url,
-    types_header: None,
-    media_type: MediaType::TypeScript,
-    source_code: TextDocument::new(
-      test_file.clone().into_bytes(),
-      Some("utf-8"),
-    ),
+  let (sender, receiver) = std::sync::mpsc::channel();
+  ops::testing::init_reporter(&worker.state, test_file_url.clone(), &sender);
+
+  let mut maybe_coverage_collector = if flags.coverage {
+    let inspector = worker
+      .inspector
+      .as_mut()
+      .expect("Inspector is not created.");
+    let mut coverage_collector =
+      coverage::CoverageCollector::new(&mut **inspector);
+    coverage_collector.start_collecting().await?;
+    Some(coverage_collector)
+  } else {
+    None
   };
-  // Save our fake file into file fetcher cache
-  // to allow module access by TS compiler
-  global_state
-    .file_fetcher
-    .save_source_file_in_cache(&main_module, source_file);
+
   let execute_result = worker.execute_module(&main_module).await;
   execute_result?;
   worker.execute("window.dispatchEvent(new Event('load'))")?;
-  (&mut *worker).await?;
-  worker.execute("window.dispatchEvent(new Event('unload'))")
+  let passed =
+    test_runner::run_serial_test_worker(&mut worker, &receiver, &mut reporters)
+      .await?;
+  if passed {
+    worker.execute("window.dispatchEvent(new Event('unload'))")?;
+  }
+
+  if let Some(coverage_collector) = maybe_coverage_collector.as_mut() {
+    let coverages = coverage_collector.collect().await?;
+    coverage_collector.stop_collecting().await?;
+
+    let filtered_coverages = coverage::filter_script_coverages(
+      coverages,
+      test_file_url,
+      &test_modules,
+    );
+    let file_coverages: Vec<coverage::FileCoverage> = filtered_coverages
+      .iter()
+      .map(|c| coverage::FileCoverage::new(c, &global_state.ts_compiler))
+      .collect();
+    coverage::print_summary(&file_coverages);
+    if let Some(lcov) = lcov {
+      coverage::write_lcov(&file_coverages, &lcov)?;
+    }
+  }
+
+  if !passed {
+    std::process::exit(1);
+  }
+  Ok(())
 }
 
 pub fn main() {
@@ -529,6 +912,11 @@ pub fn main() {
   colors::enable_ansi(); // For Windows 10
 
   let args: Vec<String> = env::args().collect();
+  if let Err(err) = standalone::try_run_standalone_binary(args.clone()) {
+    eprintln!("{}: {}", colors::red_bold("error"), err.to_string());
+    std::process::exit(1);
+  }
+
   let flags = flags::flags_from_vec(args);
 
   if let Some(ref v8_flags) = flags.v8_flags {
@@ -584,10 +972,20 @@ pub fn main() {
   .init();
 
   let fut = match flags.clone().subcommand {
+    DenoSubcommand::Bench {
+      include,
+      filter,
+      json,
+      allow_none,
+    } => bench_command(flags, include, filter, json, allow_none).boxed_local(),
     DenoSubcommand::Bundle {
       source_file,
       out_file,
     } => bundle_command(flags, source_file, out_file).boxed_local(),
+    DenoSubcommand::Compile {
+      source_file,
+      out_file,
+    } => compile_command(flags, source_file, out_file).boxed_local(),
     DenoSubcommand::Doc {
       source_file,
       json,
@@ -599,7 +997,21 @@ pub fn main() {
       code,
       as_typescript,
     } => eval_command(flags, code, as_typescript, print).boxed_local(),
-    DenoSubcommand::Cache { files } => {
+    DenoSubcommand::Cache { list: true, .. } => {
+      cache_list_command(flags).boxed_local()
+    }
+    DenoSubcommand::Cache {
+      files,
+      gc: true,
+      older_than,
+      ..
+    } => cache_gc_command(flags, files, older_than).boxed_local(),
+    DenoSubcommand::Cache {
+      check_integrity: true,
+      repair,
+      ..
+    } => cache_check_integrity_command(flags, repair).boxed_local(),
+    DenoSubcommand::Cache { files, .. } => {
       cache_command(flags, files).boxed_local()
     }
     DenoSubcommand::Fmt {
@@ -633,8 +1045,27 @@ pub fn main() {
       include,
       allow_none,
       filter,
-    } => test_command(flags, include, fail_fast, quiet, allow_none, filter)
-      .boxed_local(),
+      lcov,
+      jobs,
+      reporter,
+      reporter_output,
+      doc,
+      shuffle,
+    } => test_command(
+      flags,
+      include,
+      fail_fast,
+      quiet,
+      allow_none,
+      filter,
+      lcov,
+      jobs,
+      reporter,
+      reporter_output,
+      doc,
+      shuffle,
+    )
+    .boxed_local(),
     DenoSubcommand::Completions { buf } => {
       if let Err(e) = write_to_stdout_ignore_sigpipe(&buf) {
         eprintln!("{}", e);
@@ -659,6 +1090,11 @@ pub fn main() {
     } => {
       upgrade_command(dry_run, force, version, output, ca_file).boxed_local()
     }
+    DenoSubcommand::Vendor {
+      specifiers,
+      output,
+      force,
+    } => vendor_command(flags, specifiers, output, force).boxed_local(),
     _ => unreachable!(),
   };
 
@@ -0,0 +1,139 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+use crate::state::State;
+use deno_core::BufVec;
+use deno_core::ErrBox;
+use deno_core::OpRegistry;
+use serde_derive::Deserialize;
+use serde_json::Value;
+use std::net::IpAddr;
+use std::rc::Rc;
+use trust_dns_resolver::config::NameServerConfigGroup;
+use trust_dns_resolver::config::ResolverConfig;
+use trust_dns_resolver::config::ResolverOpts;
+use trust_dns_resolver::proto::rr::RData;
+use trust_dns_resolver::proto::rr::RecordType;
+use trust_dns_resolver::proto::xfer::DnsRequestOptions;
+use trust_dns_resolver::TokioAsyncResolver;
+
+pub fn init(s: &Rc<State>) {
+  s.register_op_json_async("op_dns_resolve", op_dns_resolve);
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResolveDnsArgs {
+  query: String,
+  record_type: String,
+  options: Option<ResolveDnsOptions>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResolveDnsOptions {
+  name_server: Option<NameServer>,
+}
+
+/// A nameserver to query instead of the ones configured on the system.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NameServer {
+  ip_addr: String,
+  #[serde(default = "default_dns_port")]
+  port: u16,
+}
+
+fn default_dns_port() -> u16 {
+  53
+}
+
+fn parse_record_type(record_type: &str) -> Result<RecordType, ErrBox> {
+  match record_type {
+    "A" => Ok(RecordType::A),
+    "AAAA" => Ok(RecordType::AAAA),
+    "CNAME" => Ok(RecordType::CNAME),
+    "MX" => Ok(RecordType::MX),
+    "NS" => Ok(RecordType::NS),
+    "SRV" => Ok(RecordType::SRV),
+    "TXT" => Ok(RecordType::TXT),
+    _ => Err(ErrBox::type_error(format!(
+      "Unsupported record type: {}",
+      record_type
+    ))),
+  }
+}
+
+async fn op_dns_resolve(
+  state: Rc<State>,
+  args: Value,
+  _zero_copy: BufVec,
+) -> Result<Value, ErrBox> {
+  state.check_unstable("Deno.resolveDns");
+  let args: ResolveDnsArgs = serde_json::from_value(args)?;
+  let record_type = parse_record_type(&args.record_type)?;
+
+  let name_server = args.options.and_then(|options| options.name_server);
+  let resolver = match name_server {
+    Some(name_server) => {
+      state.check_net(&name_server.ip_addr, name_server.port)?;
+      state.check_net(&args.query, name_server.port)?;
+      let ip_addr: IpAddr = name_server
+        .ip_addr
+        .parse()
+        .map_err(|_| ErrBox::type_error("Invalid nameserver IP address"))?;
+      let config = ResolverConfig::from_parts(
+        None,
+        vec![],
+        NameServerConfigGroup::from_ips_clear(&[ip_addr], name_server.port),
+      );
+      TokioAsyncResolver::tokio(config, ResolverOpts::default()).await?
+    }
+    None => {
+      state.check_net(&args.query, default_dns_port())?;
+      TokioAsyncResolver::tokio_from_system_conf().await?
+    }
+  };
+
+  let lookup = resolver
+    .lookup(args.query, record_type, DnsRequestOptions::default())
+    .await?;
+
+  let records: Vec<Value> = lookup
+    .iter()
+    .filter_map(|rdata| rdata_to_json(record_type, rdata))
+    .collect();
+  Ok(json!(records))
+}
+
+/// Converts a record to its JS representation. Records of another type than
+/// the queried one (eg. the CNAME records preceding the A records of an
+/// alias) are skipped.
+fn rdata_to_json(record_type: RecordType, rdata: &RData) -> Option<Value> {
+  if rdata.to_record_type() != record_type {
+    return None;
+  }
+  let value = match rdata {
+    RData::A(ip) => json!(ip.to_string()),
+    RData::AAAA(ip) => json!(ip.to_string()),
+    RData::CNAME(name) | RData::NS(name) => json!(name.to_string()),
+    RData::MX(mx) => json!({
+      "preference": mx.preference(),
+      "exchange": mx.exchange().to_string(),
+    }),
+    RData::SRV(srv) => json!({
+      "priority": srv.priority(),
+      "weight": srv.weight(),
+      "port": srv.port(),
+      "target": srv.target().to_string(),
+    }),
+    RData::TXT(txt) => {
+      let strings: Vec<String> = txt
+        .iter()
+        .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+        .collect();
+      json!(strings)
+    }
+    _ => return None,
+  };
+  Some(value)
+}
@@ -1,27 +1,72 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
 use super::io::{StreamResource, StreamResourceHolder};
-use crate::http_util::{create_http_client, HttpBody};
+use crate::http_util::{
+  canceled_error, http_client_builder, CancelHandle, HttpBody,
+};
 use crate::state::State;
 use deno_core::BufVec;
 use deno_core::ErrBox;
 use deno_core::OpRegistry;
 use deno_core::ZeroCopyBuf;
+use futures::channel::mpsc;
+use futures::future::poll_fn;
+use futures::ready;
+use futures::SinkExt;
 use http::header::HeaderName;
 use http::header::HeaderValue;
+use http::header::AUTHORIZATION;
+use http::header::CONTENT_ENCODING;
+use http::header::CONTENT_LANGUAGE;
+use http::header::CONTENT_LENGTH;
+use http::header::CONTENT_LOCATION;
+use http::header::CONTENT_TYPE;
+use http::header::LOCATION;
 use http::Method;
+use http::StatusCode;
+use reqwest::Body;
 use reqwest::Client;
+use reqwest::Request;
+use reqwest::Response;
 use serde_derive::Deserialize;
 use serde_json::Value;
 use std::convert::From;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::task::Context;
+use std::task::Poll;
+use std::time::Duration;
 
 pub fn init(s: &Rc<State>) {
-  s.register_op_json_async("op_fetch", op_fetch);
+  s.register_op_json_sync("op_fetch", op_fetch);
+  s.register_op_json_async("op_fetch_send", op_fetch_send);
+  s.register_op_json_sync("op_fetch_cancel_handle", op_fetch_cancel_handle);
+  s.register_op_json_sync("op_fetch_cancel", op_fetch_cancel);
+  s.register_op_json_async(
+    "op_fetch_request_body_abort",
+    op_fetch_request_body_abort,
+  );
   s.register_op_json_sync("op_create_http_client", op_create_http_client);
 }
 
+/// What `fetch` does when the server responds with a redirect, see
+/// https://fetch.spec.whatwg.org/#concept-request-redirect-mode
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum RedirectMode {
+  Follow,
+  Manual,
+  Error,
+}
+
+impl Default for RedirectMode {
+  fn default() -> Self {
+    RedirectMode::Follow
+  }
+}
+
+const MAX_REDIRECTS: usize = 20;
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct FetchArgs {
@@ -29,26 +74,52 @@ struct FetchArgs {
   url: String,
   headers: Vec<(String, String)>,
   client_rid: Option<u32>,
+  has_body_stream: bool,
+  cancel_handle_rid: Option<u32>,
+  /// Overrides the redirect mode of the client.
+  redirect: Option<RedirectMode>,
+  /// Overrides the total timeout of the client, in milliseconds.
+  timeout: Option<u64>,
 }
 
-async fn op_fetch(
-  state: Rc<State>,
+/// A request built by `op_fetch` that hasn't been sent yet.
+struct FetchRequestResource {
+  client: Client,
+  request: Request,
+  redirect: RedirectMode,
+  timeout: Option<Duration>,
+  cancel_handle: Option<CancelHandle>,
+}
+
+struct FetchCancelHandleResource(CancelHandle);
+
+/// Chunks of a streamed request body, written by JS with `op_write`.
+pub type FetchRequestBodySender = mpsc::Sender<Result<Vec<u8>, std::io::Error>>;
+
+/// Builds a request and stores it in the resource table, to be sent with
+/// `op_fetch_send`. If `hasBodyStream` is set the request body is streamed:
+/// JS writes the chunks to the returned `requestBodyRid` and closes it once
+/// the body is complete. Otherwise the body, if any, is passed as a single
+/// buffer.
+fn op_fetch(
+  state: &State,
   args: Value,
-  data: BufVec,
+  data: &mut [ZeroCopyBuf],
 ) -> Result<Value, ErrBox> {
   let args: FetchArgs = serde_json::from_value(args)?;
   let url = args.url;
 
-  let client = if let Some(rid) = args.client_rid {
-    let resource_table_ = state.resource_table.borrow();
-    let r = resource_table_
-      .get::<HttpClientResource>(rid)
-      .ok_or_else(ErrBox::bad_resource_id)?;
-    r.client.clone()
-  } else {
-    let client_ref = state.http_client.borrow_mut();
-    client_ref.clone()
-  };
+  let (client, client_redirect, client_timeout) =
+    if let Some(rid) = args.client_rid {
+      let resource_table_ = state.resource_table.borrow();
+      let r = resource_table_
+        .get::<HttpClientResource>(rid)
+        .ok_or_else(ErrBox::bad_resource_id)?;
+      (r.client.clone(), r.redirect, r.timeout)
+    } else {
+      let client_ref = state.http_client.borrow_mut();
+      (client_ref.clone(), RedirectMode::default(), None)
+    };
 
   let method = match args.method {
     Some(method_str) => Method::from_bytes(method_str.as_bytes())?,
@@ -70,10 +141,24 @@ async fn op_fetch(
 
   let mut request = client.request(method, url_);
 
-  match data.len() {
-    0 => {}
-    1 => request = request.body(Vec::from(&*data[0])),
-    _ => panic!("Invalid number of arguments"),
+  let mut request_body_rid = None;
+  if args.has_body_stream {
+    // A small buffer keeps memory usage constant, JS is backpressured until
+    // reqwest has consumed the previous chunk.
+    let (sender, receiver) = mpsc::channel(1);
+    request = request.body(Body::wrap_stream(receiver));
+    request_body_rid = Some(state.resource_table.borrow_mut().add(
+      "fetchRequestBody",
+      Box::new(StreamResourceHolder::new(StreamResource::FetchRequestBody(
+        Box::new(sender),
+      ))),
+    ));
+  } else {
+    match data.len() {
+      0 => {}
+      1 => request = request.body(Vec::from(&*data[0])),
+      _ => return Err(ErrBox::type_error("Expected at most one body buffer")),
+    }
   }
 
   for (key, value) in args.headers {
@@ -81,18 +166,221 @@ async fn op_fetch(
     let v = HeaderValue::from_str(&value).unwrap();
     request = request.header(name, v);
   }
-  debug!("Before fetch {}", url);
 
-  let res = request.send().await?;
+  let cancel_handle = match args.cancel_handle_rid {
+    Some(rid) => {
+      let resource_table = state.resource_table.borrow();
+      let r = resource_table
+        .get::<FetchCancelHandleResource>(rid)
+        .ok_or_else(ErrBox::bad_resource_id)?;
+      Some(r.0.clone())
+    }
+    None => None,
+  };
+
+  let request_rid = state.resource_table.borrow_mut().add(
+    "fetchRequest",
+    Box::new(FetchRequestResource {
+      client,
+      request: request.build()?,
+      redirect: args.redirect.unwrap_or(client_redirect),
+      timeout: args.timeout.map(Duration::from_millis).or(client_timeout),
+      cancel_handle,
+    }),
+  );
+
+  Ok(json!({
+    "requestRid": request_rid,
+    "requestBodyRid": request_body_rid,
+  }))
+}
+
+/// Result of sending a request and handling redirects according to its
+/// `RedirectMode`. The variants correspond to response types in the fetch
+/// spec.
+enum FetchOutcome {
+  Default {
+    response: Response,
+    redirected: bool,
+  },
+  /// A redirect was received in "manual" mode.
+  OpaqueRedirect,
+  /// A redirect was received in "error" mode, or too many redirects were
+  /// followed.
+  NetworkError,
+}
+
+fn is_redirect_status(status: StatusCode) -> bool {
+  matches!(
+    status,
+    StatusCode::MOVED_PERMANENTLY
+      | StatusCode::FOUND
+      | StatusCode::SEE_OTHER
+      | StatusCode::TEMPORARY_REDIRECT
+      | StatusCode::PERMANENT_REDIRECT
+  )
+}
+
+/// Sends `request`, following redirects if `mode` says so. Every redirect
+/// target is checked against the net permissions, just like the initial
+/// url was, and credentials aren't sent along to a different origin.
+async fn send_with_redirects(
+  state: &State,
+  client: Client,
+  mut request: Request,
+  mode: RedirectMode,
+) -> Result<FetchOutcome, ErrBox> {
+  let mut redirected = false;
+  for _ in 0..=MAX_REDIRECTS {
+    let method = request.method().clone();
+    let origin = request.url().origin();
+    let mut headers = request.headers().clone();
+    // Requests with a streamed body can't be cloned, they can only be
+    // redirected if the body is dropped anyway.
+    let maybe_clone = request.try_clone();
+
+    let response = client.execute(request).await?;
+    let status = response.status();
+    if !is_redirect_status(status) {
+      return Ok(FetchOutcome::Default {
+        response,
+        redirected,
+      });
+    }
+    let location = match response
+      .headers()
+      .get(LOCATION)
+      .and_then(|location| location.to_str().ok())
+    {
+      Some(location) => location,
+      None => {
+        return Ok(FetchOutcome::Default {
+          response,
+          redirected,
+        })
+      }
+    };
+
+    match mode {
+      RedirectMode::Follow => {}
+      RedirectMode::Manual => return Ok(FetchOutcome::OpaqueRedirect),
+      RedirectMode::Error => return Ok(FetchOutcome::NetworkError),
+    }
+
+    let url = response.url().join(location)?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+      return Ok(FetchOutcome::NetworkError);
+    }
+    state.check_net_url(&url)?;
+
+    // The `Authorization` header is only sent to the origin it was set for.
+    if url.origin() != origin {
+      headers.remove(AUTHORIZATION);
+    }
+
+    // https://fetch.spec.whatwg.org/#http-redirect-fetch step 11
+    let drop_body = (status == StatusCode::SEE_OTHER && method != Method::HEAD)
+      || ((status == StatusCode::MOVED_PERMANENTLY
+        || status == StatusCode::FOUND)
+        && method == Method::POST);
+    request = if drop_body {
+      let mut next = Request::new(Method::GET, url);
+      *next.headers_mut() = headers;
+      for name in &[
+        CONTENT_ENCODING,
+        CONTENT_LANGUAGE,
+        CONTENT_LOCATION,
+        CONTENT_TYPE,
+        CONTENT_LENGTH,
+      ] {
+        next.headers_mut().remove(name);
+      }
+      next
+    } else {
+      let mut next = maybe_clone.ok_or_else(|| {
+        ErrBox::type_error(
+          "Cannot follow redirect with a streamed request body",
+        )
+      })?;
+      *next.url_mut() = url;
+      *next.headers_mut() = headers;
+      next
+    };
+    redirected = true;
+  }
+
+  Ok(FetchOutcome::NetworkError)
+}
+
+#[derive(Deserialize)]
+struct FetchSendArgs {
+  rid: u32,
+}
+
+async fn op_fetch_send(
+  state: Rc<State>,
+  args: Value,
+  _data: BufVec,
+) -> Result<Value, ErrBox> {
+  let args: FetchSendArgs = serde_json::from_value(args)?;
+
+  let FetchRequestResource {
+    client,
+    request,
+    redirect,
+    timeout,
+    cancel_handle,
+  } = *state
+    .resource_table
+    .borrow_mut()
+    .remove::<FetchRequestResource>(args.rid)
+    .ok_or_else(ErrBox::bad_resource_id)?;
 
-  debug!("Fetch response {}", url);
+  debug!("Before fetch {}", request.url());
+  let send = async {
+    let send = send_with_redirects(&state, client, request, redirect);
+    match timeout {
+      Some(timeout) => tokio::time::timeout(timeout, send)
+        .await
+        .map_err(|_| ErrBox::new("TimedOut", "The request timed out"))?,
+      None => send.await,
+    }
+  };
+  let outcome = match &cancel_handle {
+    Some(cancel_handle) => {
+      tokio::select! {
+        result = send => result?,
+        _ = poll_fn(|cx| cancel_handle.poll_canceled(cx)) => {
+          return Err(ErrBox::from(canceled_error()));
+        }
+      }
+    }
+    None => send.await?,
+  };
+
+  let (res, redirected) = match outcome {
+    FetchOutcome::Default {
+      response,
+      redirected,
+    } => (response, redirected),
+    FetchOutcome::OpaqueRedirect => {
+      return Ok(json!({ "type": "opaqueredirect" }))
+    }
+    FetchOutcome::NetworkError => return Ok(json!({ "type": "error" })),
+  };
+
+  debug!("Fetch response {}", res.url());
+  let url = res.url().to_string();
   let status = res.status();
   let mut res_headers = Vec::new();
   for (key, val) in res.headers().iter() {
     res_headers.push((key.to_string(), val.to_str().unwrap().to_owned()));
   }
 
-  let body = HttpBody::from(res);
+  let mut body = HttpBody::from(res);
+  if let Some(cancel_handle) = cancel_handle {
+    body = body.with_cancel_handle(cancel_handle);
+  }
   let rid = state.resource_table.borrow_mut().add(
     "httpBody",
     Box::new(StreamResourceHolder::new(StreamResource::HttpBody(
@@ -101,23 +389,113 @@ async fn op_fetch(
   );
 
   let json_res = json!({
+    "type": "default",
     "bodyRid": rid,
     "status": status.as_u16(),
     "statusText": status.canonical_reason().unwrap_or(""),
-    "headers": res_headers
+    "headers": res_headers,
+    "url": url,
+    "redirected": redirected,
   });
 
   Ok(json_res)
 }
 
-struct HttpClientResource {
-  client: Client,
+/// Creates a handle that can be passed to `op_fetch` to abort the request
+/// and the reading of its response body with `op_fetch_cancel`.
+fn op_fetch_cancel_handle(
+  state: &State,
+  _args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, ErrBox> {
+  let rid = state.resource_table.borrow_mut().add(
+    "fetchCancelHandle",
+    Box::new(FetchCancelHandleResource(CancelHandle::default())),
+  );
+  Ok(json!(rid))
+}
+
+#[derive(Deserialize)]
+struct FetchCancelArgs {
+  rid: u32,
+}
+
+fn op_fetch_cancel(
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, ErrBox> {
+  let args: FetchCancelArgs = serde_json::from_value(args)?;
+  let resource_table = state.resource_table.borrow();
+  let r = resource_table
+    .get::<FetchCancelHandleResource>(args.rid)
+    .ok_or_else(ErrBox::bad_resource_id)?;
+  r.0.cancel();
+  Ok(json!({}))
 }
 
-impl HttpClientResource {
-  fn new(client: Client) -> Self {
-    Self { client }
+#[derive(Deserialize)]
+struct RequestBodyAbortArgs {
+  rid: u32,
+}
+
+/// Ends a streamed request body with an error instead of closing it, so the
+/// request fails rather than sending a truncated body.
+async fn op_fetch_request_body_abort(
+  state: Rc<State>,
+  args: Value,
+  _data: BufVec,
+) -> Result<Value, ErrBox> {
+  let args: RequestBodyAbortArgs = serde_json::from_value(args)?;
+  let mut resource_holder = state
+    .resource_table
+    .borrow_mut()
+    .remove::<StreamResourceHolder>(args.rid)
+    .ok_or_else(ErrBox::bad_resource_id)?;
+  // Replace the sender with a dummy so it can be moved out of the holder.
+  let resource = std::mem::replace(
+    &mut resource_holder.resource,
+    StreamResource::TcpStream(None),
+  );
+  let mut sender = match resource {
+    StreamResource::FetchRequestBody(sender) => sender,
+    _ => return Err(ErrBox::bad_resource_id()),
+  };
+  let err = std::io::Error::new(
+    std::io::ErrorKind::Other,
+    "The request body stream errored",
+  );
+  // The request might have failed already, in which case nobody is
+  // listening anymore.
+  let _ = sender.send(Err(err)).await;
+  Ok(json!({}))
+}
+
+/// Sends `buf` as the next chunk of a streamed request body.
+pub fn poll_write_request_body(
+  sender: &mut FetchRequestBodySender,
+  cx: &mut Context,
+  buf: &[u8],
+) -> Poll<Result<usize, ErrBox>> {
+  if ready!(sender.poll_ready(cx)).is_err() {
+    return Poll::Ready(Err(ErrBox::new(
+      "BrokenPipe",
+      "Request body has been closed",
+    )));
   }
+  match sender.start_send(Ok(buf.to_vec())) {
+    Ok(()) => Poll::Ready(Ok(buf.len())),
+    Err(_) => Poll::Ready(Err(ErrBox::new(
+      "BrokenPipe",
+      "Request body has been closed",
+    ))),
+  }
+}
+
+struct HttpClientResource {
+  client: Client,
+  redirect: RedirectMode,
+  timeout: Option<Duration>,
 }
 
 #[derive(Deserialize, Default, Debug)]
@@ -125,6 +503,14 @@ impl HttpClientResource {
 #[serde(default)]
 struct CreateHttpClientOptions {
   ca_file: Option<String>,
+  /// Redirect mode of requests made with the client, unless overridden
+  /// per request.
+  redirect: RedirectMode,
+  /// Maximum time to establish a connection, in milliseconds.
+  connect_timeout: Option<u64>,
+  /// Maximum time for a request to complete, including redirects, in
+  /// milliseconds. Reading the response body is not included.
+  timeout: Option<u64>,
 }
 
 fn op_create_http_client(
@@ -138,11 +524,21 @@ fn op_create_http_client(
     state.check_read(&PathBuf::from(ca_file))?;
   }
 
-  let client = create_http_client(args.ca_file.as_deref()).unwrap();
+  let mut builder = http_client_builder(args.ca_file.as_deref())?;
+  if let Some(connect_timeout) = args.connect_timeout {
+    builder = builder.connect_timeout(Duration::from_millis(connect_timeout));
+  }
+  let client = builder
+    .build()
+    .map_err(|_| ErrBox::error("Unable to build http client"))?;
 
-  let rid = state
-    .resource_table
-    .borrow_mut()
-    .add("httpClient", Box::new(HttpClientResource::new(client)));
+  let rid = state.resource_table.borrow_mut().add(
+    "httpClient",
+    Box::new(HttpClientResource {
+      client,
+      redirect: args.redirect,
+      timeout: args.timeout.map(Duration::from_millis),
+    }),
+  );
   Ok(json!(rid))
 }
@@ -0,0 +1,388 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+use super::io::{StreamResource, StreamResourceHolder};
+use crate::state::State;
+use bytes::Bytes;
+use deno_core::BufVec;
+use deno_core::ErrBox;
+use deno_core::OpRegistry;
+use deno_core::ZeroCopyBuf;
+use futures::channel::mpsc;
+use futures::channel::oneshot;
+use futures::future::abortable;
+use futures::future::poll_fn;
+use futures::future::AbortHandle;
+use futures::future::FutureExt;
+use futures::ready;
+use futures::task::AtomicWaker;
+use futures::SinkExt;
+use futures::StreamExt;
+use hyper::body::HttpBody as _;
+use hyper::header::HOST;
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::Body;
+use hyper::Request;
+use hyper::Response;
+use serde_derive::Deserialize;
+use serde_json::Value;
+use std::cmp::min;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::Context;
+use std::task::Poll;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use tokio_rustls::rustls::Session;
+
+pub fn init(s: &Rc<State>) {
+  s.register_op_json_sync("op_http_start", op_http_start);
+  s.register_op_json_async("op_http_next_request", op_http_next_request);
+  s.register_op_json_sync("op_http_respond", op_http_respond);
+}
+
+type ServiceError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A request received by hyper, together with the channel its response
+/// must be sent through.
+type NextRequest = (Request<Body>, oneshot::Sender<Response<Body>>);
+
+/// An HTTP connection served by hyper.
+///
+/// The connection is driven by a task spawned on the runtime; requests are
+/// handed over to JS through `requests_rx`. Dropping the resource aborts
+/// the connection.
+struct HttpConnResource {
+  requests_rx: mpsc::Receiver<NextRequest>,
+  abort_handle: AbortHandle,
+  scheme: &'static str,
+  local_addr: SocketAddr,
+  waker: AtomicWaker,
+}
+
+impl Drop for HttpConnResource {
+  fn drop(&mut self) {
+    self.abort_handle.abort();
+    // Wake up a pending `op_http_next_request` so it can notice the
+    // connection was closed.
+    self.waker.wake();
+  }
+}
+
+struct HttpResponseSenderResource(oneshot::Sender<Response<Body>>);
+
+#[derive(Deserialize)]
+struct StartArgs {
+  rid: i32,
+}
+
+fn op_http_start(
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, ErrBox> {
+  state.check_unstable("Deno.serveHttp");
+  let args: StartArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+
+  let mut resource_holder = {
+    let mut resource_table = state.resource_table.borrow_mut();
+    match resource_table.remove::<StreamResourceHolder>(rid) {
+      Some(resource) => *resource,
+      None => return Err(ErrBox::bad_resource_id()),
+    }
+  };
+
+  let conn_resource = match resource_holder.resource {
+    StreamResource::TcpStream(ref mut tcp_stream) => {
+      let tcp_stream = tcp_stream.take().unwrap();
+      let local_addr = tcp_stream.local_addr()?;
+      serve_connection(tcp_stream, "http", local_addr, false)
+    }
+    StreamResource::ServerTlsStream(ref mut tls_stream) => {
+      let (tcp_stream, session) = tls_stream.get_ref();
+      let local_addr = tcp_stream.local_addr()?;
+      let is_h2 = session.get_alpn_protocol() == Some(&b"h2"[..]);
+      // Replace the stream with a dummy so it can be moved out of the
+      // holder, which is dropped right after.
+      let tls_stream = std::mem::replace(
+        &mut resource_holder.resource,
+        StreamResource::TcpStream(None),
+      );
+      match tls_stream {
+        StreamResource::ServerTlsStream(tls_stream) => {
+          serve_connection(tls_stream, "https", local_addr, is_h2)
+        }
+        _ => unreachable!(),
+      }
+    }
+    _ => return Err(ErrBox::bad_resource_id()),
+  };
+
+  let rid = state
+    .resource_table
+    .borrow_mut()
+    .add("httpConn", Box::new(conn_resource));
+  Ok(json!(rid))
+}
+
+/// Spawns a task serving HTTP/1.1 (with keep-alive) on `io`. If
+/// `http2_only` is set, which is the case when "h2" was negotiated over
+/// ALPN, the connection speaks HTTP/2 instead. Plain text connections also
+/// accept HTTP/2 with prior knowledge.
+fn serve_connection<IO>(
+  io: IO,
+  scheme: &'static str,
+  local_addr: SocketAddr,
+  http2_only: bool,
+) -> HttpConnResource
+where
+  IO: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+  let (requests_tx, requests_rx) = mpsc::channel::<NextRequest>(1);
+  let service = service_fn(move |request: Request<Body>| {
+    let mut requests_tx = requests_tx.clone();
+    async move {
+      let (response_tx, response_rx) = oneshot::channel();
+      requests_tx.send((request, response_tx)).await?;
+      let response = response_rx.await?;
+      Ok::<_, ServiceError>(response)
+    }
+  });
+
+  let mut http = Http::new();
+  http.http2_only(http2_only);
+  let (conn, abort_handle) = abortable(http.serve_connection(io, service));
+  // A combinator rather than an async block, which rustc fails to prove Send
+  // because of the service's boxed error type.
+  tokio::spawn(conn.map(|result| {
+    if let Ok(Err(err)) = result {
+      debug!("HTTP connection error: {}", err);
+    }
+  }));
+
+  HttpConnResource {
+    requests_rx,
+    abort_handle,
+    scheme,
+    local_addr,
+    waker: AtomicWaker::new(),
+  }
+}
+
+#[derive(Deserialize)]
+struct NextRequestArgs {
+  rid: i32,
+}
+
+async fn op_http_next_request(
+  state: Rc<State>,
+  args: Value,
+  _zero_copy: BufVec,
+) -> Result<Value, ErrBox> {
+  let args: NextRequestArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+
+  let (maybe_request, scheme, local_addr) = poll_fn(|cx| {
+    let mut resource_table = state.resource_table.borrow_mut();
+    let conn_resource = resource_table
+      .get_mut::<HttpConnResource>(rid)
+      .ok_or_else(|| ErrBox::bad_resource("Connection has been closed"))?;
+    match conn_resource.requests_rx.poll_next_unpin(cx) {
+      Poll::Ready(maybe_request) => Poll::Ready(Ok::<_, ErrBox>((
+        maybe_request,
+        conn_resource.scheme,
+        conn_resource.local_addr,
+      ))),
+      Poll::Pending => {
+        conn_resource.waker.register(cx.waker());
+        Poll::Pending
+      }
+    }
+  })
+  .await?;
+
+  let (request, response_tx) = match maybe_request {
+    Some(next_request) => next_request,
+    // The peer closed the connection.
+    None => return Ok(json!(null)),
+  };
+
+  let (parts, body) = request.into_parts();
+  let host = parts
+    .uri
+    .authority()
+    .map(|authority| authority.as_str().to_string())
+    .or_else(|| {
+      parts
+        .headers
+        .get(HOST)
+        .and_then(|host| host.to_str().ok())
+        .map(String::from)
+    })
+    .unwrap_or_else(|| local_addr.to_string());
+  let path = parts
+    .uri
+    .path_and_query()
+    .map(|path_and_query| path_and_query.as_str())
+    .unwrap_or("/");
+  let url = format!("{}://{}{}", scheme, host, path);
+
+  let mut headers = Vec::new();
+  for (key, value) in parts.headers.iter() {
+    headers.push((
+      key.to_string(),
+      String::from_utf8_lossy(value.as_bytes()).to_string(),
+    ));
+  }
+
+  let mut resource_table = state.resource_table.borrow_mut();
+  let body_rid = if body.is_end_stream() {
+    None
+  } else {
+    Some(resource_table.add(
+      "httpRequestBody",
+      Box::new(StreamResourceHolder::new(StreamResource::HttpRequestBody(
+        Box::new(RequestBody::from(body)),
+      ))),
+    ))
+  };
+  let response_rid = resource_table.add(
+    "httpResponseSender",
+    Box::new(HttpResponseSenderResource(response_tx)),
+  );
+
+  Ok(json!({
+    "method": parts.method.as_str(),
+    "url": url,
+    "headers": headers,
+    "bodyRid": body_rid,
+    "responseRid": response_rid,
+  }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RespondArgs {
+  rid: i32,
+  status: u16,
+  headers: Vec<(String, String)>,
+  stream_body: bool,
+}
+
+fn op_http_respond(
+  state: &State,
+  args: Value,
+  zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, ErrBox> {
+  let args: RespondArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+
+  let mut resource_table = state.resource_table.borrow_mut();
+  let response_sender = resource_table
+    .remove::<HttpResponseSenderResource>(rid)
+    .ok_or_else(ErrBox::bad_resource_id)?;
+
+  let mut builder = Response::builder().status(args.status);
+  for (key, value) in args.headers {
+    builder = builder.header(key.as_bytes(), value.as_bytes());
+  }
+
+  let (body, body_rid) = if args.stream_body {
+    let (sender, body) = Body::channel();
+    let rid = resource_table.add(
+      "httpResponseBody",
+      Box::new(StreamResourceHolder::new(StreamResource::HttpResponseBody(
+        Box::new(sender),
+      ))),
+    );
+    (body, Some(rid))
+  } else {
+    let body = match zero_copy.len() {
+      0 => Body::empty(),
+      1 => Body::from(zero_copy[0].to_vec()),
+      _ => {
+        return Err(ErrBox::type_error(
+          "Expected at most one response body buffer",
+        ))
+      }
+    };
+    (body, None)
+  };
+
+  let response = builder
+    .body(body)
+    .map_err(|e| ErrBox::type_error(e.to_string()))?;
+  // The connection might have been closed by the peer in the meantime,
+  // there is nobody to report that to.
+  let _ = response_sender.0.send(response);
+
+  Ok(json!({ "bodyRid": body_rid }))
+}
+
+/// Body of a request received by the HTTP server, readable with `op_read`.
+pub struct RequestBody {
+  body: Body,
+  chunk: Option<Bytes>,
+}
+
+impl From<Body> for RequestBody {
+  fn from(body: Body) -> Self {
+    Self { body, chunk: None }
+  }
+}
+
+impl AsyncRead for RequestBody {
+  fn poll_read(
+    self: Pin<&mut Self>,
+    cx: &mut Context,
+    buf: &mut [u8],
+  ) -> Poll<Result<usize, io::Error>> {
+    let inner = self.get_mut();
+    loop {
+      if let Some(mut chunk) = inner.chunk.take() {
+        let n = min(buf.len(), chunk.len());
+        buf[..n].copy_from_slice(&chunk.split_to(n));
+        if !chunk.is_empty() {
+          inner.chunk = Some(chunk);
+        }
+        return Poll::Ready(Ok(n));
+      }
+
+      match ready!(Pin::new(&mut inner.body).poll_data(cx)) {
+        Some(Ok(chunk)) => {
+          if !chunk.is_empty() {
+            inner.chunk = Some(chunk);
+          }
+        }
+        Some(Err(e)) => {
+          return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, e)))
+        }
+        None => return Poll::Ready(Ok(0)),
+      }
+    }
+  }
+}
+
+/// Writes `buf` to a streamed response body as a single chunk.
+pub fn poll_write_response_body(
+  sender: &mut hyper::body::Sender,
+  cx: &mut Context,
+  buf: &[u8],
+) -> Poll<Result<usize, ErrBox>> {
+  if ready!(sender.poll_ready(cx)).is_err() {
+    return Poll::Ready(Err(ErrBox::new(
+      "BrokenPipe",
+      "Response body has been closed",
+    )));
+  }
+  match sender.try_send_data(Bytes::copy_from_slice(buf)) {
+    Ok(()) => Poll::Ready(Ok(buf.len())),
+    Err(_) => Poll::Ready(Err(ErrBox::new(
+      "BrokenPipe",
+      "Response body has been closed",
+    ))),
+  }
+}
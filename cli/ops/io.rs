@@ -1,4 +1,8 @@
 use super::dispatch_minimal::MinimalOp;
+use super::fetch::poll_write_request_body;
+use super::fetch::FetchRequestBodySender;
+use super::http::poll_write_response_body;
+use super::http::RequestBody;
 use crate::http_util::HttpBody;
 use crate::state::State;
 use deno_core::BufVec;
@@ -185,6 +189,9 @@ pub enum StreamResource {
   ServerTlsStream(Box<ServerTlsStream<TcpStream>>),
   ClientTlsStream(Box<ClientTlsStream<TcpStream>>),
   HttpBody(Box<HttpBody>),
+  FetchRequestBody(Box<FetchRequestBodySender>),
+  HttpRequestBody(Box<RequestBody>),
+  HttpResponseBody(Box<hyper::body::Sender>),
   ChildStdin(tokio::process::ChildStdin),
   ChildStdout(tokio::process::ChildStdout),
   ChildStderr(tokio::process::ChildStderr),
@@ -225,6 +232,7 @@ impl DenoAsyncRead for StreamResource {
       ChildStdout(f) => f,
       ChildStderr(f) => f,
       HttpBody(f) => f,
+      HttpRequestBody(f) => f,
       _ => return Err(ErrBox::bad_resource_id()).into(),
     };
     let v = ready!(Pin::new(f).poll_read(cx, buf))?;
@@ -321,6 +329,12 @@ impl DenoAsyncWrite for StreamResource {
       ClientTlsStream(f) => f,
       ServerTlsStream(f) => f,
       ChildStdin(f) => f,
+      HttpResponseBody(sender) => {
+        return poll_write_response_body(sender, cx, buf)
+      }
+      FetchRequestBody(sender) => {
+        return poll_write_request_body(sender, cx, buf)
+      }
       _ => return Err(ErrBox::bad_resource_id()).into(),
     };
 
@@ -339,6 +353,8 @@ impl DenoAsyncWrite for StreamResource {
       ClientTlsStream(f) => f,
       ServerTlsStream(f) => f,
       ChildStdin(f) => f,
+      // Chunks are handed over as soon as they are written.
+      HttpResponseBody(_) | FetchRequestBody(_) => return Ok(()).into(),
       _ => return Err(ErrBox::bad_resource_id()).into(),
     };
 
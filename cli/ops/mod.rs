@@ -4,10 +4,12 @@ mod dispatch_minimal;
 pub use dispatch_minimal::MinimalOp;
 
 pub mod compiler;
+pub mod dns;
 pub mod errors;
 pub mod fetch;
 pub mod fs;
 pub mod fs_events;
+pub mod http;
 pub mod idna;
 pub mod io;
 pub mod net;
@@ -23,6 +25,7 @@ pub mod resources;
 pub mod runtime;
 pub mod runtime_compiler;
 pub mod signal;
+pub mod testing;
 pub mod timers;
 pub mod tls;
 pub mod tty;
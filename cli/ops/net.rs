@@ -10,11 +10,13 @@ use deno_core::ZeroCopyBuf;
 use futures::future::poll_fn;
 use serde_derive::Deserialize;
 use serde_json::Value;
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
 use std::net::Shutdown;
 use std::net::SocketAddr;
 use std::rc::Rc;
 use std::task::Context;
 use std::task::Poll;
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tokio::net::TcpStream;
 use tokio::net::UdpSocket;
@@ -29,6 +31,8 @@ pub fn init(s: &Rc<State>) {
   s.register_op_json_sync("op_listen", op_listen);
   s.register_op_json_async("op_datagram_receive", op_datagram_receive);
   s.register_op_json_async("op_datagram_send", op_datagram_send);
+  s.register_op_json_sync("op_set_nodelay", op_set_nodelay);
+  s.register_op_json_sync("op_set_keepalive", op_set_keepalive);
 }
 
 #[derive(Deserialize)]
@@ -223,12 +227,38 @@ async fn op_datagram_send(
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct ConnectArgs {
   transport: String,
+  /// Local address to bind the socket to before connecting, "tcp" only.
+  local_addr: Option<IpListenArgs>,
   #[serde(flatten)]
   transport_args: ArgsEnum,
 }
 
+/// Connects to `addr` from a socket bound to `local_addr`. The standard
+/// library has no way to bind a socket before connecting it, so the socket
+/// is set up with socket2 and connected on the blocking thread pool.
+async fn connect_tcp_from(
+  local_addr: SocketAddr,
+  addr: SocketAddr,
+) -> Result<TcpStream, ErrBox> {
+  let std_stream = tokio::task::spawn_blocking(move || {
+    let domain = if addr.is_ipv4() {
+      Domain::ipv4()
+    } else {
+      Domain::ipv6()
+    };
+    let socket = Socket::new(domain, Type::stream(), Some(Protocol::tcp()))?;
+    socket.bind(&SockAddr::from(local_addr))?;
+    socket.connect(&SockAddr::from(addr))?;
+    Ok::<_, std::io::Error>(socket.into_tcp_stream())
+  })
+  .await
+  .map_err(|e| ErrBox::error(e.to_string()))??;
+  Ok(TcpStream::from_std(std_stream)?)
+}
+
 async fn op_connect(
   state: Rc<State>,
   args: Value,
@@ -237,11 +267,20 @@ async fn op_connect(
   match serde_json::from_value(args)? {
     ConnectArgs {
       transport,
+      local_addr,
       transport_args: ArgsEnum::Ip(args),
     } if transport == "tcp" => {
       state.check_net(&args.hostname, args.port)?;
       let addr = resolve_addr(&args.hostname, args.port)?;
-      let tcp_stream = TcpStream::connect(&addr).await?;
+      let tcp_stream = match local_addr {
+        Some(local_addr) => {
+          state.check_unstable("Deno.connect.localAddr");
+          state.check_net(&local_addr.hostname, local_addr.port)?;
+          let local_addr = resolve_addr(&local_addr.hostname, local_addr.port)?;
+          connect_tcp_from(local_addr, addr).await?
+        }
+        None => TcpStream::connect(&addr).await?,
+      };
       let local_addr = tcp_stream.local_addr()?;
       let remote_addr = tcp_stream.peer_addr()?;
       let rid = state.resource_table.borrow_mut().add(
@@ -267,6 +306,7 @@ async fn op_connect(
     #[cfg(unix)]
     ConnectArgs {
       transport,
+      local_addr: None,
       transport_args: ArgsEnum::Unix(args),
     } if transport == "unix" => {
       let address_path = net_unix::Path::new(&args.path);
@@ -341,6 +381,75 @@ fn op_shutdown(
   Ok(json!({}))
 }
 
+/// Runs `f` on the TCP stream underlying the stream resource `rid`, which
+/// may be a plain or a TLS connection.
+fn with_tcp_stream<F>(state: &State, rid: u32, f: F) -> Result<(), ErrBox>
+where
+  F: FnOnce(&TcpStream) -> std::io::Result<()>,
+{
+  let resource_table = state.resource_table.borrow();
+  let resource_holder = resource_table
+    .get::<StreamResourceHolder>(rid)
+    .ok_or_else(ErrBox::bad_resource_id)?;
+  let tcp_stream = match resource_holder.resource {
+    StreamResource::TcpStream(Some(ref stream)) => stream,
+    StreamResource::ClientTlsStream(ref stream) => stream.get_ref().0,
+    StreamResource::ServerTlsStream(ref stream) => stream.get_ref().0,
+    _ => return Err(ErrBox::bad_resource_id()),
+  };
+  f(tcp_stream)?;
+  Ok(())
+}
+
+#[derive(Deserialize)]
+struct SetNoDelayArgs {
+  rid: u32,
+  nodelay: bool,
+}
+
+fn op_set_nodelay(
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, ErrBox> {
+  state.check_unstable("Deno.Conn.setNoDelay");
+  let args: SetNoDelayArgs = serde_json::from_value(args)?;
+  with_tcp_stream(state, args.rid, |stream| stream.set_nodelay(args.nodelay))?;
+  Ok(json!({}))
+}
+
+#[derive(Deserialize)]
+struct SetKeepAliveArgs {
+  rid: u32,
+  keepalive: bool,
+  /// Idle time in milliseconds before keepalive probes are sent.
+  interval: Option<u64>,
+}
+
+/// Same as the default of Linux, macOS and Windows.
+const DEFAULT_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(2 * 60 * 60);
+
+fn op_set_keepalive(
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, ErrBox> {
+  state.check_unstable("Deno.Conn.setKeepAlive");
+  let args: SetKeepAliveArgs = serde_json::from_value(args)?;
+  let keepalive = if args.keepalive {
+    Some(
+      args
+        .interval
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_KEEPALIVE_INTERVAL),
+    )
+  } else {
+    None
+  };
+  with_tcp_stream(state, args.rid, |stream| stream.set_keepalive(keepalive))?;
+  Ok(json!({}))
+}
+
 #[allow(dead_code)]
 struct TcpListenerResource {
   listener: TcpListener,
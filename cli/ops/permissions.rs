@@ -1,12 +1,20 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
+use crate::flags_allow_net::parse_allowlist;
+use crate::permissions::resolve_fs_allowlist;
+use crate::permissions::PermissionState;
+use crate::permissions::Permissions;
+use crate::permissions::UnaryPermission;
 use crate::state::State;
 use deno_core::ErrBox;
 use deno_core::OpRegistry;
 use deno_core::ZeroCopyBuf;
 use serde_derive::Deserialize;
 use serde_json::Value;
+use std::collections::HashSet;
+use std::hash::Hash;
 use std::path::Path;
+use std::path::PathBuf;
 use std::rc::Rc;
 
 pub fn init(s: &Rc<State>) {
@@ -99,3 +107,86 @@ pub fn op_request_permission(
   };
   Ok(json!({ "state": perm.to_string() }))
 }
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum UnaryPermissionArg {
+  Enabled(bool),
+  Allowlist(Vec<String>),
+}
+
+/// Permissions requested for a worker or a test. Omitted permissions are
+/// inherited from the parent.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PermissionsArg {
+  read: Option<UnaryPermissionArg>,
+  write: Option<UnaryPermissionArg>,
+  net: Option<UnaryPermissionArg>,
+  env: Option<bool>,
+  run: Option<bool>,
+  plugin: Option<bool>,
+  hrtime: Option<bool>,
+}
+
+fn unary_permission_from_arg<T: Eq + Hash + Clone>(
+  parent: &UnaryPermission<T>,
+  arg: Option<UnaryPermissionArg>,
+  parse_allowlist: impl Fn(Vec<String>) -> Result<HashSet<T>, ErrBox>,
+) -> Result<UnaryPermission<T>, ErrBox> {
+  let (global_state, granted_list) = match arg {
+    None => return Ok(parent.clone()),
+    Some(UnaryPermissionArg::Enabled(true)) => {
+      (PermissionState::Granted, HashSet::new())
+    }
+    Some(UnaryPermissionArg::Enabled(false)) => {
+      (PermissionState::Denied, HashSet::new())
+    }
+    Some(UnaryPermissionArg::Allowlist(allowlist)) => {
+      (PermissionState::Prompt, parse_allowlist(allowlist)?)
+    }
+  };
+  Ok(UnaryPermission {
+    global_state,
+    granted_list,
+    denied_list: parent.denied_list.clone(),
+  })
+}
+
+fn permission_state_from_arg(
+  parent: PermissionState,
+  arg: Option<bool>,
+) -> PermissionState {
+  match arg {
+    None => parent,
+    Some(true) => PermissionState::Granted,
+    Some(false) => PermissionState::Denied,
+  }
+}
+
+/// Narrows `parent` to the requested permissions, making sure they don't
+/// escalate the permissions of the parent.
+pub fn fork_permissions(
+  parent: &Permissions,
+  args: PermissionsArg,
+) -> Result<Permissions, ErrBox> {
+  let parse_paths = |allowlist: Vec<String>| {
+    let paths: Vec<PathBuf> =
+      allowlist.into_iter().map(PathBuf::from).collect();
+    Ok(resolve_fs_allowlist(&paths))
+  };
+  // Same as `--allow-net`, so "https://deno.land" allows "deno.land".
+  let parse_hosts = |allowlist: Vec<String>| {
+    let hosts = parse_allowlist(allowlist).map_err(ErrBox::type_error)?;
+    Ok(hosts.into_iter().collect())
+  };
+  parent.fork(
+    unary_permission_from_arg(&parent.read, args.read, parse_paths)?,
+    unary_permission_from_arg(&parent.write, args.write, parse_paths)?,
+    unary_permission_from_arg(&parent.net, args.net, parse_hosts)?,
+    permission_state_from_arg(parent.env, args.env),
+    permission_state_from_arg(parent.run, args.run),
+    permission_state_from_arg(parent.plugin, args.plugin),
+    permission_state_from_arg(parent.hrtime, args.hrtime),
+  )
+}
@@ -0,0 +1,180 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+use crate::ops::permissions::fork_permissions;
+use crate::ops::permissions::PermissionsArg;
+use crate::state::State;
+use crate::test_runner::TestEvent;
+use crate::test_runner::TestMessage;
+use deno_core::BufVec;
+use deno_core::ErrBox;
+use deno_core::OpRegistry;
+use deno_core::ZeroCopyBuf;
+use futures::future::FutureExt;
+use rand::thread_rng;
+use rand::Rng;
+use serde_derive::Deserialize;
+use serde_json::Value;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+use std::time::Instant;
+use url::Url;
+
+pub fn init(s: &Rc<State>) {
+  s.register_op_json_async("op_test_timer_start", op_test_timer_start);
+  s.register_op_json_sync("op_test_timer_stop", op_test_timer_stop);
+  s.register_op_json_sync(
+    "op_pledge_test_permissions",
+    op_pledge_test_permissions,
+  );
+  s.register_op_json_sync(
+    "op_restore_test_permissions",
+    op_restore_test_permissions,
+  );
+  s.register_op_json_sync(
+    "op_issue_test_permissions_token",
+    op_issue_test_permissions_token,
+  );
+}
+
+/// Registers the op used by the test runner to report its progress to
+/// `deno test`. Messages are attributed to `module` until the runner posts
+/// the module of a test.
+pub fn init_reporter(s: &Rc<State>, module: Url, sender: &Sender<TestEvent>) {
+  let sender = sender.clone();
+  let current_module = RefCell::new(module);
+  s.register_op_json_sync(
+    "op_post_test_message",
+    move |_state, args, _bufs| {
+      let args: PostTestMessageArgs = serde_json::from_value(args)?;
+      if let Some(module) = args.module {
+        *current_module.borrow_mut() = Url::parse(&module)?;
+      }
+      let event = TestEvent {
+        module: current_module.borrow().clone(),
+        message: args.message,
+      };
+      sender
+        .send(event)
+        .map_err(|_| ErrBox::new("BrokenPipe", "Test reporter has exited"))?;
+      Ok(json!({}))
+    },
+  );
+}
+
+#[derive(Deserialize)]
+struct PostTestMessageArgs {
+  message: TestMessage,
+  module: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TestTimerArgs {
+  timeout: u64,
+}
+
+/// Resolves once the timeout of the running test is exceeded or the timer is
+/// stopped. Unlike `setTimeout()` this timer doesn't share the global timer
+/// of the isolate, so it doesn't disturb the op sanitizer of the test.
+async fn op_test_timer_start(
+  state: Rc<State>,
+  args: Value,
+  _zero_copy: BufVec,
+) -> Result<Value, ErrBox> {
+  let args: TestTimerArgs = serde_json::from_value(args)?;
+  let deadline = Instant::now() + Duration::from_millis(args.timeout);
+  let timer_fut = state
+    .test_timer
+    .borrow_mut()
+    .new_timeout(deadline)
+    .boxed_local();
+  let _ = timer_fut.await;
+  Ok(json!({}))
+}
+
+fn op_test_timer_stop(
+  state: &State,
+  _args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, ErrBox> {
+  state.test_timer.borrow_mut().cancel();
+  Ok(json!({}))
+}
+
+/// Issues the token which `op_pledge_test_permissions` and
+/// `op_restore_test_permissions` require. The runtime takes it while
+/// bootstrapping, before any user code runs, so only the test runner has it.
+fn op_issue_test_permissions_token(
+  state: &State,
+  _args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, ErrBox> {
+  let mut issued = state.test_permissions_token.borrow_mut();
+  if issued.is_some() {
+    return Err(ErrBox::new(
+      "PermissionDenied",
+      "The test permissions token was already issued",
+    ));
+  }
+  let token = format!("{:032x}", thread_rng().gen::<u128>());
+  *issued = Some(token.clone());
+  Ok(json!({ "token": token }))
+}
+
+fn check_test_permissions_token(
+  state: &State,
+  token: &str,
+) -> Result<(), ErrBox> {
+  match state.test_permissions_token.borrow().as_deref() {
+    Some(issued) if issued == token => Ok(()),
+    _ => Err(ErrBox::new(
+      "PermissionDenied",
+      "Invalid test permissions token",
+    )),
+  }
+}
+
+#[derive(Deserialize)]
+struct PledgeTestPermissionsArgs {
+  token: String,
+  permissions: PermissionsArg,
+}
+
+/// Narrows the permissions of the isolate for the duration of a test. The
+/// original permissions are restored by `op_restore_test_permissions`.
+fn op_pledge_test_permissions(
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, ErrBox> {
+  state.check_unstable("Deno.test.permissions");
+  let args: PledgeTestPermissionsArgs = serde_json::from_value(args)?;
+  check_test_permissions_token(state, &args.token)?;
+  let mut permissions = state.permissions.borrow_mut();
+  let narrowed = fork_permissions(&permissions, args.permissions)?;
+  let original = std::mem::replace(&mut *permissions, narrowed);
+  state
+    .test_saved_permissions
+    .borrow_mut()
+    .get_or_insert(original);
+  Ok(json!({}))
+}
+
+#[derive(Deserialize)]
+struct RestoreTestPermissionsArgs {
+  token: String,
+}
+
+fn op_restore_test_permissions(
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, ErrBox> {
+  let args: RestoreTestPermissionsArgs = serde_json::from_value(args)?;
+  check_test_permissions_token(state, &args.token)?;
+  if let Some(original) = state.test_saved_permissions.borrow_mut().take() {
+    *state.permissions.borrow_mut() = original;
+  }
+  Ok(json!({}))
+}
@@ -2,11 +2,20 @@
 
 use crate::state::State;
 use crate::web_worker::WebWorkerHandle;
+use crate::worker::worker_message_from_bufs;
 use crate::worker::WorkerEvent;
 use deno_core::OpRegistry;
 use futures::channel::mpsc;
+use serde_derive::Deserialize;
+use serde_json::Value;
 use std::rc::Rc;
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PostMessageArgs {
+  array_buffers: usize,
+}
+
 pub fn init(
   s: &Rc<State>,
   sender: &mpsc::Sender<WorkerEvent>,
@@ -16,12 +25,12 @@ pub fn init(
   let sender_ = sender.clone();
   s.register_op_json_sync(
     "op_worker_post_message",
-    move |_state, _args, bufs| {
-      assert_eq!(bufs.len(), 1, "Invalid number of arguments");
-      let msg_buf: Box<[u8]> = (*bufs[0]).into();
+    move |_state, args: Value, bufs| {
+      let args: PostMessageArgs = serde_json::from_value(args)?;
+      let msg = worker_message_from_bufs(bufs, args.array_buffers)?;
       sender_
         .clone()
-        .try_send(WorkerEvent::Message(msg_buf))
+        .try_send(WorkerEvent::Message(msg))
         .expect("Failed to post message to host");
       Ok(json!({}))
     },
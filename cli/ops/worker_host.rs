@@ -3,15 +3,19 @@
 use crate::fmt_errors::JsError;
 use crate::global_state::GlobalState;
 use crate::ops::io::get_stdio;
+use crate::ops::permissions::fork_permissions;
+use crate::ops::permissions::PermissionsArg;
 use crate::permissions::Permissions;
 use crate::startup_data;
 use crate::state::State;
 use crate::tokio_util::create_basic_runtime;
 use crate::web_worker::WebWorker;
 use crate::web_worker::WebWorkerHandle;
+use crate::worker::worker_message_from_bufs;
 use crate::worker::WorkerEvent;
 use deno_core::BufVec;
 use deno_core::ErrBox;
+use deno_core::Inbox;
 use deno_core::ModuleSpecifier;
 use deno_core::OpRegistry;
 use deno_core::ZeroCopyBuf;
@@ -168,6 +172,7 @@ struct CreateWorkerArgs {
   has_source_code: bool,
   source_code: String,
   use_deno_namespace: bool,
+  permissions: Option<PermissionsArg>,
 }
 
 /// Create worker as the host
@@ -190,7 +195,13 @@ fn op_create_worker(
     state.check_unstable("Worker.deno");
   }
   let global_state = state.global_state.clone();
-  let permissions = state.permissions.borrow().clone();
+  let permissions = match args.permissions {
+    Some(permissions_args) => {
+      state.check_unstable("Worker.deno.permissions");
+      fork_permissions(&state.permissions.borrow(), permissions_args)?
+    }
+    None => state.permissions.borrow().clone(),
+  };
   let worker_id = state.next_worker_id.get();
   state.next_worker_id.set(worker_id + 1);
 
@@ -238,9 +249,13 @@ fn op_host_terminate_worker(
   Ok(json!({}))
 }
 
-fn serialize_worker_event(event: WorkerEvent) -> Value {
+/// Messages are deposited into `inbox`, for the host to take them with
+/// `Deno.core.takeFromInbox()`.
+fn serialize_worker_event(event: WorkerEvent, inbox: &Inbox) -> Value {
   match event {
-    WorkerEvent::Message(buf) => json!({ "type": "msg", "data": buf }),
+    WorkerEvent::Message(msg) => {
+      json!({ "type": "msg", "inboxId": inbox.deposit(msg) })
+    }
     WorkerEvent::TerminalError(error) => {
       let mut serialized_error = json!({
         "type": "terminalError",
@@ -319,7 +334,7 @@ async fn op_host_get_message(
           join_handle.join().expect("Worker thread panicked");
         }
       }
-      serialize_worker_event(event)
+      serialize_worker_event(event, &state.inbox)
     }
     None => {
       // Worker shuts down
@@ -336,16 +351,22 @@ async fn op_host_get_message(
   Ok(response)
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HostPostMessageArgs {
+  id: i32,
+  array_buffers: usize,
+}
+
 /// Post message to guest worker as host
 fn op_host_post_message(
   state: &State,
   args: Value,
   data: &mut [ZeroCopyBuf],
 ) -> Result<Value, ErrBox> {
-  assert_eq!(data.len(), 1, "Invalid number of arguments");
-  let args: WorkerArgs = serde_json::from_value(args)?;
+  let args: HostPostMessageArgs = serde_json::from_value(args)?;
   let id = args.id as u32;
-  let msg = Vec::from(&*data[0]).into_boxed_slice();
+  let msg = worker_message_from_bufs(data, args.array_buffers)?;
 
   debug!("post message to worker {}", id);
   let workers = state.workers.borrow();
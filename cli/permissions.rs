@@ -98,9 +98,17 @@ pub struct UnaryPermission<T: Eq + Hash> {
 
 impl<T: Eq + Hash> UnaryPermission<T> {
   /// Check that the permissions represented by `other` don't escalate ours.
-  fn check_fork(&self, other: &Self) -> Result<(), ErrBox> {
+  /// `is_granted` tells whether an entry of `other`'s allowlist is covered by
+  /// our allowlist.
+  fn check_fork(
+    &self,
+    other: &Self,
+    is_granted: impl Fn(&T) -> bool,
+  ) -> Result<(), ErrBox> {
     self.global_state.check_fork(&other.global_state)?;
-    if !self.granted_list.is_superset(&other.granted_list) {
+    if self.global_state != PermissionState::Granted
+      && !other.granted_list.iter().all(is_granted)
+    {
       return Err(permission_escalation_error());
     }
     if !self.denied_list.is_subset(&other.denied_list) {
@@ -121,7 +129,7 @@ pub struct Permissions {
   pub hrtime: PermissionState,
 }
 
-fn resolve_fs_allowlist(allowlist: &[PathBuf]) -> HashSet<PathBuf> {
+pub fn resolve_fs_allowlist(allowlist: &[PathBuf]) -> HashSet<PathBuf> {
   allowlist
     .iter()
     .map(|raw_path| resolve_from_cwd(Path::new(&raw_path)).unwrap())
@@ -607,9 +615,16 @@ impl Permissions {
     plugin: PermissionState,
     hrtime: PermissionState,
   ) -> Result<Permissions, ErrBox> {
-    self.read.check_fork(&read)?;
-    self.write.check_fork(&write)?;
-    self.net.check_fork(&net)?;
+    self.read.check_fork(&read, |path| {
+      check_path_allowlist(path, &self.read.granted_list)
+    })?;
+    self.write.check_fork(&write, |path| {
+      check_path_allowlist(path, &self.write.granted_list)
+    })?;
+    self.net.check_fork(&net, |host_and_port| {
+      let (host, port) = split_host_and_port(host_and_port);
+      check_host_and_port_list(host, port, &self.net.granted_list)
+    })?;
     self.env.check_fork(&env)?;
     self.run.check_fork(&run)?;
     self.plugin.check_fork(&plugin)?;
@@ -716,6 +731,17 @@ fn check_host_and_port_list(
       && allowlist.contains(&format!("{}:{}", host, port.unwrap())))
 }
 
+/// Splits an allowlist entry like "deno.land:443" into host and port.
+fn split_host_and_port(entry: &str) -> (&str, Option<u16>) {
+  match entry.rfind(':') {
+    Some(i) => match entry[i + 1..].parse() {
+      Ok(port) => (&entry[..i], Some(port)),
+      Err(_) => (entry, None),
+    },
+    None => (entry, None),
+  }
+}
+
 fn permission_escalation_error() -> ErrBox {
   ErrBox::new("PermissionDenied", "Arguments escalate parent permissions")
 }
@@ -969,6 +995,37 @@ mod tests {
       .expect_err("Fork should fail.");
   }
 
+  #[test]
+  fn test_fork_allowlist() {
+    let perms0 = Permissions::from_flags(&Flags {
+      read_allowlist: vec![PathBuf::from("/a")],
+      net_allowlist: svec!["deno.land", "localhost:4545"],
+      ..Default::default()
+    });
+    let fork = |read: &str, net: &str| {
+      perms0.fork(
+        UnaryPermission {
+          granted_list: resolve_fs_allowlist(&[PathBuf::from(read)]),
+          ..Default::default()
+        },
+        UnaryPermission::default(),
+        UnaryPermission {
+          granted_list: vec![net.to_string()].into_iter().collect(),
+          ..Default::default()
+        },
+        PermissionState::Denied,
+        PermissionState::Denied,
+        PermissionState::Denied,
+        PermissionState::Denied,
+      )
+    };
+    fork("/a/b", "deno.land:443").expect("Fork should succeed.");
+    fork("/a", "localhost:4545").expect("Fork should succeed.");
+    fork("/b", "deno.land").expect_err("Fork should fail.");
+    fork("/a", "localhost").expect_err("Fork should fail.");
+    fork("/a", "localhost:4546").expect_err("Fork should fail.");
+  }
+
   #[test]
   fn test_query() {
     let perms1 = Permissions {
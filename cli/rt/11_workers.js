@@ -2,7 +2,8 @@
 /* eslint-disable @typescript-eslint/no-explicit-any */
 
 ((window) => {
-  const { log } = window.__bootstrap.util;
+  const core = window.Deno.core;
+  const { log, pathFromURL } = window.__bootstrap.util;
   const { sendSync, sendAsync } = window.__bootstrap.dispatchJson;
   /*
   import { blobURLMap } from "./web/url.ts";
@@ -13,6 +14,7 @@
     hasSourceCode,
     sourceCode,
     useDenoNamespace,
+    permissions,
     name,
   ) {
     return sendSync("op_create_worker", {
@@ -21,6 +23,7 @@
       sourceCode,
       name,
       useDenoNamespace,
+      permissions,
     });
   }
 
@@ -28,25 +31,96 @@
     sendSync("op_host_terminate_worker", { id });
   }
 
-  function hostPostMessage(id, data) {
-    sendSync("op_host_post_message", { id }, data);
+  function hostPostMessage(id, message) {
+    sendSync(
+      "op_host_post_message",
+      { id, arrayBuffers: message.arrayBuffers.length },
+      ...messageBuffers(message),
+    );
   }
 
   function hostGetMessage(id) {
     return sendAsync("op_host_get_message", { id });
   }
 
-  const encoder = new TextEncoder();
   const decoder = new TextDecoder();
 
-  function encodeMessage(data) {
-    const dataJson = JSON.stringify(data);
-    return encoder.encode(dataJson);
+  /** Converts the `deno.permissions` worker option, or the `permissions` of a
+   * test definition, to the format expected by `op_create_worker` and
+   * `op_pledge_test_permissions`. "inherit" is represented by omitting the
+   * permission. */
+  function parsePermissions(permissions) {
+    if (permissions === undefined || permissions === "inherit") {
+      return undefined;
+    }
+    if (typeof permissions !== "object" || permissions === null) {
+      throw new TypeError(
+        `Expected "inherit" or an object of permissions, got ${
+          String(permissions)
+        }`,
+      );
+    }
+    const parsed = {};
+    for (const [name, value] of Object.entries(permissions)) {
+      if (value === "inherit") {
+        continue;
+      }
+      if (typeof value === "string") {
+        throw new TypeError(
+          `Expected "inherit", a boolean or a list for the "${name}" permission, got "${value}"`,
+        );
+      }
+      if (
+        (name === "read" || name === "write") && Array.isArray(value)
+      ) {
+        parsed[name] = value.map(pathFromURL);
+      } else {
+        parsed[name] = value;
+      }
+    }
+    return parsed;
+  }
+
+  /** Normalizes the second argument of `postMessage()`, which is either the
+   * transfer list or an options bag containing it. */
+  function getTransferList(transferOrOptions) {
+    if (transferOrOptions == null) {
+      return [];
+    }
+    if (Array.isArray(transferOrOptions)) {
+      return transferOrOptions;
+    }
+    return transferOrOptions.transfer ?? [];
   }
 
-  function decodeMessage(dataIntArray) {
-    const dataJson = decoder.decode(dataIntArray);
-    return JSON.parse(dataJson);
+  /** Serializes the arguments of `postMessage()`. The ArrayBuffers in the
+   * transfer list are detached, and their contents move along with the
+   * message. */
+  function serializeMessage(message, transferOrOptions) {
+    const transfer = getTransferList(transferOrOptions);
+    try {
+      return core.serialize(message, Array.from(transfer));
+    } catch (e) {
+      if (e.name === "DataCloneError" && !(e instanceof DOMException)) {
+        throw new DOMException(e.message, "DataCloneError");
+      }
+      throw e;
+    }
+  }
+
+  /** Returns the buffers to pass to a post message op: the serialized data,
+   * followed by the transferred ArrayBuffers and the SharedArrayBuffers. */
+  function messageBuffers({ data, arrayBuffers, sharedArrayBuffers }) {
+    return [
+      data,
+      ...arrayBuffers.map((ab) => new Uint8Array(ab)),
+      ...sharedArrayBuffers.map((sab) => new Uint8Array(sab)),
+    ];
+  }
+
+  /** Deserializes a message deposited into the inbox of this isolate. */
+  function deserializeMessage(inboxId) {
+    return core.deserialize(core.takeFromInbox(inboxId));
   }
 
   class Worker extends EventTarget {
@@ -84,23 +158,32 @@
       }
       */
 
-      const useDenoNamespace = options ? !!options.deno : false;
+      const deno = options?.deno;
+      let useDenoNamespace;
+      let permissions;
+      if (typeof deno === "object" && deno !== null) {
+        useDenoNamespace = !!deno.namespace;
+        permissions = parsePermissions(deno.permissions);
+      } else {
+        useDenoNamespace = !!deno;
+      }
 
       const { id } = createWorker(
         specifier,
         hasSourceCode,
         sourceCode,
         useDenoNamespace,
+        permissions,
         options?.name,
       );
       this.#id = id;
       this.#poll();
     }
 
-    #handleMessage = (msgData) => {
+    #handleMessage = (inboxId) => {
       let data;
       try {
-        data = decodeMessage(new Uint8Array(msgData));
+        data = deserializeMessage(inboxId);
       } catch (e) {
         const msgErrorEvent = new MessageEvent("messageerror", {
           cancelable: false,
@@ -153,6 +236,9 @@
 
         // If terminate was called then we ignore all messages
         if (this.#terminated) {
+          if (event.type === "msg") {
+            core.takeFromInbox(event.inboxId);
+          }
           return;
         }
 
@@ -167,7 +253,7 @@
         }
 
         if (type === "msg") {
-          this.#handleMessage(event.data);
+          this.#handleMessage(event.inboxId);
           continue;
         }
 
@@ -189,17 +275,11 @@
     };
 
     postMessage(message, transferOrOptions) {
-      if (transferOrOptions) {
-        throw new Error(
-          "Not yet implemented: `transfer` and `options` are not supported.",
-        );
-      }
-
       if (this.#terminated) {
         return;
       }
 
-      hostPostMessage(this.#id, encodeMessage(message));
+      hostPostMessage(this.#id, serializeMessage(message, transferOrOptions));
     }
 
     terminate() {
@@ -212,5 +292,9 @@
 
   window.__bootstrap.worker = {
     Worker,
+    deserializeMessage,
+    messageBuffers,
+    parsePermissions,
+    serializeMessage,
   };
 })(this);
@@ -25,6 +25,8 @@
     return m;
   }
 
+  const redirectModes = ["follow", "error", "manual"];
+
   class Request extends body.Body {
     constructor(input, init) {
       if (arguments.length < 1) {
@@ -78,6 +80,12 @@
       // readonly attribute RequestCredentials credentials;
       this.credentials = "omit";
 
+      // readonly attribute RequestRedirect redirect;
+      this.redirect = "follow";
+      // The redirect mode given explicitly, which takes precedence over the
+      // one of the `Deno.HttpClient` used to fetch the request.
+      this._redirect = null;
+
       if (input instanceof Request) {
         if (input.bodyUsed) {
           throw TypeError(body.BodyUsedError);
@@ -86,6 +94,8 @@
         this.url = input.url;
         this.headers = new Headers(input.headers);
         this.credentials = input.credentials;
+        this.redirect = input.redirect;
+        this._redirect = input._redirect;
         this._stream = input._stream;
       } else if (typeof input === "string") {
         this.url = input;
@@ -103,6 +113,13 @@
       ) {
         this.credentials = init.credentials;
       }
+
+      if (init && init.redirect !== undefined) {
+        if (!redirectModes.includes(init.redirect)) {
+          throw new TypeError(`Invalid redirect mode: ${init.redirect}`);
+        }
+        this.redirect = this._redirect = init.redirect;
+      }
     }
 
     clone() {
@@ -129,6 +146,7 @@
         method: this.method,
         headers: new Headers(headersList),
         credentials: this.credentials,
+        redirect: this._redirect ?? undefined,
       });
     }
   }
@@ -4,13 +4,14 @@
   const { notImplemented } = window.__bootstrap.util;
   const { getHeaderValueParams, isTypedArray } = window.__bootstrap.webUtil;
   const { Blob, bytesSymbol: blobBytesSymbol } = window.__bootstrap.blob;
-  const { read } = window.__bootstrap.io;
+  const { read, write } = window.__bootstrap.io;
   const { close } = window.__bootstrap.resources;
   const { sendSync, sendAsync } = window.__bootstrap.dispatchJson;
   const Body = window.__bootstrap.body;
   const { ReadableStream } = window.__bootstrap.streams;
   const { MultipartBuilder } = window.__bootstrap.multipart;
   const { Headers } = window.__bootstrap.headers;
+  const abortSignal = window.__bootstrap.abortSignal;
 
   function createHttpClient(options) {
     return new HttpClient(opCreateHttpClient(options));
@@ -35,7 +36,58 @@
       zeroCopy = new Uint8Array(body.buffer, body.byteOffset, body.byteLength);
     }
 
-    return sendAsync("op_fetch", args, ...(zeroCopy ? [zeroCopy] : []));
+    return sendSync("op_fetch", args, ...(zeroCopy ? [zeroCopy] : []));
+  }
+
+  function opFetchSend(args) {
+    return sendAsync("op_fetch_send", args);
+  }
+
+  function opFetchCancelHandle() {
+    return sendSync("op_fetch_cancel_handle");
+  }
+
+  function opFetchCancel(rid) {
+    return sendSync("op_fetch_cancel", { rid });
+  }
+
+  function opFetchRequestBodyAbort(rid) {
+    return sendAsync("op_fetch_request_body_abort", { rid });
+  }
+
+  function abortError() {
+    return new DOMException("Ongoing fetch was aborted.", "AbortError");
+  }
+
+  // Writes the chunks of `reader` to the request body. If the stream errors
+  // or yields something else than a Uint8Array, `onError` is called and the
+  // body is ended with an error, so the request fails instead of sending a
+  // truncated body.
+  async function pumpRequestBody(rid, reader, onError) {
+    try {
+      while (true) {
+        const { done, value } = await reader.read();
+        if (done) break;
+        if (!(value instanceof Uint8Array)) {
+          throw new TypeError(
+            "Request body stream must only enqueue Uint8Array chunks",
+          );
+        }
+        await writeAll(rid, value);
+      }
+    } catch (e) {
+      onError(e);
+      await opFetchRequestBodyAbort(rid);
+      return;
+    }
+    close(rid);
+  }
+
+  async function writeAll(rid, chunk) {
+    let nwritten = 0;
+    while (nwritten < chunk.byteLength) {
+      nwritten += await write(rid, chunk.subarray(nwritten));
+    }
   }
 
   const NULL_BODY_STATUS = [101, 204, 205, 304];
@@ -183,30 +235,104 @@
     }
   }
 
-  function sendFetchReq(url, method, headers, body, clientRid) {
+  async function sendFetchReq(
+    url,
+    method,
+    headers,
+    body,
+    clientRid,
+    cancelHandleRid,
+    redirect,
+    timeout,
+  ) {
     let headerArray = [];
     if (headers) {
       headerArray = Array.from(headers.entries());
     }
 
+    const hasBodyStream = body instanceof ReadableStream;
     const args = {
       method,
       url,
       headers: headerArray,
       clientRid,
+      hasBodyStream,
+      cancelHandleRid,
+      redirect,
+      timeout,
     };
 
-    return opFetch(args, body);
+    const { requestRid, requestBodyRid } = opFetch(
+      args,
+      hasBodyStream ? null : body,
+    );
+    let reader;
+    let bodyError = null;
+    if (requestBodyRid !== null) {
+      // Stream the body while the request is in flight. A failure to read
+      // the body also fails the request, which is then rejected with the
+      // error of the stream.
+      reader = body.getReader();
+      pumpRequestBody(requestBodyRid, reader, (e) => {
+        bodyError = e;
+      }).catch(() => {});
+    }
+    try {
+      return await opFetchSend({ rid: requestRid });
+    } catch (e) {
+      // Stop pulling from the stream if the request failed or was aborted
+      // before the whole body was sent.
+      reader?.cancel(e).catch(() => {});
+      throw bodyError ?? e;
+    }
   }
 
   async function fetch(input, init) {
+    const signal = init?.signal ?? null;
+    if (signal === null) {
+      return fetchInner(input, init, null);
+    }
+    if (signal.aborted) {
+      throw abortError();
+    }
+
+    // The cancel handle aborts the request as well as the reading of the
+    // response body, so it is only released once the body is done.
+    const cancelHandleRid = opFetchCancelHandle();
+    const onAbort = () => opFetchCancel(cancelHandleRid);
+    signal[abortSignal.add](onAbort);
+    let released = false;
+    const canceler = {
+      rid: cancelHandleRid,
+      signal,
+      release() {
+        if (released) return;
+        released = true;
+        signal[abortSignal.remove](onAbort);
+        close(cancelHandleRid);
+      },
+    };
+
+    try {
+      const response = await fetchInner(input, init, canceler);
+      if (response._bodySource == null) {
+        canceler.release();
+      }
+      return response;
+    } catch (e) {
+      canceler.release();
+      throw signal.aborted ? abortError() : e;
+    }
+  }
+
+  async function fetchInner(input, init, canceler) {
     let url;
     let method = null;
     let headers = null;
     let body;
     let clientRid = null;
-    let redirected = false;
-    let remRedirectCount = 20; // TODO: use a better way to handle
+    let redirect = null;
+    let timeout = null;
 
     if (typeof input === "string" || input instanceof URL) {
       url = typeof input === "string" ? input : input.href;
@@ -250,8 +376,9 @@
             const multipartBuilder = new MultipartBuilder(init.body, boundary);
             body = multipartBuilder.getBody();
             contentType = multipartBuilder.getContentType();
+          } else if (init.body instanceof ReadableStream) {
+            body = init.body;
           } else {
-            // TODO: ReadableStream
             notImplemented();
           }
           if (contentType && !headers.has("content-type")) {
@@ -262,123 +389,95 @@
         if (init.client instanceof HttpClient) {
           clientRid = init.client.rid;
         }
+        redirect = init.redirect ?? null;
+        timeout = init.timeout ?? null;
       }
     } else {
       url = input.url;
       method = input.method;
       headers = input.headers;
+      redirect = input._redirect ?? null;
 
-      if (input._bodySource) {
+      if (input._bodySource instanceof ReadableStream) {
+        body = input.body;
+      } else if (input._bodySource) {
         body = new DataView(await input.arrayBuffer());
       }
     }
 
+    const fetchResponse = await sendFetchReq(
+      url,
+      method,
+      headers,
+      body,
+      clientRid,
+      canceler?.rid ?? null,
+      redirect,
+      timeout,
+    );
+
+    if (fetchResponse.type !== "default") {
+      // The redirect was not followed, see `RedirectMode` in ops/fetch.rs.
+      const responseInit = {};
+      responseData.set(responseInit, {
+        type: fetchResponse.type,
+        redirected: false,
+        url: "",
+      });
+      return new Response(null, responseInit);
+    }
+
     let responseBody;
-    let responseInit = {};
-    while (remRedirectCount) {
-      const fetchResponse = await sendFetchReq(
-        url,
-        method,
-        headers,
-        body,
-        clientRid,
-      );
-
-      if (
-        NULL_BODY_STATUS.includes(fetchResponse.status) ||
-        REDIRECT_STATUS.includes(fetchResponse.status)
-      ) {
-        // We won't use body of received response, so close it now
-        // otherwise it will be kept in resource table.
-        close(fetchResponse.bodyRid);
-        responseBody = null;
-      } else {
-        responseBody = new ReadableStream({
-          async pull(controller) {
-            try {
-              const b = new Uint8Array(1024 * 32);
-              const result = await read(fetchResponse.bodyRid, b);
-              if (result === null) {
-                controller.close();
-                return close(fetchResponse.bodyRid);
-              }
-
-              controller.enqueue(b.subarray(0, result));
-            } catch (e) {
-              controller.error(e);
+    if (
+      NULL_BODY_STATUS.includes(fetchResponse.status) ||
+      REDIRECT_STATUS.includes(fetchResponse.status)
+    ) {
+      // We won't use body of received response, so close it now
+      // otherwise it will be kept in resource table.
+      close(fetchResponse.bodyRid);
+      responseBody = null;
+    } else {
+      responseBody = new ReadableStream({
+        async pull(controller) {
+          try {
+            const b = new Uint8Array(1024 * 32);
+            const result = await read(fetchResponse.bodyRid, b);
+            if (result === null) {
               controller.close();
-              close(fetchResponse.bodyRid);
+              canceler?.release();
+              return close(fetchResponse.bodyRid);
             }
-          },
-          cancel() {
-            // When reader.cancel() is called
-            close(fetchResponse.bodyRid);
-          },
-        });
-      }
-
-      responseInit = {
-        status: 200,
-        statusText: fetchResponse.statusText,
-        headers: fetchResponse.headers,
-      };
 
-      responseData.set(responseInit, {
-        redirected,
-        rid: fetchResponse.bodyRid,
-        status: fetchResponse.status,
-        url,
+            controller.enqueue(b.subarray(0, result));
+          } catch (e) {
+            controller.error(canceler?.signal.aborted ? abortError() : e);
+            controller.close();
+            canceler?.release();
+            close(fetchResponse.bodyRid);
+          }
+        },
+        cancel() {
+          // When reader.cancel() is called
+          canceler?.release();
+          close(fetchResponse.bodyRid);
+        },
       });
-
-      const response = new Response(responseBody, responseInit);
-
-      if (REDIRECT_STATUS.includes(fetchResponse.status)) {
-        // We're in a redirect status
-        switch ((init && init.redirect) || "follow") {
-          case "error":
-            responseInit = {};
-            responseData.set(responseInit, {
-              type: "error",
-              redirected: false,
-              url: "",
-            });
-            return new Response(null, responseInit);
-          case "manual":
-            responseInit = {};
-            responseData.set(responseInit, {
-              type: "opaqueredirect",
-              redirected: false,
-              url: "",
-            });
-            return new Response(null, responseInit);
-          case "follow":
-          default:
-            let redirectUrl = response.headers.get("Location");
-            if (redirectUrl == null) {
-              return response; // Unspecified
-            }
-            if (
-              !redirectUrl.startsWith("http://") &&
-              !redirectUrl.startsWith("https://")
-            ) {
-              redirectUrl = new URL(redirectUrl, url).href;
-            }
-            url = redirectUrl;
-            redirected = true;
-            remRedirectCount--;
-        }
-      } else {
-        return response;
-      }
     }
 
+    const responseInit = {
+      status: 200,
+      statusText: fetchResponse.statusText,
+      headers: fetchResponse.headers,
+    };
+
     responseData.set(responseInit, {
-      type: "error",
-      redirected: false,
-      url: "",
+      redirected: fetchResponse.redirected,
+      rid: fetchResponse.bodyRid,
+      status: fetchResponse.status,
+      url: fetchResponse.url,
     });
 
-    return new Response(null, responseInit);
+    return new Response(responseBody, responseInit);
   }
 
   window.__bootstrap.fetch = {
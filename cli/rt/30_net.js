@@ -91,6 +91,14 @@
     closeWrite() {
       shutdown(this.rid, ShutdownMode.Write);
     }
+
+    setNoDelay(nodelay = true) {
+      sendSync("op_set_nodelay", { rid: this.rid, nodelay });
+    }
+
+    setKeepAlive(keepalive = true, interval = undefined) {
+      sendSync("op_set_keepalive", { rid: this.rid, keepalive, interval });
+    }
   }
 
   class Listener {
@@ -218,10 +226,14 @@
     if (options.transport === "unix") {
       res = await opConnect(options);
     } else {
+      const localAddr = options.localAddr
+        ? { hostname: "0.0.0.0", port: 0, ...options.localAddr }
+        : undefined;
       res = await opConnect({
         transport: "tcp",
         hostname: "127.0.0.1",
         ...options,
+        localAddr,
       });
     }
 
@@ -0,0 +1,148 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+((window) => {
+  const { errors } = window.__bootstrap.errors;
+  const { read, write } = window.__bootstrap.io;
+  const { close } = window.__bootstrap.resources;
+  const { sendSync, sendAsync } = window.__bootstrap.dispatchJson;
+  const { Request } = window.__bootstrap.request;
+  const { ReadableStream } = window.__bootstrap.streams;
+
+  function opHttpStart(rid) {
+    return sendSync("op_http_start", { rid });
+  }
+
+  function opHttpNextRequest(rid) {
+    return sendAsync("op_http_next_request", { rid });
+  }
+
+  function opHttpRespond(args, body) {
+    return sendSync("op_http_respond", args, ...(body ? [body] : []));
+  }
+
+  function serveHttp(conn) {
+    const rid = opHttpStart(conn.rid);
+    return new HttpConn(rid);
+  }
+
+  function createRequestBodyStream(rid) {
+    return new ReadableStream({
+      async pull(controller) {
+        try {
+          const b = new Uint8Array(1024 * 32);
+          const result = await read(rid, b);
+          if (result === null) {
+            controller.close();
+            return close(rid);
+          }
+
+          controller.enqueue(b.subarray(0, result));
+        } catch (e) {
+          controller.error(e);
+          controller.close();
+          close(rid);
+        }
+      },
+      cancel() {
+        close(rid);
+      },
+    });
+  }
+
+  async function respond(responseRid, resp) {
+    const headers = Array.from(resp.headers.entries());
+    const source = resp._bodySource;
+
+    if (source instanceof ReadableStream) {
+      const { bodyRid } = opHttpRespond({
+        rid: responseRid,
+        status: resp.status,
+        headers,
+        streamBody: true,
+      });
+      const reader = resp.body.getReader();
+      try {
+        while (true) {
+          const { done, value } = await reader.read();
+          if (done) break;
+          await write(bodyRid, value);
+        }
+      } finally {
+        close(bodyRid);
+      }
+    } else {
+      let body;
+      if (source != null) {
+        body = new Uint8Array(await resp.arrayBuffer());
+      }
+      opHttpRespond({
+        rid: responseRid,
+        status: resp.status,
+        headers,
+        streamBody: false,
+      }, body);
+    }
+  }
+
+  class HttpConn {
+    #rid = 0;
+
+    constructor(rid) {
+      this.#rid = rid;
+    }
+
+    get rid() {
+      return this.#rid;
+    }
+
+    async nextRequest() {
+      let next;
+      try {
+        next = await opHttpNextRequest(this.#rid);
+      } catch (error) {
+        if (error instanceof errors.BadResource) {
+          return null;
+        }
+        throw error;
+      }
+      if (next === null) {
+        return null;
+      }
+
+      const { method, url, headers, bodyRid, responseRid } = next;
+      const init = { method, headers };
+      if (bodyRid !== null) {
+        init.body = createRequestBodyStream(bodyRid);
+      }
+      const request = new Request(url, init);
+
+      let responded = false;
+      const respondWith = async (r) => {
+        if (responded) {
+          throw new TypeError("respondWith() has already been called");
+        }
+        responded = true;
+        await respond(responseRid, await r);
+      };
+
+      return { request, respondWith };
+    }
+
+    close() {
+      close(this.#rid);
+    }
+
+    async *[Symbol.asyncIterator]() {
+      while (true) {
+        const requestEvent = await this.nextRequest();
+        if (requestEvent === null) return;
+        yield requestEvent;
+      }
+    }
+  }
+
+  window.__bootstrap.http = {
+    serveHttp,
+    HttpConn,
+  };
+})(this);
@@ -2,6 +2,7 @@
 
 ((window) => {
   const net = window.__bootstrap.net;
+  const { sendAsync } = window.__bootstrap.dispatchJson;
 
   function listen(options) {
     if (options.transport === "unix") {
@@ -40,9 +41,14 @@
     }
   }
 
+  function resolveDns(query, recordType, options) {
+    return sendAsync("op_dns_resolve", { query, recordType, options });
+  }
+
   window.__bootstrap.netUnstable = {
     connect,
     listenDatagram,
     listen,
+    resolveDns,
   };
 })(this);
@@ -9,6 +9,8 @@
   const { metrics } = window.__bootstrap.metrics;
   const { resources } = window.__bootstrap.resources;
   const { assert } = window.__bootstrap.util;
+  const { sendSync, sendAsync } = window.__bootstrap.dispatchJson;
+  const { parsePermissions } = window.__bootstrap.worker;
 
   const disabledConsole = new Console(() => {});
 
@@ -31,12 +33,18 @@
   function assertOps(fn) {
     return async function asyncOpSanitizer() {
       const pre = metrics();
+      const abandonedBefore = pendingAbandonedTests;
       await fn();
       // Defer until next event loop turn - that way timeouts and intervals
       // cleared can actually be removed from resource table, otherwise
       // false positives may occur (https://github.com/denoland/deno/issues/4591)
       await delay(0);
       const post = metrics();
+      // Ops of abandoned tests can't be told apart from the ones of this
+      // test while they're still running.
+      if (abandonedBefore > 0 || pendingAbandonedTests > 0) {
+        return;
+      }
       // We're checking diff because one might spawn HTTP server in the background
       // that will be a pending async op before test starts.
       const dispatchedDiff = post.opsDispatchedAsync - pre.opsDispatchedAsync;
@@ -65,8 +73,13 @@ finishing test case.`,
   ) {
     return async function resourceSanitizer() {
       const pre = resources();
+      const abandonedBefore = pendingAbandonedTests;
       await fn();
       const post = resources();
+      // Same as for ops, resources of abandoned tests are ignored.
+      if (abandonedBefore > 0 || pendingAbandonedTests > 0) {
+        return;
+      }
 
       const preStr = JSON.stringify(pre, null, 2);
       const postStr = JSON.stringify(post, null, 2);
@@ -80,8 +93,110 @@ finishing test case.`;
     };
   }
 
+  // Number of tests that timed out and whose function hasn't settled yet.
+  // The sanitizers of other tests are skipped while there are any.
+  let pendingAbandonedTests = 0;
+
+  class TestTimeoutError extends Error {
+    constructor(timeout) {
+      super(`Test timed out after ${timeout}ms`);
+      this.name = "Error";
+    }
+  }
+
+  // Wrap test function so that it fails if it doesn't finish within
+  // `timeout` milliseconds. The timer lives outside of the op sanitizer
+  // and is awaited before returning, so it isn't reported as a leaking op.
+  // A test that timed out is abandoned: its pending ops keep running, but
+  // the runner moves on to the next test.
+  function assertTimeout(fn, timeout) {
+    return async function timeoutSanitizer() {
+      let settled = false;
+      let timedOut = false;
+      const timer = sendAsync("op_test_timer_start", { timeout });
+      const promise = fn();
+      try {
+        await Promise.race([
+          promise,
+          timer.then(() => {
+            if (!settled) {
+              timedOut = true;
+              throw new TestTimeoutError(timeout);
+            }
+          }),
+        ]);
+      } finally {
+        settled = true;
+        sendSync("op_test_timer_stop");
+        await timer;
+        if (timedOut) {
+          pendingAbandonedTests++;
+          promise
+            .catch(() => {})
+            .finally(() => {
+              pendingAbandonedTests--;
+            });
+        }
+      }
+    };
+  }
+
+  // Token required by the ops which change the permissions of a test. It is
+  // issued once while bootstrapping the runtime, before user code runs.
+  let permissionsToken;
+
+  function issuePermissionsToken() {
+    permissionsToken = sendSync("op_issue_test_permissions_token").token;
+  }
+
+  // Set once a test with narrowed permissions times out. The abandoned test
+  // would keep running with the original permissions, so no more tests are
+  // started after it.
+  let abandonedPermissionsTest = false;
+
+  // Wrap test function so that it runs with the given permissions, which may
+  // only narrow the permissions of the process. The original permissions are
+  // restored once the test finishes or times out.
+  function withPermissions(fn, permissions) {
+    return async function permissionsSanitizer() {
+      sendSync("op_pledge_test_permissions", {
+        token: permissionsToken,
+        permissions: parsePermissions(permissions) ?? {},
+      });
+      try {
+        await fn();
+      } catch (e) {
+        if (e instanceof TestTimeoutError) {
+          abandonedPermissionsTest = true;
+        }
+        throw e;
+      } finally {
+        sendSync("op_restore_test_permissions", { token: permissionsToken });
+      }
+    };
+  }
+
   const TEST_REGISTRY = [];
 
+  // Returns the URL of the module which registered a test: the outermost
+  // caller of `test()` outside of the runtime, so that tests registered
+  // through a helper function are attributed to the module calling it.
+  function getCallerModule() {
+    const { prepareStackTrace } = Error;
+    Error.prepareStackTrace = (_error, callSites) => callSites;
+    try {
+      const callSites = new Error().stack;
+      return callSites
+        .map((callSite) => callSite.getFileName())
+        .filter((fileName) =>
+          fileName != null && !fileName.startsWith("deno:")
+        )
+        .pop();
+    } finally {
+      Error.prepareStackTrace = prepareStackTrace;
+    }
+  }
+
   // Main test function provided by Deno, as you can see it merely
   // creates a new object with "name" and "fn" fields.
   function test(
@@ -114,6 +229,13 @@ finishing test case.`;
       testDef = { ...defaults, ...t };
     }
 
+    if (
+      testDef.timeout != null &&
+      !(Number.isFinite(testDef.timeout) && testDef.timeout >= 0)
+    ) {
+      throw new TypeError("The test timeout must be a non-negative number");
+    }
+
     if (testDef.sanitizeOps) {
       testDef.fn = assertOps(testDef.fn);
     }
@@ -122,6 +244,16 @@ finishing test case.`;
       testDef.fn = assertResources(testDef.fn);
     }
 
+    if (testDef.timeout != null) {
+      testDef.fn = assertTimeout(testDef.fn, testDef.timeout);
+    }
+
+    if (testDef.permissions != null) {
+      testDef.fn = withPermissions(testDef.fn, testDef.permissions);
+    }
+
+    testDef.module = getCallerModule();
+
     TEST_REGISTRY.push(testDef);
   }
 
@@ -194,6 +326,76 @@ finishing test case.`;
 
   exposeForTest("reportToConsole", reportToConsole);
 
+  // Stack traces are source mapped when they are prepared, so the stack of
+  // the error refers to the original source of the module.
+  function serializeError(error) {
+    const isError = error instanceof Error;
+    return {
+      name: isError ? error.name : "Error",
+      message: isError ? error.message : String(error),
+      stack: isError ? error.stack : undefined,
+      formatted: inspectArgs([error]),
+    };
+  }
+
+  // Converts a message of the test runner to the format expected by
+  // `op_post_test_message`, which hands it to the reporters of `deno test`.
+  function serializeMessage(message) {
+    if (message.start != null) {
+      return { start: { tests: message.start.tests.length } };
+    } else if (message.testStart != null) {
+      return { testStart: { name: message.testStart.name } };
+    } else if (message.testEnd != null) {
+      const { name, status, duration, error } = message.testEnd;
+      return {
+        testEnd: {
+          name,
+          status,
+          duration,
+          error: error != null ? serializeError(error) : undefined,
+        },
+      };
+    } else {
+      const end = { ...message.end };
+      delete end.results;
+      return { end };
+    }
+  }
+
+  // The tests of a serial run come from several modules, so the module of a
+  // test is posted along with its start, and applies until the next one.
+  function postMessageToHost(message) {
+    sendSync("op_post_test_message", {
+      message: serializeMessage(message),
+      module: message.testStart?.module,
+    });
+  }
+
+  // Mulberry32, a small PRNG whose sequence only depends on the seed, so that
+  // a shuffled order of tests can be replayed.
+  function createRandom(seed) {
+    let state = seed >>> 0;
+    return () => {
+      state = (state + 0x6d2b79f5) >>> 0;
+      let t = state;
+      t = Math.imul(t ^ (t >>> 15), t | 1);
+      t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+      return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+    };
+  }
+
+  // Fisher-Yates shuffle of `array` in place.
+  function shuffle(array, seed) {
+    const random = createRandom(seed);
+    for (let i = array.length - 1; i > 0; i--) {
+      const j = Math.floor(random() * (i + 1));
+      [array[i], array[j]] = [array[j], array[i]];
+    }
+    return array;
+  }
+
+  exposeForTest("shuffle", shuffle);
+
   // TODO: already implements AsyncGenerator<RunTestsMessage>, but add as "implements to class"
   // TODO: implements PromiseLike<RunTestsEndResult>
   class TestRunner {
@@ -203,6 +405,7 @@ finishing test case.`;
       tests,
       filterFn,
       failFast,
+      shuffleSeed,
     ) {
       this.stats = {
         filtered: 0,
@@ -217,6 +420,9 @@ finishing test case.`;
       this.#usedOnly = onlyTests.length > 0;
       const unfilteredTests = this.#usedOnly ? onlyTests : tests;
       this.testsToRun = unfilteredTests.filter(filterFn);
+      if (shuffleSeed != null) {
+        shuffle(this.testsToRun, shuffleSeed);
+      }
       this.stats.filtered = unfilteredTests.length - this.testsToRun.length;
     }
 
@@ -249,7 +455,10 @@ finishing test case.`;
         }
         results.push(endMessage);
         yield { testEnd: endMessage };
-        if (this.failFast && endMessage.error != null) {
+        if (
+          (this.failFast && endMessage.error != null) ||
+          abandonedPermissionsTest
+        ) {
           break;
         }
       }
@@ -301,10 +510,17 @@ finishing test case.`;
     skip = undefined,
     disableLog = false,
     reportToConsole: reportToConsole_ = true,
+    reportToHost = false,
     onMessage = undefined,
+    shuffle: shuffleSeed = undefined,
   } = {}) {
     const filterFn = createFilterFn(filter, skip);
-    const testRunner = new TestRunner(TEST_REGISTRY, filterFn, failFast);
+    const testRunner = new TestRunner(
+      TEST_REGISTRY,
+      filterFn,
+      failFast,
+      shuffleSeed,
+    );
 
     const originalConsole = globalThis.console;
 
@@ -321,6 +537,9 @@ finishing test case.`;
       if (reportToConsole_) {
         reportToConsole(message);
       }
+      if (reportToHost) {
+        postMessageToHost(message);
+      }
       if (message.end != null) {
         endMsg = message.end;
       }
@@ -341,5 +560,7 @@ finishing test case.`;
 
   window.__bootstrap.testing = {
     test,
+    createFilterFn,
+    issuePermissionsToken,
   };
 })(this);
@@ -22,25 +22,61 @@
     return sendAsync("op_start_tls", args);
   }
 
+  class TlsConn extends Conn {
+    #alpnProtocol = null;
+    #peerCertificates = [];
+
+    constructor(rid, remoteAddr, localAddr, handshake) {
+      super(rid, remoteAddr, localAddr);
+      this.#alpnProtocol = handshake.alpnProtocol;
+      this.#peerCertificates = handshake.peerCertificates;
+    }
+
+    get alpnProtocol() {
+      return this.#alpnProtocol;
+    }
+
+    get peerCertificates() {
+      return this.#peerCertificates.slice();
+    }
+  }
+
   async function connectTls({
     port,
     hostname = "127.0.0.1",
     transport = "tcp",
     certFile = undefined,
+    caCerts = undefined,
+    certChainFile = undefined,
+    certChain = undefined,
+    privateKeyFile = undefined,
+    privateKey = undefined,
+    alpnProtocols = undefined,
   }) {
     const res = await opConnectTls({
       port,
       hostname,
       transport,
       certFile,
+      caCerts,
+      certChainFile,
+      certChain,
+      privateKeyFile,
+      privateKey,
+      alpnProtocols,
     });
-    return new Conn(res.rid, res.remoteAddr, res.localAddr);
+    return new TlsConn(res.rid, res.remoteAddr, res.localAddr, res.handshake);
   }
 
   class TLSListener extends Listener {
     async accept() {
       const res = await opAcceptTLS(this.rid);
-      return new Conn(res.rid, res.remoteAddr, res.localAddr);
+      return new TlsConn(
+        res.rid,
+        res.remoteAddr,
+        res.localAddr,
+        res.handshake,
+      );
     }
   }
 
@@ -48,29 +84,56 @@
     port,
     certFile,
     keyFile,
+    cert,
+    key,
     hostname = "0.0.0.0",
     transport = "tcp",
+    alpnProtocols,
+    clientCaFile,
+    clientCaCerts,
+    requireClientCert = false,
   }) {
     const res = opListenTls({
       port,
       certFile,
       keyFile,
+      cert,
+      key,
       hostname,
       transport,
+      alpnProtocols,
+      clientCaFile,
+      clientCaCerts,
+      requireClientCert,
     });
     return new TLSListener(res.rid, res.localAddr);
   }
 
   async function startTls(
     conn,
-    { hostname = "127.0.0.1", certFile } = {},
+    {
+      hostname = "127.0.0.1",
+      certFile,
+      caCerts,
+      certChainFile,
+      certChain,
+      privateKeyFile,
+      privateKey,
+      alpnProtocols,
+    } = {},
   ) {
     const res = await opStartTls({
       rid: conn.rid,
       hostname,
       certFile,
+      caCerts,
+      certChainFile,
+      certChain,
+      privateKeyFile,
+      privateKey,
+      alpnProtocols,
     });
-    return new Conn(res.rid, res.remoteAddr, res.localAddr);
+    return new TlsConn(res.rid, res.remoteAddr, res.localAddr, res.handshake);
   }
 
   window.__bootstrap.tls = {
@@ -78,5 +141,6 @@
     listenTls,
     connectTls,
     TLSListener,
+    TlsConn,
   };
 })(this);
@@ -0,0 +1,264 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+((window) => {
+  const { gray, green, italic, red, yellow } = window.__bootstrap.colors;
+  const { exit } = window.__bootstrap.os;
+  const { Console, inspectArgs } = window.__bootstrap.console;
+  const { stdout } = window.__bootstrap.files;
+  const { exposeForTest } = window.__bootstrap.internals;
+  const { sendSync } = window.__bootstrap.dispatchJson;
+  const { createFilterFn } = window.__bootstrap.testing;
+
+  const disabledConsole = new Console(() => {});
+
+  const BENCH_REGISTRY = [];
+
+  // Main benchmark function provided by Deno, it merely creates a new object
+  // with "name" and "fn" fields and the number of warmup and measured
+  // iterations.
+  function bench(
+    b,
+    fn,
+  ) {
+    let benchDef;
+    const defaults = {
+      ignore: false,
+      only: false,
+      warmup: 10,
+      iterations: 100,
+    };
+
+    if (typeof b === "string") {
+      if (!fn || typeof fn != "function") {
+        throw new TypeError("Missing benchmark function");
+      }
+      if (!b) {
+        throw new TypeError("The benchmark name can't be empty");
+      }
+      benchDef = { fn: fn, name: b, ...defaults };
+    } else {
+      if (!b.fn) {
+        throw new TypeError("Missing benchmark function");
+      }
+      if (!b.name) {
+        throw new TypeError("The benchmark name can't be empty");
+      }
+      benchDef = { ...defaults, ...b };
+    }
+
+    if (!Number.isInteger(benchDef.warmup) || benchDef.warmup < 0) {
+      throw new TypeError(
+        "The number of warmups must be a non-negative integer",
+      );
+    }
+    if (!Number.isInteger(benchDef.iterations) || benchDef.iterations < 1) {
+      throw new TypeError(
+        "The number of iterations must be a positive integer",
+      );
+    }
+
+    BENCH_REGISTRY.push(benchDef);
+  }
+
+  // Same clock as `performance.now()`, but never rounded to 2ms: `deno bench`
+  // registers `op_bench_now` whether or not --allow-hrtime is passed.
+  function now() {
+    const res = sendSync("op_bench_now");
+    return res.seconds * 1e3 + res.subsecNanos / 1e6;
+  }
+
+  // Returns the sample at the given percentile of the sorted samples, using
+  // the nearest-rank method.
+  function percentile(sortedSamples, p) {
+    const rank = Math.ceil((p / 100) * sortedSamples.length);
+    const index = Math.min(Math.max(rank, 1), sortedSamples.length) - 1;
+    return sortedSamples[index];
+  }
+
+  function computeStats(samples) {
+    const sorted = [...samples].sort((a, b) => a - b);
+    const total = samples.reduce((sum, sample) => sum + sample, 0);
+    return {
+      mean: total / samples.length,
+      min: sorted[0],
+      max: sorted[sorted.length - 1],
+      p75: percentile(sorted, 75),
+      p99: percentile(sorted, 99),
+    };
+  }
+
+  exposeForTest("computeBenchStats", computeStats);
+
+  async function measure({ fn, warmup, iterations }) {
+    for (let i = 0; i < warmup; i++) {
+      await fn();
+    }
+    const samples = [];
+    for (let i = 0; i < iterations; i++) {
+      const start = now();
+      await fn();
+      samples.push(now() - start);
+    }
+    return computeStats(samples);
+  }
+
+  const encoder = new TextEncoder();
+
+  function log(msg, noNewLine = false) {
+    if (!noNewLine) {
+      msg += "\n";
+    }
+
+    // Using `stdout` here because it doesn't force new lines
+    // compared to `console.log`; `core.print` on the other hand
+    // is line-buffered and doesn't output message without newline
+    stdout.writeSync(encoder.encode(msg));
+  }
+
+  function formatTime(ms) {
+    return `${ms.toFixed(3)}ms`;
+  }
+
+  function formatDuration(time = 0) {
+    const timeStr = `(${time}ms)`;
+    return gray(italic(timeStr));
+  }
+
+  function reportToConsole(message) {
+    if (message.start != null) {
+      log(`running ${message.start.benchmarks.length} benchmarks`);
+    } else if (message.benchStart != null) {
+      log(`bench ${message.benchStart.name} ... `, true);
+    } else if (message.benchEnd != null) {
+      const { status, iterations, stats } = message.benchEnd;
+      if (status === "measured") {
+        log(
+          `${iterations} iterations ${formatTime(stats.mean)}/iter ` +
+            gray(
+              `(min ${formatTime(stats.min)}, max ${formatTime(stats.max)}, ` +
+                `p75 ${formatTime(stats.p75)}, p99 ${formatTime(stats.p99)})`,
+            ),
+        );
+      } else if (status === "failed") {
+        log(red("FAILED"));
+      } else {
+        log(yellow("ignored"));
+      }
+    } else if (message.end != null) {
+      const failures = message.end.results.filter((r) => r.error != null);
+      if (failures.length > 0) {
+        log(`\nfailures:\n`);
+
+        for (const { name, error } of failures) {
+          log(name);
+          log(inspectArgs([error]));
+          log("");
+        }
+      }
+      const { measured, failed, ignored, filtered, duration } = message.end;
+      log(
+        `\nbench result: ${failed ? red("FAILED") : green("ok")}. ` +
+          `${measured} measured; ${failed} failed; ${ignored} ignored; ` +
+          `${filtered} filtered out ${formatDuration(duration)}\n`,
+      );
+    }
+  }
+
+  function reportToJson(message) {
+    if (message.end == null) {
+      return;
+    }
+    const benchmarks = message.end.results.map(
+      ({ name, status, warmup, iterations, stats, error }) => ({
+        name,
+        status,
+        warmup,
+        iterations,
+        ...stats,
+        error: error != null ? inspectArgs([error]) : undefined,
+      }),
+    );
+    log(JSON.stringify({ benchmarks }, null, 2));
+  }
+
+  async function* runBenchmarksIter(benchmarks, filterFn) {
+    const onlyBenchmarks = benchmarks.filter(({ only }) => only);
+    const unfiltered = onlyBenchmarks.length > 0 ? onlyBenchmarks : benchmarks;
+    const benchmarksToRun = unfiltered.filter(filterFn);
+    const stats = {
+      measured: 0,
+      failed: 0,
+      ignored: 0,
+      filtered: unfiltered.length - benchmarksToRun.length,
+    };
+
+    yield { start: { benchmarks: benchmarksToRun } };
+
+    const results = [];
+    const suiteStart = +new Date();
+    for (const benchmark of benchmarksToRun) {
+      const { name, warmup, iterations } = benchmark;
+      const endMessage = { name, warmup, iterations };
+      yield { benchStart: { name } };
+      if (benchmark.ignore) {
+        endMessage.status = "ignored";
+        stats.ignored++;
+      } else {
+        try {
+          endMessage.stats = await measure(benchmark);
+          endMessage.status = "measured";
+          stats.measured++;
+        } catch (err) {
+          endMessage.status = "failed";
+          endMessage.error = err;
+          stats.failed++;
+        }
+      }
+      results.push(endMessage);
+      yield { benchEnd: endMessage };
+    }
+
+    const duration = +new Date() - suiteStart;
+    yield { end: { ...stats, duration, results } };
+  }
+
+  async function runBenchmarks({
+    exitOnFail = true,
+    filter = undefined,
+    json = false,
+  } = {}) {
+    const filterFn = createFilterFn(filter);
+
+    const originalConsole = globalThis.console;
+    if (json) {
+      // Keep the output of the benchmarks out of the JSON report.
+      globalThis.console = disabledConsole;
+    }
+
+    let endMsg;
+    for await (const message of runBenchmarksIter(BENCH_REGISTRY, filterFn)) {
+      if (json) {
+        reportToJson(message);
+      } else {
+        reportToConsole(message);
+      }
+      if (message.end != null) {
+        endMsg = message.end;
+      }
+    }
+
+    globalThis.console = originalConsole;
+
+    if (endMsg.failed > 0 && exitOnFail) {
+      exit(1);
+    }
+
+    return endMsg;
+  }
+
+  exposeForTest("runBenchmarks", runBenchmarks);
+
+  window.__bootstrap.bench = {
+    bench,
+  };
+})(this);
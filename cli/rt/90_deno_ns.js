@@ -85,6 +85,7 @@ __bootstrap.denoNs = {
 };
 
 __bootstrap.denoNsUnstable = {
+  bench: __bootstrap.bench.bench,
   signal: __bootstrap.signals.signal,
   signals: __bootstrap.signals.signals,
   Signal: __bootstrap.signals.Signal,
@@ -110,7 +111,9 @@ __bootstrap.denoNsUnstable = {
   listen: __bootstrap.netUnstable.listen,
   connect: __bootstrap.netUnstable.connect,
   listenDatagram: __bootstrap.netUnstable.listenDatagram,
+  resolveDns: __bootstrap.netUnstable.resolveDns,
   startTls: __bootstrap.tls.startTls,
+  serveHttp: __bootstrap.http.serveHttp,
   fstatSync: __bootstrap.fs.fstatSync,
   fstat: __bootstrap.fs.fstat,
   ftruncateSync: __bootstrap.fs.ftruncateSync,
@@ -33,6 +33,7 @@ delete Object.prototype.__proto__;
   const webSocket = window.__bootstrap.webSocket;
   const request = window.__bootstrap.request;
   const fetch = window.__bootstrap.fetch;
+  const testing = window.__bootstrap.testing;
   const denoNs = window.__bootstrap.denoNs;
   const denoNsUnstable = window.__bootstrap.denoNsUnstable;
   const errors = window.__bootstrap.errors.errors;
@@ -57,8 +58,6 @@ delete Object.prototype.__proto__;
     }
   }
 
-  const encoder = new TextEncoder();
-
   function workerClose() {
     if (isClosing) {
       return;
@@ -73,14 +72,21 @@ delete Object.prototype.__proto__;
   const onmessage = () => {};
   const onerror = () => {};
 
-  function postMessage(data) {
-    const dataJson = JSON.stringify(data);
-    const dataIntArray = encoder.encode(dataJson);
-    opPostMessage(dataIntArray);
+  function postMessage(message, transferOrOptions) {
+    opPostMessage(worker.serializeMessage(message, transferOrOptions));
   }
 
   let isClosing = false;
-  async function workerMessageRecvCallback(data) {
+  async function workerMessageRecvCallback(inboxId) {
+    let data;
+    try {
+      data = worker.deserializeMessage(inboxId);
+    } catch (e) {
+      globalThis.dispatchEvent(
+        new MessageEvent("messageerror", { cancelable: false }),
+      );
+      return;
+    }
     const msgEvent = new MessageEvent("message", {
       cancelable: false,
       data,
@@ -128,8 +134,12 @@ delete Object.prototype.__proto__;
     }
   }
 
-  function opPostMessage(data) {
-    dispatchJson.sendSync("op_worker_post_message", {}, data);
+  function opPostMessage(message) {
+    dispatchJson.sendSync(
+      "op_worker_post_message",
+      { arrayBuffers: message.arrayBuffers.length },
+      ...worker.messageBuffers(message),
+    );
   }
 
   function opCloseWorker() {
@@ -324,6 +334,7 @@ delete Object.prototype.__proto__;
       runtimeStart();
 
     registerErrors();
+    testing.issuePermissionsToken();
 
     const finalDenoNs = {
       core,
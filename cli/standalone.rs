@@ -0,0 +1,217 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+use crate::file_fetcher::SourceFile;
+use crate::flags;
+use crate::flags::Flags;
+use crate::fs as deno_fs;
+use crate::global_state::GlobalState;
+use crate::msg::MediaType;
+use crate::tokio_util;
+use crate::worker::MainWorker;
+use deno_core::ErrBox;
+use deno_core::ModuleSpecifier;
+use serde::Deserialize;
+use serde::Serialize;
+use std::convert::TryInto;
+use std::env::current_exe;
+use std::fs::File;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::PathBuf;
+
+const MAGIC_TRAILER: &[u8; 8] = b"d3n0l4nd";
+const TRAILER_LEN: usize = 24;
+const STANDALONE_SPECIFIER: &str = "./__$deno$standalone.js";
+
+/// Data recorded by `deno compile` next to the embedded bundle.
+#[derive(Deserialize, Serialize)]
+struct Metadata {
+  /// Arguments that are passed to `deno run` when the binary starts,
+  /// eg. `["--allow-net", "--unstable"]`.
+  args: Vec<String>,
+}
+
+/// Produces a standalone executable by appending the bundled program and
+/// its metadata to a copy of the currently running Deno binary.
+///
+/// The layout of the resulting file is:
+///
+/// ```text
+/// <deno binary> <bundle> <metadata json> <trailer>
+/// ```
+///
+/// where the trailer is the 8 byte magic string followed by two big endian
+/// u64 offsets pointing to the start of the bundle and of the metadata.
+pub fn create_standalone_binary(
+  source_code: Vec<u8>,
+  flags: &Flags,
+  output: PathBuf,
+) -> Result<(), ErrBox> {
+  let mut args = flags.to_permission_args();
+  if flags.unstable {
+    args.push("--unstable".to_string());
+  }
+  let metadata = serde_json::to_vec(&Metadata { args })?;
+
+  let original_binary_path = current_exe()?;
+  let mut final_bin = std::fs::read(original_binary_path)?;
+
+  // A binary produced by `deno compile` can itself be used to compile other
+  // programs, strip the previous payload so they don't accumulate.
+  if let Some(original_len) = find_payload_start(&final_bin) {
+    final_bin.truncate(original_len);
+  }
+
+  let source_pos = final_bin.len() as u64;
+  let metadata_pos = source_pos + source_code.len() as u64;
+  final_bin.extend(source_code);
+  final_bin.extend(metadata);
+  final_bin.extend(MAGIC_TRAILER);
+  final_bin.extend(&source_pos.to_be_bytes());
+  final_bin.extend(&metadata_pos.to_be_bytes());
+
+  if output.is_dir() {
+    return Err(ErrBox::from(Error::new(
+      ErrorKind::Other,
+      format!(
+        "Could not compile: {} is a directory.",
+        output.to_string_lossy()
+      ),
+    )));
+  }
+
+  deno_fs::write_file(&output, final_bin, 0o777)?;
+  Ok(())
+}
+
+/// Returns the length of the original Deno binary if `bin` carries a
+/// payload written by `create_standalone_binary`.
+fn find_payload_start(bin: &[u8]) -> Option<usize> {
+  if bin.len() < TRAILER_LEN {
+    return None;
+  }
+  let trailer = &bin[bin.len() - TRAILER_LEN..];
+  let (magic, rest) = trailer.split_at(MAGIC_TRAILER.len());
+  if magic != MAGIC_TRAILER {
+    return None;
+  }
+  let source_pos = u64::from_be_bytes(rest[..8].try_into().unwrap());
+  Some(source_pos as usize)
+}
+
+/// Checks if the currently running binary was produced by `deno compile`.
+/// If so the embedded bundle and the `Flags` it should be run with are
+/// returned, otherwise `None`.
+pub fn extract_standalone(
+  args: Vec<String>,
+) -> Result<Option<(String, Flags)>, ErrBox> {
+  let current_exe_path = current_exe()?;
+  let mut current_exe = File::open(current_exe_path)?;
+  let len = current_exe.metadata()?.len();
+  if len < TRAILER_LEN as u64 {
+    return Ok(None);
+  }
+
+  current_exe.seek(SeekFrom::End(-(TRAILER_LEN as i64)))?;
+  let mut trailer = [0; TRAILER_LEN];
+  current_exe.read_exact(&mut trailer)?;
+  let (magic, rest) = trailer.split_at(MAGIC_TRAILER.len());
+  if magic != MAGIC_TRAILER {
+    return Ok(None);
+  }
+  let (source_pos, metadata_pos) = rest.split_at(8);
+  let source_pos = u64::from_be_bytes(source_pos.try_into().unwrap());
+  let metadata_pos = u64::from_be_bytes(metadata_pos.try_into().unwrap());
+  let trailer_pos = len - TRAILER_LEN as u64;
+  if source_pos > metadata_pos || metadata_pos > trailer_pos {
+    return Err(ErrBox::from(Error::new(
+      ErrorKind::InvalidData,
+      "Corrupted standalone binary",
+    )));
+  }
+
+  current_exe.seek(SeekFrom::Start(source_pos))?;
+  let mut source_code = String::new();
+  current_exe
+    .by_ref()
+    .take(metadata_pos - source_pos)
+    .read_to_string(&mut source_code)?;
+  let mut metadata = Vec::new();
+  current_exe
+    .take(trailer_pos - metadata_pos)
+    .read_to_end(&mut metadata)?;
+  let metadata: Metadata = serde_json::from_slice(&metadata)?;
+
+  let mut run_args = vec!["deno".to_string(), "run".to_string()];
+  run_args.extend(metadata.args);
+  run_args.push(STANDALONE_SPECIFIER.to_string());
+  let mut flags = flags::flags_from_vec_safe(run_args)
+    .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+  flags.argv = args.into_iter().skip(1).collect();
+
+  Ok(Some((source_code, flags)))
+}
+
+/// If the currently running binary was produced by `deno compile`, runs the
+/// embedded program and exits the process. Returns `Ok(())` otherwise.
+pub fn try_run_standalone_binary(args: Vec<String>) -> Result<(), ErrBox> {
+  if let Some((source_code, flags)) = extract_standalone(args)? {
+    tokio_util::run_basic(run(source_code, flags))?;
+    std::process::exit(0);
+  }
+  Ok(())
+}
+
+async fn run(source_code: String, flags: Flags) -> Result<(), ErrBox> {
+  let global_state = GlobalState::new(flags)?;
+  let main_module =
+    ModuleSpecifier::resolve_url_or_path(STANDALONE_SPECIFIER).unwrap();
+  let mut worker = MainWorker::create(&global_state, main_module.clone())?;
+  let main_module_url = main_module.as_url().to_owned();
+  // Create a dummy source file.
+  let source_file = SourceFile {
+    filename: main_module_url.to_file_path().unwrap(),
+    url: main_module_url,
+    types_header: None,
+    media_type: MediaType::JavaScript,
+    source_code: source_code.into_bytes().into(),
+  };
+  // Save our fake file into file fetcher cache
+  // to allow module access by TS compiler
+  global_state
+    .file_fetcher
+    .save_source_file_in_cache(&main_module, source_file);
+  debug!("main_module {}", main_module);
+  worker.execute_module(&main_module).await?;
+  worker.execute("window.dispatchEvent(new Event('load'))")?;
+  (&mut *worker).await?;
+  worker.execute("window.dispatchEvent(new Event('unload'))")?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn find_payload_start_without_trailer() {
+    assert_eq!(find_payload_start(b""), None);
+    assert_eq!(find_payload_start(&[0; 64]), None);
+  }
+
+  #[test]
+  fn find_payload_start_with_trailer() {
+    let mut bin = b"binary".to_vec();
+    let source_pos = bin.len() as u64;
+    bin.extend(b"console.log(1)");
+    let metadata_pos = bin.len() as u64;
+    bin.extend(b"{\"args\":[]}");
+    bin.extend(MAGIC_TRAILER);
+    bin.extend(&source_pos.to_be_bytes());
+    bin.extend(&metadata_pos.to_be_bytes());
+    assert_eq!(find_payload_start(&bin), Some(6));
+  }
+}
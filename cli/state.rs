@@ -12,6 +12,7 @@ use crate::tsc::TargetLib;
 use crate::web_worker::WebWorkerHandle;
 use deno_core::BufVec;
 use deno_core::ErrBox;
+use deno_core::Inbox;
 use deno_core::ModuleLoadId;
 use deno_core::ModuleLoader;
 use deno_core::ModuleSpecifier;
@@ -46,8 +47,18 @@ pub struct State {
   pub import_map: Option<ImportMap>,
   pub metrics: RefCell<Metrics>,
   pub global_timer: RefCell<GlobalTimer>,
+  /// Fails the running `Deno.test()` when its timeout is exceeded.
+  pub test_timer: RefCell<GlobalTimer>,
+  /// Permissions to restore once the running `Deno.test()`, which has
+  /// narrowed permissions, finishes.
+  pub test_saved_permissions: RefCell<Option<Permissions>>,
+  /// Token required to change the permissions of a `Deno.test()`, so that
+  /// user code can't restore the permissions a test was narrowed from.
+  pub test_permissions_token: RefCell<Option<String>>,
   pub workers: RefCell<HashMap<u32, (JoinHandle<()>, WebWorkerHandle)>>,
   pub next_worker_id: Cell<u32>,
+  /// Messages from workers, or from the host, waiting to be deserialized.
+  pub inbox: Inbox,
   pub start_time: Instant,
   pub seeded_rng: Option<RefCell<StdRng>>,
   pub target_lib: TargetLib,
@@ -194,8 +205,12 @@ impl State {
       import_map: maybe_import_map,
       metrics: Default::default(),
       global_timer: Default::default(),
+      test_timer: Default::default(),
+      test_saved_permissions: Default::default(),
+      test_permissions_token: Default::default(),
       workers: Default::default(),
       next_worker_id: Default::default(),
+      inbox: Default::default(),
       start_time: Instant::now(),
       seeded_rng: fl.seed.map(|v| StdRng::seed_from_u64(v).into()),
       target_lib: TargetLib::Main,
@@ -224,8 +239,12 @@ impl State {
       import_map: None,
       metrics: Default::default(),
       global_timer: Default::default(),
+      test_timer: Default::default(),
+      test_saved_permissions: Default::default(),
+      test_permissions_token: Default::default(),
       workers: Default::default(),
       next_worker_id: Default::default(),
+      inbox: Default::default(),
       start_time: Instant::now(),
       seeded_rng: fl.seed.map(|v| StdRng::seed_from_u64(v).into()),
       target_lib: TargetLib::Worker,
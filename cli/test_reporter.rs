@@ -0,0 +1,402 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+use crate::colors;
+use crate::flags::TestReporterKind;
+use crate::test_runner::TestEvent;
+use crate::test_runner::TestMessage;
+use crate::test_runner::TestResult;
+use crate::test_runner::TestStatus;
+use crate::test_runner::TestSummary;
+use deno_core::ErrBox;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::PathBuf;
+use url::Url;
+
+/// Consumes the events posted by the JS test runner through
+/// `op_post_test_message`.
+pub trait TestReporter {
+  fn visit_event(&mut self, event: &TestEvent) -> Result<(), ErrBox>;
+
+  /// Called once all test modules have completed, with the totals of all
+  /// modules and the duration of the run in milliseconds.
+  fn done(
+    &mut self,
+    summary: &TestSummary,
+    duration: u64,
+  ) -> Result<(), ErrBox>;
+}
+
+/// Writes one JSON object per line: one for every completed test, followed
+/// by the totals of the run.
+pub struct JsonTestReporter {
+  out: Box<dyn Write>,
+}
+
+impl JsonTestReporter {
+  pub fn new(out: Box<dyn Write>) -> Self {
+    Self { out }
+  }
+}
+
+impl TestReporter for JsonTestReporter {
+  fn visit_event(&mut self, event: &TestEvent) -> Result<(), ErrBox> {
+    if let TestMessage::TestEnd(result) = &event.message {
+      let error = result.error.as_ref().map(|error| {
+        json!({
+          "name": error.name,
+          "message": error.message,
+          "stack": error.stack,
+        })
+      });
+      let line = json!({
+        "type": "test",
+        "module": event.module.as_str(),
+        "name": result.name,
+        "status": result.status,
+        "duration": result.duration,
+        "error": error,
+      });
+      writeln!(self.out, "{}", line)?;
+    }
+    Ok(())
+  }
+
+  fn done(
+    &mut self,
+    summary: &TestSummary,
+    duration: u64,
+  ) -> Result<(), ErrBox> {
+    let line = json!({
+      "type": "summary",
+      "passed": summary.passed,
+      "failed": summary.failed,
+      "ignored": summary.ignored,
+      "measured": summary.measured,
+      "filtered": summary.filtered,
+      "usedOnly": summary.used_only,
+      "duration": duration,
+    });
+    writeln!(self.out, "{}", line)?;
+    self.out.flush()?;
+    Ok(())
+  }
+}
+
+/// Writes a JUnit XML report, with a test suite per module, once all modules
+/// have completed.
+pub struct JunitTestReporter {
+  out: Box<dyn Write>,
+  suites: Vec<(Url, Vec<TestResult>)>,
+}
+
+impl JunitTestReporter {
+  pub fn new(out: Box<dyn Write>) -> Self {
+    Self {
+      out,
+      suites: vec![],
+    }
+  }
+
+  /// Returns the results of the suite of `module`, which is created once its
+  /// first test ends: the tests of a serial run are started all at once, and
+  /// the tests of different modules are interleaved when shuffled.
+  fn suite_mut(&mut self, module: &Url) -> &mut Vec<TestResult> {
+    match self.suites.iter().position(|(m, _)| m == module) {
+      Some(index) => &mut self.suites[index].1,
+      None => {
+        self.suites.push((module.clone(), vec![]));
+        &mut self.suites.last_mut().unwrap().1
+      }
+    }
+  }
+}
+
+/// Escapes `s` for use in XML text and attribute values. Color codes and
+/// other characters which are not allowed in XML 1.0 documents, even as
+/// character references, are removed.
+fn escape_xml(s: &str) -> String {
+  let s = colors::strip_ansi_codes(s);
+  let mut escaped = String::with_capacity(s.len());
+  for c in s.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&apos;"),
+      '\t' | '\n' | '\r' => escaped.push(c),
+      '\u{0}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => {}
+      _ => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+fn format_seconds(duration: u64) -> String {
+  format!("{:.3}", duration as f64 / 1000.0)
+}
+
+impl TestReporter for JunitTestReporter {
+  fn visit_event(&mut self, event: &TestEvent) -> Result<(), ErrBox> {
+    if let TestMessage::TestEnd(result) = &event.message {
+      self.suite_mut(&event.module).push(result.clone());
+    }
+    Ok(())
+  }
+
+  fn done(
+    &mut self,
+    summary: &TestSummary,
+    duration: u64,
+  ) -> Result<(), ErrBox> {
+    let out = &mut self.out;
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+      out,
+      r#"<testsuites name="deno test" tests="{}" failures="{}" skipped="{}" time="{}">"#,
+      summary.passed + summary.failed + summary.ignored,
+      summary.failed,
+      summary.ignored,
+      format_seconds(duration),
+    )?;
+    for (module, results) in &self.suites {
+      let count =
+        |status| results.iter().filter(|r| r.status == status).count();
+      let suite_duration: u64 = results.iter().map(|r| r.duration).sum();
+      writeln!(
+        out,
+        r#"  <testsuite name="{}" tests="{}" failures="{}" skipped="{}" time="{}">"#,
+        escape_xml(module.as_str()),
+        results.len(),
+        count(TestStatus::Failed),
+        count(TestStatus::Ignored),
+        format_seconds(suite_duration),
+      )?;
+      for result in results {
+        write!(
+          out,
+          r#"    <testcase name="{}" classname="{}" time="{}""#,
+          escape_xml(&result.name),
+          escape_xml(module.as_str()),
+          format_seconds(result.duration),
+        )?;
+        match (&result.status, &result.error) {
+          (TestStatus::Failed, Some(error)) => {
+            writeln!(out, ">")?;
+            writeln!(
+              out,
+              r#"      <failure message="{}" type="{}">{}</failure>"#,
+              escape_xml(&error.message),
+              escape_xml(&error.name),
+              escape_xml(error.stack.as_deref().unwrap_or(&error.message)),
+            )?;
+            writeln!(out, "    </testcase>")?;
+          }
+          (TestStatus::Ignored, _) => {
+            writeln!(out, ">")?;
+            writeln!(out, "      <skipped/>")?;
+            writeln!(out, "    </testcase>")?;
+          }
+          _ => writeln!(out, "/>")?,
+        }
+      }
+      writeln!(out, "  </testsuite>")?;
+    }
+    writeln!(out, "</testsuites>")?;
+    out.flush()?;
+    Ok(())
+  }
+}
+
+fn open_output(output: Option<PathBuf>) -> Result<Box<dyn Write>, ErrBox> {
+  match output {
+    Some(path) => Ok(Box::new(BufWriter::new(File::create(path)?))),
+    None => Ok(Box::new(std::io::stdout())),
+  }
+}
+
+/// Creates the reporter for `--reporter=kind`, which writes to `output` or
+/// to stdout. The pretty format is printed by the JS test runner, or by
+/// `PrettyTestReporter` for test modules running in their own isolates, so
+/// there is no reporter for it.
+pub fn create_reporter(
+  kind: TestReporterKind,
+  output: Option<PathBuf>,
+) -> Result<Option<Box<dyn TestReporter>>, ErrBox> {
+  match kind {
+    TestReporterKind::Pretty if output.is_some() => Err(ErrBox::type_error(
+      "--reporter-output requires --reporter=junit or --reporter=json",
+    )),
+    TestReporterKind::Pretty => Ok(None),
+    TestReporterKind::Junit => {
+      Ok(Some(Box::new(JunitTestReporter::new(open_output(output)?))))
+    }
+    TestReporterKind::Json => {
+      Ok(Some(Box::new(JsonTestReporter::new(open_output(output)?))))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_runner::TestError;
+  use std::cell::RefCell;
+  use std::rc::Rc;
+
+  /// A writer whose contents can be inspected after being moved into a
+  /// reporter.
+  #[derive(Clone, Default)]
+  struct SharedBuf(Rc<RefCell<Vec<u8>>>);
+
+  impl Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+      self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+      Ok(())
+    }
+  }
+
+  impl SharedBuf {
+    fn contents(&self) -> String {
+      String::from_utf8(self.0.borrow().clone()).unwrap()
+    }
+  }
+
+  fn events() -> Vec<TestEvent> {
+    let module = Url::parse("file:///a_test.ts").unwrap();
+    vec![
+      TestEvent {
+        module: module.clone(),
+        message: TestMessage::Start { tests: 2 },
+      },
+      TestEvent {
+        module: module.clone(),
+        message: TestMessage::TestEnd(TestResult {
+          name: "ok".to_string(),
+          status: TestStatus::Passed,
+          duration: 5,
+          error: None,
+        }),
+      },
+      TestEvent {
+        module,
+        message: TestMessage::TestEnd(TestResult {
+          name: "a < b".to_string(),
+          status: TestStatus::Failed,
+          duration: 1500,
+          error: Some(TestError {
+            name: "AssertionError".to_string(),
+            message: "fail".to_string(),
+            stack: Some(
+              "AssertionError: fail\n    at a_test.ts:3:9".to_string(),
+            ),
+            formatted: "AssertionError: fail".to_string(),
+          }),
+        }),
+      },
+    ]
+  }
+
+  fn summary() -> TestSummary {
+    TestSummary {
+      passed: 1,
+      failed: 1,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn json_reporter() {
+    let buf = SharedBuf::default();
+    let mut reporter = JsonTestReporter::new(Box::new(buf.clone()));
+    for event in events() {
+      reporter.visit_event(&event).unwrap();
+    }
+    reporter.done(&summary(), 1505).unwrap();
+
+    let lines: Vec<serde_json::Value> = buf
+      .contents()
+      .lines()
+      .map(|line| serde_json::from_str(line).unwrap())
+      .collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["module"], "file:///a_test.ts");
+    assert_eq!(lines[0]["status"], "passed");
+    assert_eq!(lines[1]["error"]["message"], "fail");
+    assert_eq!(lines[2]["type"], "summary");
+    assert_eq!(lines[2]["failed"], 1);
+  }
+
+  #[test]
+  fn junit_reporter() {
+    let buf = SharedBuf::default();
+    let mut reporter = JunitTestReporter::new(Box::new(buf.clone()));
+    for event in events() {
+      reporter.visit_event(&event).unwrap();
+    }
+    reporter.done(&summary(), 1505).unwrap();
+
+    let xml = buf.contents();
+    assert!(xml.contains(
+      r#"<testsuite name="file:///a_test.ts" tests="2" failures="1" skipped="0" time="1.505">"#
+    ));
+    assert!(xml.contains(
+      r#"<testcase name="ok" classname="file:///a_test.ts" time="0.005"/>"#
+    ));
+    assert!(xml.contains(r#"<testcase name="a &lt; b""#));
+    assert!(xml.contains(
+      r#"<failure message="fail" type="AssertionError">AssertionError: fail"#
+    ));
+  }
+
+  #[test]
+  fn junit_reporter_interleaved_modules() {
+    let buf = SharedBuf::default();
+    let mut reporter = JunitTestReporter::new(Box::new(buf.clone()));
+    let mut events = events();
+    // The tests of a shuffled serial run interleave modules.
+    events.insert(
+      2,
+      TestEvent {
+        module: Url::parse("file:///b_test.ts").unwrap(),
+        message: TestMessage::TestEnd(TestResult {
+          name: "b".to_string(),
+          status: TestStatus::Ignored,
+          duration: 0,
+          error: None,
+        }),
+      },
+    );
+    for event in events {
+      reporter.visit_event(&event).unwrap();
+    }
+    reporter.done(&summary(), 1505).unwrap();
+
+    let xml = buf.contents();
+    assert!(xml.contains(
+      r#"<testsuite name="file:///a_test.ts" tests="2" failures="1" skipped="0" time="1.505">"#
+    ));
+    assert!(xml.contains(
+      r#"<testsuite name="file:///b_test.ts" tests="1" failures="0" skipped="1" time="0.000">"#
+    ));
+  }
+
+  #[test]
+  fn test_escape_xml() {
+    assert_eq!(
+      escape_xml("<a href=\"x\">'&'</a>"),
+      "&lt;a href=&quot;x&quot;&gt;&apos;&amp;&apos;&lt;/a&gt;"
+    );
+    assert_eq!(
+      escape_xml("\u{1b}[31mAssertionError\u{1b}[39m: fail"),
+      "AssertionError: fail"
+    );
+    assert_eq!(escape_xml("a\u{0}b\u{8}c\u{ffff}"), "abc");
+    assert_eq!(escape_xml("line\r\n\tindented"), "line\r\n\tindented");
+  }
+}
@@ -1,10 +1,40 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
+use crate::colors;
+use crate::file_fetcher::SourceFile;
+use crate::file_fetcher::TextDocument;
+use crate::file_watcher;
+use crate::flags::Flags;
 use crate::fs as deno_fs;
+use crate::global_state::GlobalState;
 use crate::installer::is_remote_url;
+use crate::module_graph::ModuleGraphLoader;
+use crate::msg::MediaType;
+use crate::ops;
+use crate::permissions::Permissions;
+use crate::test_reporter::TestReporter;
+use crate::tokio_util;
+use crate::worker::MainWorker;
 use deno_core::ErrBox;
+use deno_core::ModuleSpecifier;
+use futures::future::poll_fn;
+use futures::future::FutureExt;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Poll;
 use url::Url;
 
 fn is_supported(p: &Path) -> bool {
@@ -34,6 +64,16 @@ fn is_supported(p: &Path) -> bool {
 pub fn prepare_test_modules_urls(
   include: Vec<String>,
   root_path: &PathBuf,
+) -> Result<Vec<Url>, ErrBox> {
+  prepare_modules_urls(include, root_path, is_supported)
+}
+
+/// Expands directories of `include` to the files matching `is_supported`.
+/// Files and remote modules are included as is.
+pub fn prepare_modules_urls(
+  include: Vec<String>,
+  root_path: &Path,
+  is_supported: fn(&Path) -> bool,
 ) -> Result<Vec<Url>, ErrBox> {
   let (include_paths, include_urls): (Vec<String>, Vec<String>) =
     include.into_iter().partition(|n| !is_remote_url(n));
@@ -68,6 +108,35 @@ pub fn render_test_file(
   fail_fast: bool,
   quiet: bool,
   filter: Option<String>,
+  shuffle: Option<u64>,
+) -> String {
+  // Results are posted to `deno test` as well, which hands them to the
+  // reporters and decides the exit code, see `run_serial_test_worker()`.
+  let mut options = json!({
+    "failFast": fail_fast,
+    "reportToConsole": !quiet,
+    "reportToHost": true,
+    "exitOnFail": false,
+    "disableLog": quiet,
+  });
+  if let Some(filter) = filter {
+    options["filter"] = json!(filter);
+  }
+  if let Some(seed) = shuffle {
+    options["shuffle"] = json!(seed);
+  }
+  render_test_file_with_options(modules, options)
+}
+
+/// Shuffles the test modules in an order which only depends on `seed`.
+pub fn shuffle_modules(modules: &mut Vec<Url>, seed: u64) {
+  modules.sort();
+  modules.shuffle(&mut StdRng::seed_from_u64(seed));
+}
+
+fn render_test_file_with_options(
+  modules: Vec<Url>,
+  options: serde_json::Value,
 ) -> String {
   let mut test_file = "".to_string();
 
@@ -75,12 +144,6 @@ pub fn render_test_file(
     test_file.push_str(&format!("import \"{}\";\n", module.to_string()));
   }
 
-  let options = if let Some(filter) = filter {
-    json!({ "failFast": fail_fast, "reportToConsole": !quiet, "disableLog": quiet, "filter": filter })
-  } else {
-    json!({ "failFast": fail_fast, "reportToConsole": !quiet, "disableLog": quiet })
-  };
-
   let run_tests_cmd = format!(
     "// @ts-ignore\nDeno[Deno.internal].runTests({});\n",
     options
@@ -90,6 +153,502 @@ pub fn render_test_file(
   test_file
 }
 
+/// Saves a generated test file in the file fetcher cache, allowing module
+/// access by the TS compiler.
+pub fn save_test_file(
+  global_state: &GlobalState,
+  test_file_url: &Url,
+  test_file: String,
+) -> ModuleSpecifier {
+  let main_module =
+    ModuleSpecifier::resolve_url(&test_file_url.to_string()).unwrap();
+  let source_file = SourceFile {
+    filename: test_file_url.to_file_path().unwrap(),
+    url: test_file_url.clone(),
+    types_header: None,
+    media_type: MediaType::TypeScript,
+    source_code: TextDocument::new(test_file.into_bytes(), Some("utf-8")),
+  };
+  global_state
+    .file_fetcher
+    .save_source_file_in_cache(&main_module, source_file);
+  main_module
+}
+
+/// A message sent by the JS test runner, see `serializeMessage()` in
+/// `40_testing.js`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TestMessage {
+  Start { tests: usize },
+  TestStart { name: String },
+  TestEnd(TestResult),
+  End(TestSummary),
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TestStatus {
+  Passed,
+  Failed,
+  Ignored,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestError {
+  pub name: String,
+  pub message: String,
+  /// The stack trace, source mapped to the original source of the modules.
+  pub stack: Option<String>,
+  /// The error formatted for display.
+  pub formatted: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestResult {
+  pub name: String,
+  pub status: TestStatus,
+  pub duration: u64,
+  pub error: Option<TestError>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestSummary {
+  pub passed: usize,
+  pub failed: usize,
+  pub ignored: usize,
+  pub measured: usize,
+  pub filtered: usize,
+  pub used_only: bool,
+}
+
+#[derive(Debug)]
+pub struct TestEvent {
+  pub module: Url,
+  pub message: TestMessage,
+}
+
+fn format_duration(duration: u64) -> String {
+  colors::italic_gray(&format!("({}ms)", duration)).to_string()
+}
+
+/// Prints the results of test modules running in parallel, in the same
+/// format as `reportToConsole()` in `40_testing.js`. Results are printed
+/// once a test ends, so that lines of concurrent modules don't interleave.
+pub struct PrettyTestReporter {
+  quiet: bool,
+  failures: Vec<(String, String)>,
+}
+
+impl PrettyTestReporter {
+  pub fn new(quiet: bool) -> Self {
+    Self {
+      quiet,
+      failures: vec![],
+    }
+  }
+}
+
+impl TestReporter for PrettyTestReporter {
+  fn visit_event(&mut self, event: &TestEvent) -> Result<(), ErrBox> {
+    match &event.message {
+      TestMessage::Start { .. } | TestMessage::TestEnd(_) if self.quiet => {}
+      TestMessage::Start { tests } => {
+        println!("running {} tests from {}", tests, event.module);
+      }
+      TestMessage::TestStart { .. } => {}
+      TestMessage::TestEnd(result) => {
+        if let Some(error) = &result.error {
+          self
+            .failures
+            .push((result.name.clone(), error.formatted.clone()));
+        }
+        let status = match result.status {
+          TestStatus::Passed => colors::green("ok").to_string(),
+          TestStatus::Failed => colors::red("FAILED").to_string(),
+          TestStatus::Ignored => colors::yellow("ignored").to_string(),
+        };
+        println!(
+          "test {} ... {} {}",
+          result.name,
+          status,
+          format_duration(result.duration)
+        );
+      }
+      TestMessage::End(_) => {}
+    }
+    Ok(())
+  }
+
+  /// Prints the failures and the totals of all modules.
+  fn done(
+    &mut self,
+    summary: &TestSummary,
+    duration: u64,
+  ) -> Result<(), ErrBox> {
+    let failed = summary.failed > 0 || !self.failures.is_empty();
+    if self.quiet {
+      return Ok(());
+    }
+
+    if !self.failures.is_empty() {
+      println!("\nfailures:\n");
+      for (name, error) in &self.failures {
+        println!("{}", name);
+        println!("{}", error);
+        println!();
+      }
+
+      println!("failures:\n");
+      for (name, _) in &self.failures {
+        println!("\t{}", name);
+      }
+    }
+
+    println!(
+      "\ntest result: {}. {} passed; {} failed; {} ignored; {} measured; {} filtered out {}\n",
+      if failed {
+        colors::red("FAILED").to_string()
+      } else {
+        colors::green("ok").to_string()
+      },
+      summary.passed,
+      summary.failed,
+      summary.ignored,
+      summary.measured,
+      summary.filtered,
+      format_duration(duration),
+    );
+
+    if summary.used_only && !failed {
+      println!(
+        "{} because the \"only\" option was used\n",
+        colors::red("FAILED")
+      );
+    }
+    Ok(())
+  }
+}
+
+#[derive(Clone)]
+pub struct TestRunOptions {
+  pub jobs: usize,
+  pub fail_fast: bool,
+  pub quiet: bool,
+  pub filter: Option<String>,
+  pub shuffle: Option<u64>,
+}
+
+/// Runs every test module in its own isolate, spreading them over
+/// `options.jobs` threads, and hands the results to `reporters`. Returns
+/// whether all tests passed.
+///
+/// Unlike a serial run, where the "only" option applies to the tests of all
+/// modules, it applies to the tests of each module separately.
+pub fn run_test_modules_in_isolates(
+  global_state: &Arc<GlobalState>,
+  cwd: &Path,
+  modules: Vec<Url>,
+  options: TestRunOptions,
+  reporters: &mut [Box<dyn TestReporter>],
+) -> Result<bool, ErrBox> {
+  let start = std::time::Instant::now();
+  let queue: Arc<Mutex<VecDeque<(usize, Url)>>> =
+    Arc::new(Mutex::new(modules.into_iter().enumerate().collect()));
+  let cancelled = Arc::new(AtomicBool::new(false));
+  let (sender, receiver) = mpsc::channel::<TestEvent>();
+
+  let mut runner_options = json!({
+    "failFast": options.fail_fast,
+    "reportToConsole": false,
+    "reportToHost": true,
+    "exitOnFail": false,
+    "disableLog": options.quiet,
+  });
+  if let Some(filter) = &options.filter {
+    runner_options["filter"] = json!(filter);
+  }
+  if let Some(seed) = options.shuffle {
+    runner_options["shuffle"] = json!(seed);
+  }
+
+  let mut join_handles = vec![];
+  for i in 0..options.jobs {
+    let global_state = global_state.clone();
+    let cwd = cwd.to_path_buf();
+    let queue = queue.clone();
+    let cancelled = cancelled.clone();
+    let sender = sender.clone();
+    let runner_options = runner_options.clone();
+    let join_handle = std::thread::Builder::new()
+      .name(format!("deno-test-{}", i))
+      .spawn(move || -> Result<(), ErrBox> {
+        let mut rt = tokio_util::create_basic_runtime();
+        loop {
+          if cancelled.load(Ordering::Relaxed) {
+            return Ok(());
+          }
+          let next = queue.lock().unwrap().pop_front();
+          let (index, module) = match next {
+            Some(next) => next,
+            None => return Ok(()),
+          };
+          let test_file_url =
+            Url::from_file_path(cwd.join(format!(".deno.test.{}.ts", index)))
+              .expect("Should be valid file url");
+          let test_file = render_test_file_with_options(
+            vec![module.clone()],
+            runner_options.clone(),
+          );
+          let main_module =
+            save_test_file(&global_state, &test_file_url, test_file);
+
+          let result = rt.block_on(async {
+            let mut worker =
+              MainWorker::create(&global_state, main_module.clone())?;
+            ops::testing::init_reporter(&worker.state, module, &sender);
+            worker.execute_module(&main_module).await?;
+            worker.execute("window.dispatchEvent(new Event('load'))")?;
+            (&mut *worker).await?;
+            worker.execute("window.dispatchEvent(new Event('unload'))")
+          });
+          if let Err(err) = result {
+            cancelled.store(true, Ordering::Relaxed);
+            return Err(err);
+          }
+        }
+      })?;
+    join_handles.push(join_handle);
+  }
+  drop(sender);
+
+  let mut summary = TestSummary::default();
+  let mut reporter_result = Ok(());
+  for event in receiver {
+    match &event.message {
+      TestMessage::TestEnd(result)
+        if options.fail_fast && result.status == TestStatus::Failed =>
+      {
+        cancelled.store(true, Ordering::Relaxed);
+      }
+      TestMessage::End(module_summary) => {
+        summary.passed += module_summary.passed;
+        summary.failed += module_summary.failed;
+        summary.ignored += module_summary.ignored;
+        summary.measured += module_summary.measured;
+        summary.filtered += module_summary.filtered;
+        summary.used_only |= module_summary.used_only;
+      }
+      _ => {}
+    }
+    if reporter_result.is_ok() {
+      reporter_result = reporters
+        .iter_mut()
+        .try_for_each(|reporter| reporter.visit_event(&event));
+    }
+  }
+
+  let mut result = Ok(());
+  for join_handle in join_handles {
+    let thread_result = join_handle.join().expect("Panic in test thread");
+    if result.is_ok() {
+      result = thread_result;
+    }
+  }
+  result?;
+  reporter_result?;
+
+  let duration = start.elapsed().as_millis() as u64;
+  for reporter in reporters.iter_mut() {
+    reporter.done(&summary, duration)?;
+  }
+
+  Ok(summary.failed == 0 && !summary.used_only)
+}
+
+/// Drives `worker`, which runs the tests of all modules in a single isolate,
+/// and hands the events posted to `receiver` to `reporters`. Returns whether
+/// all tests passed.
+///
+/// The JS test runner leaves exiting to `deno test`, so that reports and
+/// coverage can be written first. Like its `exitOnFail` option, a failed run
+/// stops as soon as its totals are posted, rather than waiting for the ops
+/// left behind by tests which timed out.
+pub async fn run_serial_test_worker(
+  worker: &mut MainWorker,
+  receiver: &mpsc::Receiver<TestEvent>,
+  reporters: &mut [Box<dyn TestReporter>],
+) -> Result<bool, ErrBox> {
+  let start = std::time::Instant::now();
+  let mut summary: Option<TestSummary> = None;
+  let mut reporter_result = Ok(());
+  poll_fn(|cx| {
+    let poll_result = worker.poll_unpin(cx);
+    for event in receiver.try_iter() {
+      if let TestMessage::End(module_summary) = &event.message {
+        summary = Some(module_summary.clone());
+      }
+      if reporter_result.is_ok() {
+        reporter_result = reporters
+          .iter_mut()
+          .try_for_each(|reporter| reporter.visit_event(&event));
+      }
+    }
+    match (poll_result, &summary) {
+      (Poll::Ready(result), _) => Poll::Ready(result),
+      (Poll::Pending, Some(summary))
+        if summary.failed > 0 || summary.used_only =>
+      {
+        Poll::Ready(Ok(()))
+      }
+      (Poll::Pending, _) => Poll::Pending,
+    }
+  })
+  .await?;
+  reporter_result?;
+
+  let summary = summary.unwrap_or_default();
+  let duration = start.elapsed().as_millis() as u64;
+  for reporter in reporters.iter_mut() {
+    reporter.done(&summary, duration)?;
+  }
+
+  Ok(summary.failed == 0 && !summary.used_only)
+}
+
+/// Returns the local files in the module graph of `module`.
+async fn local_dependencies(
+  global_state: &GlobalState,
+  module: &Url,
+) -> Result<HashSet<PathBuf>, ErrBox> {
+  let mut module_graph_loader = ModuleGraphLoader::new(
+    global_state.file_fetcher.clone(),
+    global_state.maybe_import_map.clone(),
+    Permissions::allow_all(),
+    false,
+    false,
+  );
+  module_graph_loader
+    .add_to_graph(&ModuleSpecifier::from(module.clone()), None)
+    .await?;
+  let module_graph = module_graph_loader.get_graph();
+  Ok(
+    module_graph
+      .values()
+      .filter_map(|f| Url::parse(&f.url).ok())
+      .filter(|url| url.scheme() == "file")
+      .filter_map(|url| url.to_file_path().ok())
+      .collect(),
+  )
+}
+
+/// Returns the test modules to run again after `changed_paths` changed: those
+/// whose module graph in the previous run, as recorded in `dependencies`,
+/// includes one of them, and those which weren't part of the previous run,
+/// like newly created ones.
+fn modules_to_rerun(
+  test_modules: &[Url],
+  dependencies: &HashMap<Url, HashSet<PathBuf>>,
+  changed_paths: &[PathBuf],
+) -> Vec<Url> {
+  test_modules
+    .iter()
+    .filter(|module| match dependencies.get(module) {
+      Some(paths) => changed_paths.iter().any(|p| paths.contains(p)),
+      None => true,
+    })
+    .cloned()
+    .collect()
+}
+
+/// Runs the test modules matched by `include`, then keeps watching the files
+/// in their module graphs and the directories of `include`. When files
+/// change, only the test modules whose module graph includes one of them, and
+/// new test modules, are run.
+pub async fn run_tests_with_watch(
+  flags: Flags,
+  include: Vec<String>,
+  cwd: &Path,
+  options: TestRunOptions,
+) -> Result<(), ErrBox> {
+  let mut dependencies: HashMap<Url, HashSet<PathBuf>> = HashMap::new();
+  // `None` runs all test modules.
+  let mut maybe_changed_paths: Option<Vec<PathBuf>> = None;
+  let include_dirs: Vec<PathBuf> = include
+    .iter()
+    .filter(|path| !is_remote_url(path))
+    .map(|path| deno_fs::normalize_path(&cwd.join(path)))
+    .filter(|path| path.is_dir())
+    .collect();
+
+  loop {
+    // GlobalState must be created on each run, otherwise the file fetcher
+    // would serve stale source files from its in-memory cache.
+    let global_state = GlobalState::new(flags.clone())?;
+    let test_modules =
+      prepare_test_modules_urls(include.clone(), &cwd.to_path_buf())?;
+
+    let mut modules_to_run = match &maybe_changed_paths {
+      Some(changed_paths) => {
+        let modules =
+          modules_to_rerun(&test_modules, &dependencies, changed_paths);
+        info!(
+          "{} File change detected! Rerunning {} test module(s)",
+          colors::intense_blue("Watcher"),
+          modules.len()
+        );
+        modules
+      }
+      None => test_modules.clone(),
+    };
+
+    for module in &test_modules {
+      match local_dependencies(&global_state, module).await {
+        Ok(paths) => {
+          dependencies.insert(module.clone(), paths);
+        }
+        Err(err) => {
+          // Keep watching the files of the previous graph, or at least the
+          // module itself, so fixing the error triggers a rerun.
+          eprintln!("{}: {}", colors::red_bold("error"), err.to_string());
+          dependencies
+            .entry(module.clone())
+            .or_insert_with(|| module.to_file_path().into_iter().collect());
+        }
+      }
+    }
+    dependencies.retain(|module, _| test_modules.contains(module));
+
+    if let Some(seed) = options.shuffle {
+      shuffle_modules(&mut modules_to_run, seed);
+    }
+    if !modules_to_run.is_empty() {
+      let mut reporters: Vec<Box<dyn TestReporter>> =
+        vec![Box::new(PrettyTestReporter::new(options.quiet))];
+      if let Err(err) = run_test_modules_in_isolates(
+        &global_state,
+        cwd,
+        modules_to_run,
+        options.clone(),
+        &mut reporters,
+      ) {
+        eprintln!("{}: {}", colors::red_bold("error"), err.to_string());
+      }
+    }
+
+    info!(
+      "{} Watching for file changes...",
+      colors::intense_blue("Watcher")
+    );
+    let paths: HashSet<&PathBuf> = dependencies.values().flatten().collect();
+    let paths: Vec<PathBuf> = paths.into_iter().cloned().collect();
+    let changed_paths =
+      file_watcher::file_and_dir_watcher(&paths, &include_dirs).await?;
+    maybe_changed_paths = Some(changed_paths);
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -125,6 +684,66 @@ mod tests {
     assert_eq!(matched_urls, expected);
   }
 
+  #[test]
+  fn test_shuffle_modules() {
+    let modules: Vec<Url> = (0..10)
+      .map(|i| Url::parse(&format!("file:///{}_test.ts", i)).unwrap())
+      .collect();
+    let mut shuffled = modules.clone();
+    shuffle_modules(&mut shuffled, 42);
+    let mut reversed: Vec<Url> = modules.iter().rev().cloned().collect();
+    shuffle_modules(&mut reversed, 42);
+    assert_eq!(shuffled, reversed);
+
+    shuffled.sort();
+    assert_eq!(shuffled, modules);
+  }
+
+  #[test]
+  fn test_modules_to_rerun() {
+    let root = if cfg!(windows) {
+      PathBuf::from("C:\\project")
+    } else {
+      PathBuf::from("/project")
+    };
+    let a = Url::from_file_path(root.join("a_test.ts")).unwrap();
+    let b = Url::from_file_path(root.join("b_test.ts")).unwrap();
+    let c = Url::from_file_path(root.join("c_test.ts")).unwrap();
+    let mut dependencies: HashMap<Url, HashSet<PathBuf>> = HashMap::new();
+    dependencies.insert(
+      a.clone(),
+      vec![root.join("a_test.ts"), root.join("util.ts")]
+        .into_iter()
+        .collect(),
+    );
+    dependencies.insert(
+      b.clone(),
+      vec![root.join("b_test.ts")].into_iter().collect(),
+    );
+    let test_modules = vec![a.clone(), b.clone()];
+
+    let changed = vec![root.join("util.ts")];
+    assert_eq!(
+      modules_to_rerun(&test_modules, &dependencies, &changed),
+      vec![a.clone()]
+    );
+    let changed = vec![root.join("b_test.ts"), root.join("README.md")];
+    assert_eq!(
+      modules_to_rerun(&test_modules, &dependencies, &changed),
+      vec![b.clone()]
+    );
+    let changed = vec![root.join("README.md")];
+    assert!(modules_to_rerun(&test_modules, &dependencies, &changed).is_empty());
+
+    // A new test module is run, whatever changed.
+    let test_modules = vec![a, b, c.clone()];
+    let changed = vec![root.join("c_test.ts")];
+    assert_eq!(
+      modules_to_rerun(&test_modules, &dependencies, &changed),
+      vec![c]
+    );
+  }
+
   #[test]
   fn test_is_supported() {
     assert!(is_supported(Path::new("tests/subdir/foo_test.ts")));
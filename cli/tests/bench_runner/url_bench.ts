@@ -0,0 +1,18 @@
+Deno.bench({
+  name: "parse url",
+  warmup: 1,
+  iterations: 5,
+  fn() {
+    new URL("https://deno.land/std/testing/asserts.ts");
+  },
+});
+
+Deno.bench({
+  name: "ignored",
+  ignore: true,
+  fn() {},
+});
+
+Deno.bench("throws", function () {
+  throw new Error("bench failed");
+});
@@ -0,0 +1,64 @@
+import {
+  assertStrictEquals,
+  assertThrows,
+  assertThrowsAsync,
+} from "../../std/testing/asserts.ts";
+
+Deno.test({
+  name: "read denied",
+  permissions: { read: false },
+  fn() {
+    assertThrows(() => Deno.cwd(), Deno.errors.PermissionDenied);
+  },
+});
+
+Deno.test("read restored", function () {
+  Deno.cwd();
+});
+
+Deno.test({
+  name: "read allowlist",
+  permissions: { read: [new URL(".", import.meta.url)] },
+  async fn() {
+    const status = await Deno.permissions.query({ name: "read" });
+    assertStrictEquals(status.state, "prompt");
+    await Deno.readFile(new URL("deno_test_permissions.ts", import.meta.url));
+    await assertThrowsAsync(
+      () => Deno.readFile(new URL("../Cargo.toml", import.meta.url)),
+      Deno.errors.PermissionDenied,
+    );
+  },
+});
+
+Deno.test({
+  name: "net escalation",
+  permissions: { net: true },
+  fn() {},
+});
+
+Deno.test({
+  name: "permissions can't be restored by the test",
+  permissions: { read: false },
+  fn() {
+    // deno-lint-ignore no-explicit-any
+    const { jsonOpSync } = Deno.core as any;
+    assertThrows(() => jsonOpSync("op_issue_test_permissions_token", {}));
+    assertThrows(() =>
+      jsonOpSync("op_restore_test_permissions", { token: "" })
+    );
+    assertThrows(() => Deno.cwd(), Deno.errors.PermissionDenied);
+  },
+});
+
+Deno.test({
+  name: "times out with narrowed permissions",
+  permissions: { read: false },
+  timeout: 50,
+  fn() {
+    return new Promise(() => {});
+  },
+});
+
+Deno.test("not run after a timeout with narrowed permissions", function () {
+  throw new Error("unreachable");
+});
@@ -0,0 +1,31 @@
+Deno.test({
+  name: "finishes in time",
+  timeout: 5000,
+  async fn() {
+    await new Promise((resolve) => setTimeout(resolve, 10));
+  },
+});
+
+Deno.test({
+  name: "times out with a pending op",
+  timeout: 50,
+  async fn() {
+    await new Promise((resolve) => setTimeout(resolve, 200));
+  },
+});
+
+Deno.test("runs while the timed out test finishes", async function () {
+  // The op of the abandoned test completes in the meantime, which must not
+  // trip the op sanitizer of this test.
+  await new Promise((resolve) => setTimeout(resolve, 300));
+});
+
+Deno.test("no timeout", function () {});
+
+Deno.test({
+  name: "hangs",
+  timeout: 50,
+  fn() {
+    return new Promise(() => {});
+  },
+});
@@ -0,0 +1,25 @@
+/**
+ * Adds two numbers.
+ *
+ * ```ts
+ * import { add } from "./mod.ts";
+ * if (add(1, 2) !== 3) throw new Error("Expected 3");
+ * ```
+ */
+export function add(a: number, b: number): number {
+  return a + b;
+}
+
+export class Calculator {
+  /** Multiplies two numbers.
+   *
+   * ```ts
+   * import { Calculator } from "./mod.ts";
+   * const result = new Calculator().multiply(2, 3);
+   * if (result !== 6) throw new Error("Expected 6");
+   * ```
+   */
+  multiply(a: number, b: number): number {
+    return a * b;
+  }
+}
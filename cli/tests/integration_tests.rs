@@ -694,6 +694,181 @@ fn ts_reload() {
     .contains("\"compiler::host.writeFile\" \"deno://002_hello.js\""));
 }
 
+#[test]
+fn cache_gc() {
+  let _g = util::http_server();
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  let t = TempDir::new().expect("tempdir fail");
+  let main = t.path().join("main.ts");
+  std::fs::write(
+    &main,
+    "import { printHello } from \"http://localhost:4545/cli/tests/subdir/print_hello.ts\";\n\
+     printHello();\n",
+  )
+  .expect("error writing file");
+
+  let deno_cache = |args: &[&str]| {
+    let output = util::deno_cmd()
+      .current_dir(t.path())
+      .env("DENO_DIR", deno_dir.path())
+      .arg("cache")
+      .arg("--unstable")
+      .args(args)
+      .output()
+      .expect("failed to spawn script");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+  };
+  deno_cache(&["main.ts", "http://localhost:4545/cli/tests/002_hello.ts"]);
+
+  let out = deno_cache(&["--list"]);
+  assert!(out.contains("http://localhost:4545/cli/tests/002_hello.ts"));
+  assert!(out.contains("http://localhost:4545/cli/tests/subdir/print_hello.ts"));
+  assert!(out.contains("2 cached modules"));
+
+  // Modules used in the last day are kept, whatever the roots.
+  deno_cache(&["--gc", "--older-than=1", "main.ts"]);
+  assert!(deno_cache(&["--list"]).contains("2 cached modules"));
+
+  deno_cache(&["--gc", "main.ts"]);
+  let out = deno_cache(&["--list"]);
+  assert!(!out.contains("002_hello.ts"));
+  assert!(out.contains("http://localhost:4545/cli/tests/subdir/print_hello.ts"));
+  assert!(out.contains("1 cached modules"));
+
+  // The files emitted for the removed module are gone as well.
+  let deps_dir = deno_dir.path().join("deps/http/localhost_PORT4545");
+  let gen_dir = deno_dir.path().join("gen/http/localhost_PORT4545");
+  for entry in std::fs::read_dir(gen_dir).unwrap() {
+    let file_name = entry.unwrap().file_name();
+    let file_name = file_name.to_str().unwrap();
+    let hash = file_name.split('.').next().unwrap();
+    assert!(deps_dir.join(hash).is_file(), "{} is orphaned", file_name);
+  }
+}
+
+#[test]
+fn cache_check_integrity() {
+  let _g = util::http_server();
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  let module_url = "http://localhost:4545/cli/tests/subdir/print_hello.ts";
+  let deno_cache = |args: &[&str]| {
+    util::deno_cmd()
+      .env("DENO_DIR", deno_dir.path())
+      .env("NO_COLOR", "1")
+      .arg("cache")
+      .arg("--unstable")
+      .args(args)
+      .output()
+      .expect("failed to spawn script")
+  };
+  assert!(deno_cache(&[module_url]).status.success());
+
+  let output = deno_cache(&["--check-integrity"]);
+  assert!(output.status.success());
+
+  let deps_dir = deno_dir.path().join("deps/http/localhost_PORT4545");
+  let cache_filename = std::fs::read_dir(deps_dir)
+    .unwrap()
+    .map(|entry| entry.unwrap().path())
+    .find(|path| path.extension().is_none())
+    .unwrap();
+  let source = std::fs::read(&cache_filename).unwrap();
+  std::fs::write(&cache_filename, "console.log(\"Hacked\");\n").unwrap();
+
+  let output = deno_cache(&["--check-integrity"]);
+  assert!(!output.status.success());
+  let out = std::str::from_utf8(&output.stdout).unwrap();
+  assert!(out.contains(&format!("Corrupted {}", module_url)));
+
+  let output = deno_cache(&["--check-integrity", "--repair"]);
+  assert!(output.status.success());
+  let out = std::str::from_utf8(&output.stdout).unwrap();
+  assert!(out.contains(&format!("Repaired {}", module_url)));
+  assert_eq!(std::fs::read(&cache_filename).unwrap(), source);
+}
+
+#[test]
+fn vendor() {
+  let _g = util::http_server();
+  let t = TempDir::new().expect("tempdir fail");
+  let main = t.path().join("main.ts");
+  std::fs::write(
+    &main,
+    "import { printHello } from \"http://localhost:4545/cli/tests/vendor/mod.ts\";\n\
+     printHello();\n",
+  )
+  .expect("error writing file");
+
+  let status = util::deno_cmd()
+    .current_dir(t.path())
+    .arg("vendor")
+    .arg("--unstable")
+    .arg("main.ts")
+    .spawn()
+    .expect("failed to spawn script")
+    .wait()
+    .expect("failed to wait for the child process");
+  assert!(status.success());
+  let vendor_dir = t.path().join("vendor");
+  assert!(vendor_dir
+    .join("localhost_PORT4545/cli/tests/subdir/no_ext.ts")
+    .is_file());
+  let import_map: serde_json::Value = serde_json::from_str(
+    &std::fs::read_to_string(vendor_dir.join("import_map.json")).unwrap(),
+  )
+  .unwrap();
+  assert_eq!(
+    import_map,
+    serde_json::json!({
+      "imports": {
+        "http://localhost:4545/": "./localhost_PORT4545/",
+        "http://localhost:4545/cli/tests/subdir/no_ext":
+          "./localhost_PORT4545/cli/tests/subdir/no_ext.ts",
+        "http://localhost:4546/": "./localhost_PORT4546/",
+        "http://localhost:4546/cli/tests/subdir/print_hello.ts":
+          "./localhost_PORT4545/cli/tests/subdir/print_hello.ts",
+      },
+      "scopes": {
+        "./localhost_PORT4545/": {
+          "./localhost_PORT4545/cli/tests/subdir/no_ext":
+            "./localhost_PORT4545/cli/tests/subdir/no_ext.ts",
+        },
+      },
+    })
+  );
+
+  // The vendored modules are used without accessing the network.
+  let output = util::deno_cmd()
+    .current_dir(t.path())
+    .arg("run")
+    .arg("--unstable")
+    .arg("--no-remote")
+    .arg("--importmap")
+    .arg("vendor/import_map.json")
+    .arg("main.ts")
+    .output()
+    .expect("failed to spawn script");
+  assert!(output.status.success());
+  assert_eq!(
+    std::str::from_utf8(&output.stdout).unwrap(),
+    "true\nHello\n"
+  );
+
+  // The output directory isn't overwritten without --force.
+  let status = util::deno_cmd()
+    .current_dir(t.path())
+    .arg("vendor")
+    .arg("--unstable")
+    .arg("main.ts")
+    .stderr(std::process::Stdio::null())
+    .spawn()
+    .expect("failed to spawn script")
+    .wait()
+    .expect("failed to wait for the child process");
+  assert!(!status.success());
+}
+
 #[test]
 fn bundle_exports() {
   // First we have to generate a bundle of some module that has exports.
@@ -872,6 +1047,38 @@ fn bundle_js() {
   assert_eq!(output.stderr, b"");
 }
 
+#[test]
+fn compile() {
+  let dir = TempDir::new().expect("tempdir fail");
+  let exe = if cfg!(windows) {
+    dir.path().join("echo_test.exe")
+  } else {
+    dir.path().join("echo_test")
+  };
+  let output = util::deno_cmd()
+    .current_dir(util::root_path())
+    .arg("compile")
+    .arg("--unstable")
+    .arg("./cli/tests/echo.ts")
+    .arg(&exe)
+    .stdout(std::process::Stdio::piped())
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(output.status.success());
+  let output = Command::new(exe)
+    .arg("foo")
+    .arg("--bar")
+    .stdout(std::process::Stdio::piped())
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(output.status.success());
+  assert_eq!(output.stdout, "foo, --bar".as_bytes());
+}
+
 #[test]
 fn bundle_dynamic_import() {
   let dynamic_import =
@@ -981,6 +1188,186 @@ fn info_with_compiled_source() {
   assert_eq!(output.stderr, b"");
 }
 
+#[test]
+fn run_watch() {
+  let t = TempDir::new().expect("tempdir fail");
+  let file_to_watch = t.path().join("file_to_watch.js");
+  std::fs::write(&file_to_watch, "console.log('Hello world');")
+    .expect("error writing file");
+
+  let mut child = util::deno_cmd()
+    .current_dir(util::root_path())
+    .arg("run")
+    .arg("--watch")
+    .arg("--unstable")
+    .arg(&file_to_watch)
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .expect("failed to spawn script");
+
+  let stdout = child.stdout.as_mut().unwrap();
+  let mut stdout_lines =
+    std::io::BufReader::new(stdout).lines().map(|r| r.unwrap());
+  let stderr = child.stderr.as_mut().unwrap();
+  let mut stderr_lines =
+    std::io::BufReader::new(stderr).lines().map(|r| r.unwrap());
+
+  assert!(stdout_lines.next().unwrap().contains("Hello world"));
+  assert!(stderr_lines.next().unwrap().contains("Process terminated"));
+
+  // Change content of the file
+  std::fs::write(&file_to_watch, "console.log('Hello world2');")
+    .expect("error writing file");
+
+  assert!(stderr_lines.next().unwrap().contains("Restarting"));
+  assert!(stdout_lines.next().unwrap().contains("Hello world2"));
+  child.kill().unwrap();
+  drop(t);
+}
+
+#[test]
+fn run_watch_deleted_dependency() {
+  let t = TempDir::new().expect("tempdir fail");
+  let file_to_watch = t.path().join("file_to_watch.js");
+  let dependency = t.path().join("dep.js");
+  std::fs::write(&file_to_watch, "import \"./dep.js\";")
+    .expect("error writing file");
+  std::fs::write(&dependency, "console.log('Hello world');")
+    .expect("error writing file");
+
+  let mut child = util::deno_cmd()
+    .current_dir(util::root_path())
+    .arg("run")
+    .arg("--watch")
+    .arg("--unstable")
+    .arg(&file_to_watch)
+    .env("NO_COLOR", "1")
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .expect("failed to spawn script");
+
+  let stdout = child.stdout.as_mut().unwrap();
+  let mut stdout_lines =
+    std::io::BufReader::new(stdout).lines().map(|r| r.unwrap());
+  let stderr = child.stderr.as_mut().unwrap();
+  let mut stderr_lines =
+    std::io::BufReader::new(stderr).lines().map(|r| r.unwrap());
+
+  assert!(stdout_lines.next().unwrap().contains("Hello world"));
+  assert!(stderr_lines.next().unwrap().contains("Process terminated"));
+
+  // The module graph can't be loaded without the dependency, but the
+  // watcher keeps waiting for it.
+  std::fs::remove_file(&dependency).expect("error removing file");
+  assert!(stderr_lines.next().unwrap().contains("Restarting"));
+  assert!(stderr_lines.any(|line| line.contains("Process terminated")));
+
+  std::fs::write(&dependency, "console.log('Hello world2');")
+    .expect("error writing file");
+  assert!(stderr_lines.next().unwrap().contains("Restarting"));
+  assert!(stdout_lines.next().unwrap().contains("Hello world2"));
+  child.kill().unwrap();
+  drop(t);
+}
+
+#[test]
+fn test_watch() {
+  let t = TempDir::new().expect("tempdir fail");
+  let dependency = t.path().join("dep.ts");
+  std::fs::write(&dependency, "export const value = 1;")
+    .expect("error writing file");
+  std::fs::write(
+    t.path().join("a_test.ts"),
+    "import { value } from \"./dep.ts\";\n\
+     Deno.test(\"uses dep\", () => { console.log(value); });",
+  )
+  .expect("error writing file");
+  std::fs::write(
+    t.path().join("b_test.ts"),
+    "Deno.test(\"standalone\", () => {});",
+  )
+  .expect("error writing file");
+
+  let mut child = util::deno_cmd()
+    .current_dir(t.path())
+    .arg("test")
+    .arg("--watch")
+    .arg("--unstable")
+    .arg(".")
+    .env("NO_COLOR", "1")
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .expect("failed to spawn script");
+
+  let stdout = child.stdout.as_mut().unwrap();
+  let mut stdout_lines =
+    std::io::BufReader::new(stdout).lines().map(|r| r.unwrap());
+  let stderr = child.stderr.as_mut().unwrap();
+  let mut stderr_lines =
+    std::io::BufReader::new(stderr).lines().map(|r| r.unwrap());
+
+  // Output of a run, up to its summary.
+  let mut next_run = || -> Vec<String> {
+    let mut lines = vec![];
+    for line in &mut stdout_lines {
+      let done = line.starts_with("test result:");
+      lines.push(line);
+      if done {
+        break;
+      }
+    }
+    lines
+  };
+  // Messages of the watcher, up to the point it waits for changes again.
+  let mut next_wait = || -> Vec<String> {
+    let mut lines = vec![];
+    for line in &mut stderr_lines {
+      let done = line.contains("Watching for file changes");
+      lines.push(line);
+      if done {
+        break;
+      }
+    }
+    lines
+  };
+
+  let run = next_run();
+  assert!(run.iter().any(|l| l.starts_with("test uses dep ... ok")));
+  assert!(run.iter().any(|l| l.starts_with("test standalone ... ok")));
+  next_wait();
+
+  // Only the test module depending on the changed file reruns.
+  std::fs::write(&dependency, "export const value = 2;")
+    .expect("error writing file");
+  let wait = next_wait();
+  assert!(wait
+    .iter()
+    .any(|l| l.contains("Rerunning 1 test module(s)")));
+  let run = next_run();
+  assert!(run.iter().any(|l| l.starts_with("test uses dep ... ok")));
+  assert!(!run.iter().any(|l| l.starts_with("test standalone")));
+
+  // A deleted dependency is still watched, restoring it reruns its module.
+  std::fs::remove_file(&dependency).expect("error removing file");
+  next_wait();
+  std::fs::write(&dependency, "export const value = 3;")
+    .expect("error writing file");
+  let wait = next_wait();
+  assert!(wait
+    .iter()
+    .any(|l| l.contains("Rerunning 1 test module(s)")));
+  let run = next_run();
+  assert!(run.iter().any(|l| l == "3"));
+  assert!(run.iter().any(|l| l.starts_with("test uses dep ... ok")));
+  assert!(!run.iter().any(|l| l.starts_with("test standalone")));
+
+  child.kill().unwrap();
+  drop(t);
+}
+
 #[test]
 fn repl_test_console_log() {
   let (out, err) = util::run_and_collect_output(
@@ -1410,6 +1797,91 @@ itest!(deno_test_fail_fast {
   output: "deno_test_fail_fast.out",
 });
 
+itest!(deno_test_shuffle {
+  args: "test --shuffle=1 test_runner_test.ts",
+  exit_code: 1,
+  output: "deno_test_shuffle.out",
+});
+
+itest!(deno_test_coverage {
+  args: "test --unstable --coverage test_coverage/",
+  output: "deno_test_coverage.out",
+});
+
+itest!(deno_test_coverage_fail {
+  args: "test --unstable --coverage test_coverage_fail/",
+  exit_code: 1,
+  output: "deno_test_coverage_fail.out",
+});
+
+itest!(deno_test_jobs {
+  args: "test --jobs=2 test_runner_jobs/",
+  exit_code: 1,
+  output: "deno_test_jobs.out",
+});
+
+itest!(deno_test_jobs_filter {
+  args: "test --jobs=2 --filter=pass test_runner_jobs/",
+  output: "deno_test_jobs_filter.out",
+});
+
+itest!(deno_test_reporter_json {
+  args: "test --reporter=json test_runner_jobs/b_test.ts",
+  output: "deno_test_reporter_json.out",
+});
+
+itest!(deno_test_reporter_junit {
+  args: "test --reporter=junit test_runner_jobs/a_test.ts",
+  exit_code: 1,
+  output: "deno_test_reporter_junit.out",
+});
+
+itest!(deno_test_reporter_junit_serial {
+  args: "test --reporter=junit test_runner_jobs/a_test.ts test_runner_jobs/b_test.ts",
+  exit_code: 1,
+  output: "deno_test_reporter_junit_serial.out",
+});
+
+itest!(deno_test_timeout {
+  args: "test deno_test_timeout.ts",
+  exit_code: 1,
+  output: "deno_test_timeout.out",
+});
+
+itest!(deno_test_permissions {
+  args: "test --unstable --allow-read deno_test_permissions.ts",
+  exit_code: 1,
+  output: "deno_test_permissions.out",
+});
+
+itest!(deno_test_doc {
+  args: "test --doc=run doc_tests/mod.ts doc_tests/README.md",
+  output: "deno_test_doc.out",
+});
+
+itest!(deno_test_doc_type_error {
+  args: "test --doc doc_tests/type_error.md",
+  exit_code: 1,
+  output: "deno_test_doc_type_error.out",
+});
+
+itest!(deno_bench {
+  args: "bench --unstable bench_runner/",
+  exit_code: 1,
+  output: "deno_bench.out",
+});
+
+itest!(deno_bench_unstable {
+  args: "bench bench_runner/",
+  exit_code: 70,
+  output: "deno_bench_unstable.out",
+});
+
+itest!(deno_bench_json {
+  args: "bench --unstable --json --filter=parse bench_runner/",
+  output: "deno_bench_json.out",
+});
+
 itest!(deno_test_only {
   args: "test deno_test_only.ts",
   exit_code: 1,
@@ -1707,6 +2179,20 @@ itest!(lock_check_err_with_bundle {
   http_server: true,
 });
 
+itest!(lock_check_err_redirect {
+  args: "run --lock=lock_check_err_redirect.json http://localhost:4546/cli/tests/003_relative_import.ts",
+  output: "lock_check_err_redirect.out",
+  exit_code: 10,
+  http_server: true,
+});
+
+itest!(lock_frozen {
+  args: "run --lock=lock_frozen.json --frozen http://127.0.0.1:4545/cli/tests/003_relative_import.ts",
+  output: "lock_frozen.out",
+  exit_code: 10,
+  http_server: true,
+});
+
 itest!(async_error {
   exit_code: 1,
   args: "run --reload async_error.ts",
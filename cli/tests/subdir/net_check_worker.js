@@ -0,0 +1,8 @@
+onmessage = async function (e) {
+  const results = [];
+  for (const url of e.data) {
+    const status = await Deno.permissions.query({ name: "net", url });
+    results.push(status.state);
+  }
+  postMessage(results);
+};
@@ -0,0 +1,15 @@
+onmessage = async function (e) {
+  const results = [];
+  for (const href of e.data) {
+    try {
+      await Deno.readTextFile(new URL(href));
+      results.push(true);
+    } catch (e) {
+      if (!(e instanceof Deno.errors.PermissionDenied)) {
+        throw e;
+      }
+      results.push(false);
+    }
+  }
+  postMessage(results);
+};
@@ -0,0 +1,7 @@
+onmessage = function (e) {
+  const arr = new Int32Array(e.data);
+  Atomics.store(arr, 1, 42);
+  Atomics.store(arr, 0, 1);
+  Atomics.notify(arr, 0);
+  postMessage(e.data);
+};
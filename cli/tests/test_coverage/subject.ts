@@ -0,0 +1,7 @@
+export function returnsHi(): string {
+  return "Hi";
+}
+
+export function returnsBye(): string {
+  return "Bye";
+}
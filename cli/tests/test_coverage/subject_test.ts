@@ -0,0 +1,7 @@
+import { returnsHi } from "./subject.ts";
+
+Deno.test("returns hi", function () {
+  if (returnsHi() !== "Hi") {
+    throw new Error("expected Hi");
+  }
+});
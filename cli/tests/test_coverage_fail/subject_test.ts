@@ -0,0 +1,7 @@
+import { returnsHi } from "../test_coverage/subject.ts";
+
+Deno.test("returns bye", function () {
+  if (returnsHi() !== "Bye") {
+    throw new Error("expected Bye");
+  }
+});
@@ -0,0 +1,9 @@
+import { assert } from "../../../std/testing/asserts.ts";
+
+Deno.test("a pass", function () {
+  assert(true);
+});
+
+Deno.test("a fail", function () {
+  assert(false, "a fail assertion");
+});
@@ -0,0 +1,9 @@
+import { assert } from "../../../std/testing/asserts.ts";
+
+Deno.test("b pass 1", function () {
+  assert(true);
+});
+
+Deno.test("b pass 2", function () {
+  assert(true);
+});
@@ -0,0 +1,64 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { assertEquals, assertThrows, unitTest } from "./test_util.ts";
+
+unitTest(function benchFnOverloading(): void {
+  // just verifying that you can use this benchmark definition syntax
+  Deno.bench("bench fn overloading", (): void => {});
+});
+
+unitTest(function nameOfBenchCaseCantBeEmpty(): void {
+  assertThrows(
+    () => {
+      Deno.bench("", () => {});
+    },
+    TypeError,
+    "The benchmark name can't be empty",
+  );
+  assertThrows(
+    () => {
+      Deno.bench({
+        name: "",
+        fn: () => {},
+      });
+    },
+    TypeError,
+    "The benchmark name can't be empty",
+  );
+});
+
+unitTest(function benchIterationsMustBePositive(): void {
+  assertThrows(
+    () => {
+      Deno.bench({
+        name: "no iterations",
+        iterations: 0,
+        fn: () => {},
+      });
+    },
+    TypeError,
+    "The number of iterations must be a positive integer",
+  );
+});
+
+unitTest(function benchStats(): void {
+  // @ts-expect-error TypeScript (as of 3.7) does not support indexing namespaces by symbol
+  const { computeBenchStats } = Deno[Deno.internal];
+  const samples: number[] = [];
+  for (let i = 100; i > 0; i--) {
+    samples.push(i);
+  }
+  assertEquals(computeBenchStats(samples), {
+    mean: 50.5,
+    min: 1,
+    max: 100,
+    p75: 75,
+    p99: 99,
+  });
+  assertEquals(computeBenchStats([3]), {
+    mean: 3,
+    min: 3,
+    max: 3,
+    p75: 3,
+    p99: 3,
+  });
+});
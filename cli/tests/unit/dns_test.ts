@@ -0,0 +1,113 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { assertEquals, assertThrowsAsync, unitTest } from "./test_util.ts";
+
+const nameServer = { ipAddr: "127.0.0.1", port: 4553 };
+
+/** Builds the answer of a minimal stub resolver: A queries resolve to
+ * 192.0.2.1, TXT queries to a single "hello" string, anything else to
+ * NXDOMAIN. */
+function stubAnswer(query: Uint8Array): Uint8Array {
+  // Skip the header and the labels of the question name.
+  let offset = 12;
+  while (query[offset] !== 0) {
+    offset += query[offset] + 1;
+  }
+  const qtype = (query[offset + 1] << 8) | query[offset + 2];
+  const question = query.subarray(12, offset + 5);
+
+  let rdata: number[] | null = null;
+  if (qtype === 1) {
+    rdata = [192, 0, 2, 1];
+  } else if (qtype === 16) {
+    rdata = [5, ...new TextEncoder().encode("hello")];
+  }
+
+  const header = [
+    query[0],
+    query[1], // id
+    0x81,
+    rdata === null ? 0x83 : 0x80, // response, RD, RA, rcode
+    0,
+    1, // qdcount
+    0,
+    rdata === null ? 0 : 1, // ancount
+    0,
+    0,
+    0,
+    0,
+  ];
+  const answer = rdata === null ? [] : [
+    0xc0,
+    12, // pointer to the question name
+    qtype >> 8,
+    qtype & 0xff,
+    0,
+    1, // class IN
+    0,
+    0,
+    0,
+    60, // ttl
+    0,
+    rdata.length,
+    ...rdata,
+  ];
+  return new Uint8Array([...header, ...question, ...answer]);
+}
+
+async function serveStub(socket: Deno.DatagramConn): Promise<void> {
+  const buf = new Uint8Array(512);
+  try {
+    while (true) {
+      const [query, addr] = await socket.receive(buf);
+      await socket.send(stubAnswer(query), addr);
+    }
+  } catch {
+    // The socket was closed.
+  }
+}
+
+unitTest(
+  { perms: { net: true } },
+  async function resolveDnsWithNameServer(): Promise<void> {
+    const socket = Deno.listenDatagram({
+      hostname: nameServer.ipAddr,
+      port: nameServer.port,
+      transport: "udp",
+    });
+    const serving = serveStub(socket);
+
+    const a = await Deno.resolveDns("deno.test", "A", { nameServer });
+    assertEquals(a, ["192.0.2.1"]);
+    const txt = await Deno.resolveDns("deno.test", "TXT", { nameServer });
+    assertEquals(txt, [["hello"]]);
+    await assertThrowsAsync(
+      async () => {
+        await Deno.resolveDns("deno.test", "MX", { nameServer });
+      },
+      Deno.errors.NotFound,
+    );
+
+    socket.close();
+    await serving;
+  },
+);
+
+unitTest(async function resolveDnsPerm(): Promise<void> {
+  await assertThrowsAsync(async () => {
+    await Deno.resolveDns("deno.test", "A", { nameServer });
+  }, Deno.errors.PermissionDenied);
+});
+
+unitTest(
+  { perms: { net: true } },
+  async function resolveDnsUnsupportedRecordType(): Promise<void> {
+    await assertThrowsAsync(
+      async () => {
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+        await Deno.resolveDns("deno.test", "PTR" as any, { nameServer });
+      },
+      TypeError,
+      "Unsupported record type",
+    );
+  },
+);
@@ -422,6 +422,98 @@ unitTest(
   },
 );
 
+unitTest(
+  { perms: { net: true } },
+  async function fetchInitReadableStreamBody(): Promise<void> {
+    const data = "Hello World";
+    const chunks = new TextEncoder().encode(data);
+    const stream = new ReadableStream({
+      start(controller): void {
+        controller.enqueue(chunks.subarray(0, 5));
+        controller.enqueue(chunks.subarray(5));
+        controller.close();
+      },
+    });
+    const response = await fetch("http://localhost:4545/echo_server", {
+      method: "POST",
+      body: stream,
+    });
+    const text = await response.text();
+    assertEquals(text, data);
+  },
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchInitReadableStreamBodyError(): Promise<void> {
+    const stream = new ReadableStream({
+      start(controller): void {
+        controller.enqueue(new TextEncoder().encode("Hello"));
+      },
+      pull(controller): void {
+        controller.error(new Error("stream failed"));
+      },
+    });
+    await assertThrowsAsync(
+      async () => {
+        await fetch("http://localhost:4545/echo_server", {
+          method: "POST",
+          body: stream,
+        });
+      },
+      Error,
+      "stream failed",
+    );
+  },
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchInitReadableStreamBodyInvalidChunk(): Promise<void> {
+    const stream = new ReadableStream({
+      start(controller): void {
+        controller.enqueue(new TextEncoder().encode("Hello"));
+        controller.enqueue("World");
+        controller.close();
+      },
+    });
+    await assertThrowsAsync(
+      async () => {
+        await fetch("http://localhost:4545/echo_server", {
+          method: "POST",
+          body: stream,
+        });
+      },
+      TypeError,
+      "Request body stream must only enqueue Uint8Array chunks",
+    );
+  },
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchInitReadableStreamBodyTypedArrayChunk(): Promise<
+    void
+  > {
+    const stream = new ReadableStream({
+      start(controller): void {
+        controller.enqueue(new Uint16Array([1, 2, 3]));
+        controller.close();
+      },
+    });
+    await assertThrowsAsync(
+      async () => {
+        await fetch("http://localhost:4545/echo_server", {
+          method: "POST",
+          body: stream,
+        });
+      },
+      TypeError,
+      "Request body stream must only enqueue Uint8Array chunks",
+    );
+  },
+);
+
 unitTest(
   { perms: { net: true } },
   async function fetchInitURLSearchParamsBody(): Promise<void> {
@@ -966,3 +1058,132 @@ unitTest(
     client.close();
   },
 );
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchAbortBeforeStart(): Promise<void> {
+    const controller = new AbortController();
+    controller.abort();
+    await assertThrowsAsync(
+      async () => {
+        await fetch("http://localhost:4545/cli/tests/fixture.json", {
+          signal: controller.signal,
+        });
+      },
+      DOMException,
+      "Ongoing fetch was aborted.",
+    );
+  },
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchAbortInFlight(): Promise<void> {
+    const controller = new AbortController();
+    // The echo server waits for the whole body, which never ends.
+    const body = new ReadableStream({
+      start(controller): void {
+        controller.enqueue(new TextEncoder().encode("pending"));
+      },
+    });
+    const promise = fetch("http://localhost:4545/echo_server", {
+      method: "POST",
+      body,
+      signal: controller.signal,
+    });
+    setTimeout(() => controller.abort(), 100);
+    const err = await assertThrowsAsync(
+      async () => {
+        await promise;
+      },
+      DOMException,
+    );
+    assertEquals((err as DOMException).name, "AbortError");
+  },
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchCustomHttpClientRedirectMode(): Promise<void> {
+    const client = Deno.createHttpClient({ redirect: "manual" });
+    const response = await fetch("http://localhost:4546/", { client });
+    assertEquals(response.type, "opaqueredirect");
+    // The option of the request takes precedence over the client's.
+    const followed = await fetch("http://localhost:4546/", {
+      client,
+      redirect: "follow",
+    });
+    assertEquals(followed.status, 200);
+    assertEquals(followed.redirected, true);
+    assertEquals(followed.url, "http://localhost:4545/");
+    await followed.text();
+    client.close();
+  },
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchTimeout(): Promise<void> {
+    // Accepts connections but never responds.
+    const listener = Deno.listen({ port: 4503 });
+    const accepted = listener.accept();
+    const client = Deno.createHttpClient({ timeout: 100 });
+    await assertThrowsAsync(
+      async () => {
+        await fetch("http://localhost:4503/", { client });
+      },
+      Deno.errors.TimedOut,
+      "The request timed out",
+    );
+    // The timeout of the request takes precedence over the client's.
+    const patient = Deno.createHttpClient({ timeout: 60000 });
+    await assertThrowsAsync(
+      async () => {
+        await fetch("http://localhost:4503/", {
+          client: patient,
+          timeout: 100,
+        });
+      },
+      Deno.errors.TimedOut,
+      "The request timed out",
+    );
+    (await accepted).close();
+    listener.close();
+    client.close();
+    patient.close();
+  },
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchRequestRedirectMode(): Promise<void> {
+    const request = new Request("http://localhost:4546/", {
+      redirect: "manual",
+    });
+    assertEquals(request.redirect, "manual");
+    assertEquals(request.clone().redirect, "manual");
+    assertEquals(new Request("http://localhost:4546/").redirect, "follow");
+    assertThrows((): void => {
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      new Request("http://localhost:4546/", { redirect: "none" as any });
+    }, TypeError);
+    const response = await fetch(request);
+    assertEquals(response.type, "opaqueredirect");
+  },
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchCrossOriginRedirectDropsAuthorization(): Promise<void> {
+    // Redirects to the `x-location` header, on another origin.
+    const response = await fetch("http://localhost:4550/a/b/c", {
+      headers: {
+        "x-location": "http://localhost:4545/auth_script.ts",
+        authorization: "Bearer abcdef123456789",
+      },
+    });
+    assertEquals(response.redirected, true);
+    assertEquals(response.status, 404);
+    await response.text();
+  },
+);
@@ -0,0 +1,53 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { unitTest, assert, assertEquals } from "./test_util.ts";
+
+unitTest({ perms: { net: true } }, async function httpServerBasic(): Promise<
+  void
+> {
+  const promise = (async (): Promise<void> => {
+    const listener = Deno.listen({ port: 4501 });
+    for await (const conn of listener) {
+      const httpConn = Deno.serveHttp(conn);
+      for await (const { request, respondWith } of httpConn) {
+        assertEquals(new URL(request.url).href, "http://127.0.0.1:4501/");
+        assertEquals(await request.text(), "");
+        respondWith(new Response("Hello World", { headers: { foo: "bar" } }));
+      }
+      break;
+    }
+    listener.close();
+  })();
+
+  const resp = await fetch("http://127.0.0.1:4501/", {
+    headers: { "connection": "close" },
+  });
+  const text = await resp.text();
+  assertEquals(text, "Hello World");
+  assertEquals(resp.headers.get("foo"), "bar");
+  await promise;
+});
+
+unitTest({ perms: { net: true } }, async function httpServerStreams(): Promise<
+  void
+> {
+  const promise = (async (): Promise<void> => {
+    const listener = Deno.listen({ port: 4501 });
+    const conn = await listener.accept();
+    const httpConn = Deno.serveHttp(conn);
+    const event = await httpConn.nextRequest();
+    assert(event);
+    const { request, respondWith } = event;
+    assertEquals(request.method, "POST");
+    // Echo the request body back as a streamed response.
+    await respondWith(new Response(request.body));
+    httpConn.close();
+    listener.close();
+  })();
+
+  const resp = await fetch("http://127.0.0.1:4501/", {
+    method: "POST",
+    body: "hello from the client",
+  });
+  assertEquals(await resp.text(), "hello from the client");
+  await promise;
+});
@@ -533,3 +533,47 @@ unitTest(
     await resolvable;
   },
 );
+
+unitTest(
+  { perms: { net: true } },
+  async function netTcpConnectLocalAddr(): Promise<void> {
+    const listener = Deno.listen({ hostname: "127.0.0.1", port: 3500 });
+    const [conn, accepted] = await Promise.all([
+      Deno.connect({
+        hostname: "127.0.0.1",
+        port: 3500,
+        localAddr: { hostname: "127.0.0.1", port: 4505 },
+      }),
+      listener.accept(),
+    ]);
+    const localAddr = conn.localAddr as Deno.NetAddr;
+    assertEquals(localAddr.hostname, "127.0.0.1");
+    assertEquals(localAddr.port, 4505);
+    assertEquals((accepted.remoteAddr as Deno.NetAddr).port, 4505);
+    conn.close();
+    accepted.close();
+    listener.close();
+  },
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function netTcpSocketOptions(): Promise<void> {
+    const listener = Deno.listen({ hostname: "127.0.0.1", port: 3500 });
+    const [conn, accepted] = await Promise.all([
+      Deno.connect({ hostname: "127.0.0.1", port: 3500 }),
+      listener.accept(),
+    ]);
+    conn.setNoDelay();
+    conn.setNoDelay(false);
+    conn.setKeepAlive(true, 30000);
+    conn.setKeepAlive(false);
+    accepted.setNoDelay();
+    accepted.setKeepAlive();
+
+    conn.close();
+    assertThrows(() => conn.setNoDelay(), Deno.errors.BadResource);
+    accepted.close();
+    listener.close();
+  },
+);
@@ -1,5 +1,10 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
-import { assertThrows, unitTest } from "./test_util.ts";
+import {
+  assertEquals,
+  assertNotEquals,
+  assertThrows,
+  unitTest,
+} from "./test_util.ts";
 
 unitTest(function testFnOverloading(): void {
   // just verifying that you can use this test definition syntax
@@ -25,3 +30,13 @@ unitTest(function nameOfTestCaseCantBeEmpty(): void {
     "The test name can't be empty",
   );
 });
+
+unitTest(function shuffleIsReproducible(): void {
+  // @ts-expect-error TypeScript (as of 3.7) does not support indexing namespaces by symbol
+  const { shuffle } = Deno[Deno.internal];
+  const items = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+  const first = shuffle([...items], 42);
+  assertEquals(shuffle([...items], 42), first);
+  assertNotEquals(first, items);
+  assertEquals([...first].sort(), items);
+});
@@ -5,6 +5,7 @@
 // Test runner automatically spawns subprocesses for each required permissions combination.
 
 import "./abort_controller_test.ts";
+import "./bench_test.ts";
 import "./blob_test.ts";
 import "./body_test.ts";
 import "./buffer_test.ts";
@@ -17,6 +18,7 @@ import "./custom_event_test.ts";
 import "./dir_test.ts";
 import "./dispatch_minimal_test.ts";
 import "./dispatch_json_test.ts";
+import "./dns_test.ts";
 import "./dom_exception_test.ts";
 import "./error_stack_test.ts";
 import "./event_test.ts";
@@ -32,6 +34,7 @@ import "./fs_events_test.ts";
 import "./get_random_values_test.ts";
 import "./globals_test.ts";
 import "./headers_test.ts";
+import "./http_test.ts";
 import "./internals_test.ts";
 import "./io_test.ts";
 import "./link_test.ts";
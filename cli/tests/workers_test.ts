@@ -7,7 +7,11 @@
 // yet implemented. Once it gets implemented this file should be
 // again moved to `cli/js/` as an unit test file.
 
-import { assert, assertEquals } from "../../std/testing/asserts.ts";
+import {
+  assert,
+  assertEquals,
+  assertThrows,
+} from "../../std/testing/asserts.ts";
 
 export interface ResolvableMethods<T> {
   resolve: (value?: T | PromiseLike<T>) => void;
@@ -316,3 +320,239 @@ Deno.test({
     w.terminate();
   },
 });
+
+Deno.test({
+  name: "worker postMessage structured clone",
+  fn: async function (): Promise<void> {
+    const promise = createResolvable();
+    const w = new Worker(
+      new URL("subdir/echo_worker.js", import.meta.url).href,
+      { type: "module" },
+    );
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    const cyclic: any = { name: "cyclic" };
+    cyclic.self = cyclic;
+    const bytes = new Uint8Array([1, 2, 3, 4]);
+    const message = {
+      map: new Map([["a", 1]]),
+      set: new Set([1, 2]),
+      date: new Date(0),
+      bigint: 12345678901234567890n,
+      numbers: [NaN, Infinity, -0, undefined],
+      regexp: /foo/gi,
+      bytes,
+      view: new Uint16Array(bytes.buffer, 2, 1),
+      error: new TypeError("bad"),
+      cyclic,
+    };
+    w.onmessage = (e): void => {
+      const data = e.data;
+      assertEquals(data.map.get("a"), 1);
+      assert(data.set.has(2));
+      assertEquals(data.date.getTime(), 0);
+      assertEquals(data.bigint, 12345678901234567890n);
+      assert(Number.isNaN(data.numbers[0]));
+      assertEquals(data.numbers[1], Infinity);
+      assert(Object.is(data.numbers[2], -0));
+      assertEquals(data.numbers.length, 4);
+      assertEquals(data.regexp.flags, "gi");
+      assertEquals(Array.from(data.bytes), [1, 2, 3, 4]);
+      assert(data.view.buffer === data.bytes.buffer);
+      assert(data.error instanceof TypeError);
+      assertEquals(data.error.message, "bad");
+      assert(data.cyclic.self === data.cyclic);
+      promise.resolve();
+    };
+    w.postMessage(message);
+    await promise;
+    w.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker postMessage throws DataCloneError",
+  fn: function (): void {
+    const w = new Worker(
+      new URL("subdir/echo_worker.js", import.meta.url).href,
+      { type: "module" },
+    );
+    let err;
+    try {
+      w.postMessage({ fn(): void {} });
+    } catch (e) {
+      err = e;
+    }
+    assert(err instanceof DOMException);
+    assertEquals(err.name, "DataCloneError");
+    w.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker postMessage transfers ArrayBuffers",
+  fn: async function (): Promise<void> {
+    const promise = createResolvable();
+    const w = new Worker(
+      new URL("subdir/echo_worker.js", import.meta.url).href,
+      { type: "module" },
+    );
+    const bytes = new Uint8Array([1, 2, 3, 4]);
+    w.onmessage = (e): void => {
+      assertEquals(Array.from(e.data), [1, 2, 3, 4]);
+      promise.resolve();
+    };
+    w.postMessage(bytes, [bytes.buffer]);
+    // The contents moved to the worker.
+    assertEquals(bytes.buffer.byteLength, 0);
+    assertEquals(bytes.byteLength, 0);
+    await promise;
+    w.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker postMessage throws DataCloneError for invalid transfer lists",
+  fn: function (): void {
+    const w = new Worker(
+      new URL("subdir/echo_worker.js", import.meta.url).href,
+      { type: "module" },
+    );
+    const buffer = new ArrayBuffer(4);
+    for (
+      const transfer of [
+        [buffer, buffer],
+        { transfer: [buffer, buffer] },
+        [new SharedArrayBuffer(4)],
+        [{}],
+      ]
+    ) {
+      let err;
+      try {
+        w.postMessage(buffer, transfer);
+      } catch (e) {
+        err = e;
+      }
+      assert(err instanceof DOMException);
+      assertEquals(err.name, "DataCloneError");
+    }
+    assertEquals(buffer.byteLength, 4);
+    w.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker shares SharedArrayBuffer",
+  fn: async function (): Promise<void> {
+    const promise = createResolvable();
+    const w = new Worker(
+      new URL("subdir/shared_array_buffer_worker.js", import.meta.url).href,
+      { type: "module" },
+    );
+    const sab = new SharedArrayBuffer(8);
+    const arr = new Int32Array(sab);
+    w.onmessage = (e): void => {
+      assert(e.data instanceof SharedArrayBuffer);
+      const received = new Int32Array(e.data);
+      assertEquals(received[1], 42);
+      // Writes on this side are visible through the original buffer.
+      Atomics.store(received, 1, 7);
+      assertEquals(Atomics.load(arr, 1), 7);
+      promise.resolve();
+    };
+    w.postMessage(sab);
+    Atomics.wait(arr, 0, 0, 5000);
+    assertEquals(Atomics.load(arr, 1), 42);
+    await promise;
+    w.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker with narrower permissions",
+  fn: async function (): Promise<void> {
+    const promise = createResolvable<boolean[]>();
+    const worker = new Worker(
+      new URL("subdir/read_check_worker.js", import.meta.url).href,
+      {
+        type: "module",
+        deno: {
+          namespace: true,
+          permissions: {
+            read: [new URL("subdir", import.meta.url)],
+            net: false,
+          },
+        },
+      },
+    );
+    worker.onmessage = (e): void => {
+      promise.resolve(e.data);
+    };
+    worker.postMessage([
+      new URL("subdir/read_check_worker.js", import.meta.url).href,
+      new URL("workers_test.ts", import.meta.url).href,
+    ]);
+    assertEquals(await promise, [true, false]);
+    worker.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker net permissions accept urls",
+  fn: async function (): Promise<void> {
+    const promise = createResolvable<string[]>();
+    const worker = new Worker(
+      new URL("subdir/net_check_worker.js", import.meta.url).href,
+      {
+        type: "module",
+        deno: {
+          namespace: true,
+          permissions: { net: ["http://localhost:4545"] },
+        },
+      },
+    );
+    worker.onmessage = (e): void => {
+      promise.resolve(e.data);
+    };
+    worker.postMessage([
+      "http://localhost:4545/cli/tests/",
+      "http://localhost:4546/",
+    ]);
+    assertEquals(await promise, ["granted", "prompt"]);
+    worker.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker permissions reject invalid options",
+  fn: function (): void {
+    const url = new URL("subdir/read_check_worker.js", import.meta.url).href;
+    // deno-lint-ignore no-explicit-any
+    const create = (permissions: any): Worker =>
+      new Worker(url, {
+        type: "module",
+        deno: { namespace: true, permissions },
+      });
+    assertThrows(() => create("none"), TypeError);
+    assertThrows(() => create({ read: "none" }), TypeError);
+    assertThrows(() => create({ reed: false }), Deno.errors.InvalidData);
+  },
+});
+
+Deno.test({
+  name: "worker permissions can't escalate",
+  fn: function (): void {
+    let err;
+    try {
+      new Worker(
+        new URL("subdir/read_check_worker.js", import.meta.url).href,
+        {
+          type: "module",
+          deno: { namespace: true, permissions: { write: true } },
+        },
+      );
+    } catch (e) {
+      err = e;
+    }
+    assert(err instanceof Deno.errors.PermissionDenied);
+  },
+});
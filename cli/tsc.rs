@@ -702,13 +702,10 @@ impl TsCompiler {
       let mut g = lockfile.lock().unwrap();
 
       for graph_file in &module_graph_files {
-        let check_passed =
-          g.check_or_insert(&graph_file.url, &graph_file.source_code);
-
-        if !check_passed {
+        if let Err(mismatch) = g.check_or_insert(graph_file) {
           eprintln!(
             "Subresource integrity check failed --lock={}\n{}",
-            g.filename, graph_file.url
+            g.filename, mismatch
           );
           std::process::exit(10);
         }
@@ -0,0 +1,504 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! `deno vendor` copies the remote dependencies of modules into a local
+//! directory, along with an import map redirecting their URLs to the copies.
+//!
+//! Remote modules are saved at paths mirroring their URL, so
+//! `https://deno.land/std@0.67.0/fs/mod.ts` is saved as
+//! `deno.land/std@0.67.0/fs/mod.ts` and a single import map entry per origin
+//! covers most modules. Relative imports between vendored modules then keep
+//! working without rewriting them. The path of a module differs from its URL
+//! when it has a query string or when its extension doesn't match its media
+//! type, and redirected URLs point to the path of their target. These get
+//! exact entries, and relative imports reaching them from vendored modules get
+//! entries in the scope of the importing origin.
+
+use crate::checksum;
+use crate::file_fetcher::map_file_extension;
+use crate::fs as deno_fs;
+use crate::global_state::GlobalState;
+use crate::module_graph::ModuleGraph;
+use crate::module_graph::ModuleGraphFile;
+use crate::module_graph::ModuleGraphLoader;
+use crate::msg::MediaType;
+use crate::permissions::Permissions;
+use deno_core::ErrBox;
+use deno_core::ModuleSpecifier;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use url::Url;
+
+/// Name of the import map written to the output directory.
+pub const IMPORT_MAP_FILENAME: &str = "import_map.json";
+
+fn is_remote(url: &Url) -> bool {
+  url.scheme() == "http" || url.scheme() == "https"
+}
+
+/// Ex: `deno.land` or `localhost_PORT4545`, like the directories of the HTTP
+/// cache.
+fn host_dir(url: &Url) -> String {
+  let host = url.host_str().unwrap();
+  match url.port() {
+    Some(port) => format!("{}_PORT{}", host, port),
+    None => host.to_string(),
+  }
+}
+
+fn extension_for_media_type(media_type: MediaType) -> Option<&'static str> {
+  match media_type {
+    MediaType::JavaScript => Some("js"),
+    MediaType::JSX => Some("jsx"),
+    MediaType::TypeScript => Some("ts"),
+    MediaType::TSX => Some("tsx"),
+    MediaType::Json => Some("json"),
+    MediaType::Wasm => Some("wasm"),
+    MediaType::Unknown => None,
+  }
+}
+
+/// Returns the URL of the directory of the vendored modules of the origin of
+/// `url`.
+fn origin_dir_url(base_url: &Url, url: &Url) -> Url {
+  base_url.join(&format!("{}/", host_dir(url))).unwrap()
+}
+
+/// Returns the URL `url` would be vendored at if its path was kept as-is.
+fn mirror_url(base_url: &Url, url: &Url) -> Url {
+  // The path of `url` is already percent-encoded and starts with "/".
+  base_url
+    .join(&format!("{}{}", host_dir(url), url.path()))
+    .unwrap()
+}
+
+/// Returns the URL of the file `url` is vendored at. Queries are replaced by
+/// a hash of them, and an extension is appended when the one of the URL
+/// doesn't match `media_type`, since local files get their media type from
+/// their extension.
+pub fn local_url(base_url: &Url, url: &Url, media_type: MediaType) -> Url {
+  let mut local_url = mirror_url(base_url, url);
+  let mut path = local_url.path().to_string();
+  if path.ends_with('/') {
+    path.push_str("index");
+  }
+  if let Some(query) = url.query() {
+    let hash = checksum::gen(&[query.as_bytes()]);
+    path.push_str(&format!("_{}", &hash[..8]));
+  }
+  if let Some(extension) = extension_for_media_type(media_type) {
+    let filename = path.rsplit('/').next().unwrap();
+    if map_file_extension(Path::new(filename)) != media_type {
+      path.push_str(&format!(".{}", extension));
+    }
+  }
+  local_url.set_path(&path);
+  local_url
+}
+
+/// Formats `url` relative to the import map when it's located in the output
+/// directory.
+fn relative_to_base(base_url: &Url, url: &Url) -> String {
+  match url.as_str().strip_prefix(base_url.as_str()) {
+    Some(relative) => format!("./{}", relative),
+    None => url.to_string(),
+  }
+}
+
+/// Returns a relative specifier of the file `to` from the module `from`.
+fn relative_specifier(from: &Url, to: &Url) -> String {
+  let mut from_dir: Vec<&str> = from.path().split('/').collect();
+  from_dir.pop();
+  let to: Vec<&str> = to.path().split('/').collect();
+  let common = from_dir.iter().zip(&to).take_while(|(a, b)| a == b).count();
+  let mut parts = vec![".."; from_dir.len() - common];
+  parts.extend(&to[common..]);
+  let relative = parts.join("/");
+  if relative.starts_with("../") {
+    relative
+  } else {
+    format!("./{}", relative)
+  }
+}
+
+fn is_relative_specifier(specifier: &str) -> bool {
+  specifier.starts_with("./")
+    || specifier.starts_with("../")
+    || specifier.starts_with('/')
+}
+
+/// Follows the redirects of `specifier` to the module it resolves to.
+fn resolve_redirects<'a>(
+  graph: &'a ModuleGraph,
+  specifier: &str,
+) -> Option<&'a ModuleGraphFile> {
+  let mut file = graph.get(specifier)?;
+  // Same limit as the file fetcher.
+  for _ in 0..10 {
+    match &file.redirect {
+      Some(redirect) => file = graph.get(redirect)?,
+      None => return Some(file),
+    }
+  }
+  None
+}
+
+/// Returns the vendored URL of the module `specifier` resolves to, if it's a
+/// remote module.
+fn resolve_local_url(
+  graph: &ModuleGraph,
+  base_url: &Url,
+  specifier: &str,
+) -> Option<Url> {
+  let file = resolve_redirects(graph, specifier)?;
+  let url = Url::parse(&file.url).ok()?;
+  if !is_remote(&url) {
+    return None;
+  }
+  Some(local_url(base_url, &url, file.media_type))
+}
+
+/// Builds the import map redirecting the remote modules of `graph` to their
+/// copies in the directory of `base_url`, where the import map is saved.
+pub fn build_import_map(graph: &ModuleGraph, base_url: &Url) -> Value {
+  let mut imports: BTreeMap<String, String> = BTreeMap::new();
+  let mut scopes: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+
+  for (specifier, file) in graph {
+    let url = match Url::parse(specifier) {
+      Ok(url) if is_remote(&url) => url,
+      _ => continue,
+    };
+    let local_url = match resolve_local_url(graph, base_url, specifier) {
+      Some(local_url) => local_url,
+      None => continue,
+    };
+
+    imports.insert(
+      format!("{}/", url.origin().ascii_serialization()),
+      relative_to_base(base_url, &origin_dir_url(base_url, &url)),
+    );
+    if mirror_url(base_url, &url) != local_url {
+      imports.insert(url.to_string(), relative_to_base(base_url, &local_url));
+    }
+    if file.redirect.is_some() {
+      continue;
+    }
+
+    // Relative imports of vendored modules are resolved against their local
+    // URL, and don't reach the exact entries above.
+    let scope = relative_to_base(base_url, &origin_dir_url(base_url, &url));
+    for import in &file.imports {
+      if !is_relative_specifier(&import.specifier) {
+        continue;
+      }
+      let dependency_url = match resolve_local_url(
+        graph,
+        base_url,
+        &import.resolved_specifier.to_string(),
+      ) {
+        Some(dependency_url) => dependency_url,
+        None => continue,
+      };
+      let resolved_url = match local_url.join(&import.specifier) {
+        Ok(resolved_url) => resolved_url,
+        Err(_) => continue,
+      };
+      if resolved_url != dependency_url {
+        scopes.entry(scope.clone()).or_default().insert(
+          relative_to_base(base_url, &resolved_url),
+          relative_to_base(base_url, &dependency_url),
+        );
+      }
+    }
+  }
+
+  if scopes.is_empty() {
+    json!({ "imports": imports })
+  } else {
+    json!({ "imports": imports, "scopes": scopes })
+  }
+}
+
+/// Returns the path of `local_url`, the URL the module `url` is vendored at.
+/// `Url::to_file_path()` decodes percent-encoded separators, so a URL path
+/// like `/..%2F..%2Fetc/passwd` could otherwise escape `output_dir`.
+fn local_path(
+  output_dir: &Path,
+  url: &Url,
+  local_url: &Url,
+) -> Result<PathBuf, ErrBox> {
+  let escapes_output_dir = |path: &Path| {
+    path.components().any(|c| c == Component::ParentDir)
+      || !path.starts_with(output_dir)
+  };
+  match local_url.to_file_path() {
+    Ok(path) if !escapes_output_dir(&path) => Ok(path),
+    _ => Err(ErrBox::error(format!(
+      "Cannot vendor {}, its path is outside of {}",
+      url,
+      output_dir.display()
+    ))),
+  }
+}
+
+/// Copies the remote modules of `graph` from the HTTP cache to the directory
+/// of `base_url`. Vendored JavaScript modules which had an
+/// `X-TypeScript-Types` header get a reference to their vendored types
+/// instead. Returns the number of vendored modules.
+fn write_modules(
+  global_state: &GlobalState,
+  graph: &ModuleGraph,
+  base_url: &Url,
+) -> Result<usize, ErrBox> {
+  let output_dir = base_url.to_file_path().unwrap();
+  let mut count = 0;
+  for (specifier, file) in graph {
+    let url = match Url::parse(specifier) {
+      Ok(url) if is_remote(&url) && file.redirect.is_none() => url,
+      _ => continue,
+    };
+    let local_url = local_url(base_url, &url, file.media_type);
+    let path = local_path(&output_dir, &url, &local_url)?;
+
+    let (mut cached_file, _) =
+      global_state.file_fetcher.http_cache.get(&url)?;
+    let mut source = vec![];
+    cached_file.read_to_end(&mut source)?;
+
+    if let Some(type_header) = file.type_headers.first() {
+      if let Some(types_url) = resolve_local_url(
+        graph,
+        base_url,
+        &type_header.resolved_specifier.to_string(),
+      ) {
+        let reference = format!(
+          "/// <reference types=\"{}\" />\n",
+          relative_specifier(&local_url, &types_url)
+        );
+        source = [reference.into_bytes(), source].concat();
+      }
+    }
+
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    deno_fs::write_file(&path, source, 0o666)?;
+    count += 1;
+  }
+  Ok(count)
+}
+
+/// Downloads the module graphs of `specifiers` and vendors their remote
+/// modules into `output_dir`, along with an import map. Returns the number of
+/// vendored modules.
+pub async fn vendor(
+  global_state: &GlobalState,
+  specifiers: Vec<ModuleSpecifier>,
+  output_dir: &Path,
+) -> Result<usize, ErrBox> {
+  let mut module_graph_loader = ModuleGraphLoader::new(
+    global_state.file_fetcher.clone(),
+    global_state.maybe_import_map.clone(),
+    Permissions::allow_all(),
+    false,
+    true,
+  );
+  for specifier in specifiers {
+    module_graph_loader.add_to_graph(&specifier, None).await?;
+  }
+  let graph = module_graph_loader.get_graph();
+
+  std::fs::create_dir_all(output_dir)?;
+  let output_dir = output_dir.canonicalize()?;
+  let base_url = Url::from_directory_path(&output_dir).unwrap();
+  let count = write_modules(global_state, &graph, &base_url)?;
+
+  let import_map = build_import_map(&graph, &base_url);
+  let import_map_path = output_dir.join(IMPORT_MAP_FILENAME);
+  let json = serde_json::to_string_pretty(&import_map)? + "\n";
+  deno_fs::write_file(&import_map_path, json, 0o666)?;
+
+  Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::module_graph::ImportDescriptor;
+  use crate::swc_util::Location;
+
+  fn graph_file(
+    url: &str,
+    redirect: Option<&str>,
+    media_type: MediaType,
+    imports: Vec<(&str, &str)>,
+  ) -> ModuleGraphFile {
+    let imports = imports
+      .into_iter()
+      .map(|(specifier, resolved)| ImportDescriptor {
+        specifier: specifier.to_string(),
+        resolved_specifier: ModuleSpecifier::resolve_url(resolved).unwrap(),
+        type_directive: None,
+        resolved_type_directive: None,
+        location: Location {
+          filename: url.to_string(),
+          line: 0,
+          col: 0,
+        },
+      })
+      .collect();
+    ModuleGraphFile {
+      specifier: url.to_string(),
+      url: url.to_string(),
+      redirect: redirect.map(String::from),
+      filename: "".to_string(),
+      version_hash: "".to_string(),
+      imports,
+      referenced_files: vec![],
+      lib_directives: vec![],
+      types_directives: vec![],
+      type_headers: vec![],
+      media_type,
+      source_code: "".to_string(),
+    }
+  }
+
+  #[test]
+  fn test_local_url() {
+    let base_url = Url::parse("file:///project/vendor/").unwrap();
+    let cases = vec![
+      (
+        "https://deno.land/std@0.67.0/fs/mod.ts",
+        MediaType::TypeScript,
+        "file:///project/vendor/deno.land/std@0.67.0/fs/mod.ts",
+      ),
+      (
+        "http://localhost:4545/cli/tests/subdir/no_ext",
+        MediaType::TypeScript,
+        "file:///project/vendor/localhost_PORT4545/cli/tests/subdir/no_ext.ts",
+      ),
+      (
+        "https://example.com/mismatch.ts",
+        MediaType::JavaScript,
+        "file:///project/vendor/example.com/mismatch.ts.js",
+      ),
+      (
+        "https://example.com/pkg/",
+        MediaType::JavaScript,
+        "file:///project/vendor/example.com/pkg/index.js",
+      ),
+      (
+        "https://example.com/pkg.js?target=deno",
+        MediaType::JavaScript,
+        "file:///project/vendor/example.com/pkg.js_d30a221b.js",
+      ),
+    ];
+    for (url, media_type, expected) in cases {
+      let url = Url::parse(url).unwrap();
+      assert_eq!(local_url(&base_url, &url, media_type).as_str(), expected);
+    }
+  }
+
+  #[test]
+  fn test_local_path() {
+    let (base_url, output_dir) = if cfg!(windows) {
+      (
+        "file:///C:/project/vendor/",
+        PathBuf::from("C:\\project\\vendor"),
+      )
+    } else {
+      ("file:///project/vendor/", PathBuf::from("/project/vendor"))
+    };
+    let base_url = Url::parse(base_url).unwrap();
+
+    let url = Url::parse("https://deno.land/std@0.67.0/fs/mod.ts").unwrap();
+    let vendored_url = local_url(&base_url, &url, MediaType::TypeScript);
+    assert_eq!(
+      local_path(&output_dir, &url, &vendored_url).unwrap(),
+      output_dir.join("deno.land/std@0.67.0/fs/mod.ts")
+    );
+
+    let url = Url::parse("https://example.com/..%2F..%2F..%2Fevil.ts").unwrap();
+    let vendored_url = local_url(&base_url, &url, MediaType::TypeScript);
+    assert!(local_path(&output_dir, &url, &vendored_url).is_err());
+  }
+
+  #[test]
+  fn test_relative_specifier() {
+    let from = Url::parse("file:///vendor/example.com/a/b/mod.js").unwrap();
+    let to = Url::parse("file:///vendor/example.com/a/b/mod.d.ts").unwrap();
+    assert_eq!(relative_specifier(&from, &to), "./mod.d.ts");
+    let to = Url::parse("file:///vendor/deno.land/types.d.ts").unwrap();
+    assert_eq!(
+      relative_specifier(&from, &to),
+      "../../../deno.land/types.d.ts"
+    );
+  }
+
+  #[test]
+  fn test_build_import_map() {
+    let base_url = Url::parse("file:///project/vendor/").unwrap();
+    let mut graph = ModuleGraph::new();
+    let files = vec![
+      graph_file(
+        "file:///project/main.ts",
+        None,
+        MediaType::TypeScript,
+        vec![(
+          "https://deno.land/std/mod.ts",
+          "https://deno.land/std/mod.ts",
+        )],
+      ),
+      graph_file(
+        "https://deno.land/std/mod.ts",
+        Some("https://deno.land/std@0.67.0/mod.ts"),
+        MediaType::TypeScript,
+        vec![],
+      ),
+      graph_file(
+        "https://deno.land/std@0.67.0/mod.ts",
+        None,
+        MediaType::TypeScript,
+        vec![
+          ("./fs.ts", "https://deno.land/std@0.67.0/fs.ts"),
+          ("./no_ext", "https://deno.land/std@0.67.0/no_ext"),
+        ],
+      ),
+      graph_file(
+        "https://deno.land/std@0.67.0/fs.ts",
+        None,
+        MediaType::TypeScript,
+        vec![],
+      ),
+      graph_file(
+        "https://deno.land/std@0.67.0/no_ext",
+        None,
+        MediaType::TypeScript,
+        vec![],
+      ),
+    ];
+    for file in files {
+      graph.insert(file.specifier.clone(), file);
+    }
+
+    assert_eq!(
+      build_import_map(&graph, &base_url),
+      json!({
+        "imports": {
+          "https://deno.land/": "./deno.land/",
+          "https://deno.land/std/mod.ts": "./deno.land/std@0.67.0/mod.ts",
+          "https://deno.land/std@0.67.0/no_ext":
+            "./deno.land/std@0.67.0/no_ext.ts",
+        },
+        "scopes": {
+          "./deno.land/": {
+            "./deno.land/std@0.67.0/no_ext":
+              "./deno.land/std@0.67.0/no_ext.ts",
+          },
+        },
+      })
+    );
+  }
+}
@@ -134,6 +134,8 @@ impl WebWorker {
         ops::fs_events::init(&state);
         ops::plugin::init(&state);
         ops::net::init(&state);
+        ops::dns::init(&state);
+        ops::http::init(&state);
         ops::tls::init(&state);
         ops::os::init(&state);
         ops::permissions::init(&state);
@@ -212,8 +214,8 @@ impl Future for WebWorker {
     {
       match r {
         Some(msg) => {
-          let msg = String::from_utf8(msg.to_vec()).unwrap();
-          let script = format!("workerMessageRecvCallback({})", msg);
+          let inbox_id = worker.state.inbox.deposit(msg);
+          let script = format!("workerMessageRecvCallback({})", inbox_id);
 
           if let Err(e) = worker.execute(&script) {
             // If execution was terminated during message callback then
@@ -248,6 +250,7 @@ mod tests {
   use crate::state::State;
   use crate::tokio_util;
   use crate::worker::WorkerEvent;
+  use crate::worker::WorkerMessage;
 
   fn create_test_worker() -> WebWorker {
     let state = State::mock("./hello.js");
@@ -262,6 +265,13 @@ mod tests {
       .unwrap();
     worker
   }
+  fn take_message(event: Option<WorkerEvent>) -> WorkerMessage {
+    match event {
+      Some(WorkerEvent::Message(msg)) => msg,
+      _ => unreachable!(),
+    }
+  }
+
   #[test]
   fn test_worker_messages() {
     let (handle_sender, handle_receiver) =
@@ -269,17 +279,21 @@ mod tests {
 
     let join_handle = std::thread::spawn(move || {
       let mut worker = create_test_worker();
+      // Messages can only be serialized by an isolate, so the worker sends
+      // the ones the host is going to post back to it.
       let source = r#"
           onmessage = function(e) {
             console.log("msg from main script", e.data);
             if (e.data == "exit") {
               return close();
             } else {
-              console.assert(e.data === "hi");
+              console.assert(e.data.join() === "1,2,3");
             }
-            postMessage([1, 2, 3]);
+            postMessage(e.data);
             console.log("after postMessage");
           }
+          postMessage([1, 2, 3]);
+          postMessage("exit");
           "#;
       worker.execute(source).unwrap();
       let handle = worker.thread_safe_handle();
@@ -291,30 +305,18 @@ mod tests {
     let mut handle = handle_receiver.recv().unwrap();
 
     tokio_util::run_basic(async move {
-      let msg = json!("hi").to_string().into_boxed_str().into_boxed_bytes();
-      let r = handle.post_message(msg.clone());
-      assert!(r.is_ok());
-
-      let maybe_msg = handle.get_event().await.unwrap();
-      assert!(maybe_msg.is_some());
+      let msg = take_message(handle.get_event().await.unwrap());
+      let exit_msg = take_message(handle.get_event().await.unwrap());
 
-      let r = handle.post_message(msg.clone());
+      let data = msg.data.clone();
+      let r = handle.post_message(msg);
       assert!(r.is_ok());
 
-      let maybe_msg = handle.get_event().await.unwrap();
-      assert!(maybe_msg.is_some());
-      match maybe_msg {
-        Some(WorkerEvent::Message(buf)) => {
-          assert_eq!(*buf, *b"[1,2,3]");
-        }
-        _ => unreachable!(),
-      }
+      // The worker echoes the message, which serializes to the same bytes.
+      let echo = take_message(handle.get_event().await.unwrap());
+      assert_eq!(echo.data, data);
 
-      let msg = json!("exit")
-        .to_string()
-        .into_boxed_str()
-        .into_boxed_bytes();
-      let r = handle.post_message(msg);
+      let r = handle.post_message(exit_msg);
       assert!(r.is_ok());
       let event = handle.get_event().await.unwrap();
       assert!(event.is_none());
@@ -330,7 +332,9 @@ mod tests {
 
     let join_handle = std::thread::spawn(move || {
       let mut worker = create_test_worker();
-      worker.execute("onmessage = () => { close(); }").unwrap();
+      worker
+        .execute("onmessage = () => { close(); }; postMessage(null);")
+        .unwrap();
       let handle = worker.thread_safe_handle();
       handle_sender.send(handle).unwrap();
       let r = tokio_util::run_basic(worker);
@@ -340,8 +344,8 @@ mod tests {
     let mut handle = handle_receiver.recv().unwrap();
 
     tokio_util::run_basic(async move {
-      let msg = json!("hi").to_string().into_boxed_str().into_boxed_bytes();
-      let r = handle.post_message(msg.clone());
+      let msg = take_message(handle.get_event().await.unwrap());
+      let r = handle.post_message(msg);
       assert!(r.is_ok());
       let event = handle.get_event().await.unwrap();
       assert!(event.is_none());
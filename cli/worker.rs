@@ -12,6 +12,7 @@ use deno_core::JsRuntime;
 use deno_core::ModuleId;
 use deno_core::ModuleSpecifier;
 use deno_core::StartupData;
+use deno_core::ZeroCopyBuf;
 use futures::channel::mpsc;
 use futures::future::FutureExt;
 use futures::stream::StreamExt;
@@ -28,30 +29,53 @@ use std::task::Poll;
 use tokio::sync::Mutex as AsyncMutex;
 use url::Url;
 
+/// A message passed between a worker and its host, serialized with
+/// `Deno.core.serialize()`.
+pub type WorkerMessage = deno_core::SerializedValue;
+
+/// Builds a `WorkerMessage` from the buffers passed to a post message op: the
+/// serialized data, followed by the `array_buffers` ArrayBuffers transferred
+/// with it, followed by the SharedArrayBuffers it references.
+pub fn worker_message_from_bufs(
+  bufs: &[ZeroCopyBuf],
+  array_buffers: usize,
+) -> Result<WorkerMessage, ErrBox> {
+  let (data, buffers) = match bufs.split_first() {
+    Some(split) if split.1.len() >= array_buffers => split,
+    _ => return Err(ErrBox::type_error("Invalid number of arguments")),
+  };
+  let (array_buffers, shared_array_buffers) = buffers.split_at(array_buffers);
+  Ok(WorkerMessage {
+    data: Vec::from(&**data).into_boxed_slice(),
+    array_buffers: array_buffers.to_vec(),
+    shared_array_buffers: shared_array_buffers.to_vec(),
+  })
+}
+
 /// Events that are sent to host from child
 /// worker.
 pub enum WorkerEvent {
-  Message(Box<[u8]>),
+  Message(WorkerMessage),
   Error(ErrBox),
   TerminalError(ErrBox),
 }
 
 pub struct WorkerChannelsInternal {
   pub sender: mpsc::Sender<WorkerEvent>,
-  pub receiver: mpsc::Receiver<Box<[u8]>>,
+  pub receiver: mpsc::Receiver<WorkerMessage>,
 }
 
 #[derive(Clone)]
 pub struct WorkerHandle {
-  pub sender: mpsc::Sender<Box<[u8]>>,
+  pub sender: mpsc::Sender<WorkerMessage>,
   pub receiver: Arc<AsyncMutex<mpsc::Receiver<WorkerEvent>>>,
 }
 
 impl WorkerHandle {
   /// Post message to worker as a host.
-  pub fn post_message(&self, buf: Box<[u8]>) -> Result<(), ErrBox> {
+  pub fn post_message(&self, msg: WorkerMessage) -> Result<(), ErrBox> {
     let mut sender = self.sender.clone();
-    sender.try_send(buf)?;
+    sender.try_send(msg)?;
     Ok(())
   }
 
@@ -64,7 +88,7 @@ impl WorkerHandle {
 }
 
 fn create_channels() -> (WorkerChannelsInternal, WorkerHandle) {
-  let (in_tx, in_rx) = mpsc::channel::<Box<[u8]>>(1);
+  let (in_tx, in_rx) = mpsc::channel::<WorkerMessage>(1);
   let (out_tx, out_rx) = mpsc::channel::<WorkerEvent>(1);
   let internal_channels = WorkerChannelsInternal {
     sender: out_tx,
@@ -119,16 +143,24 @@ impl Worker {
       core_state.set_js_error_create_fn(move |core_js_error| {
         JsError::create(core_js_error, &global_state.ts_compiler)
       });
+      core_state.set_inbox(state.inbox.clone());
     }
 
     let inspector = {
       let global_state = &state.global_state;
-      global_state
+      let inspect = global_state
         .flags
         .inspect
-        .or(global_state.flags.inspect_brk)
-        .filter(|_| !state.is_internal)
-        .map(|inspector_host| DenoInspector::new(&mut isolate, inspector_host))
+        .or(global_state.flags.inspect_brk);
+      // Coverage is collected through a local inspector session, so the
+      // main worker needs an inspector even if no debugger will connect.
+      let should_create_inspector =
+        inspect.is_some() || (global_state.flags.coverage && state.is_main);
+      if should_create_inspector && !state.is_internal {
+        Some(DenoInspector::new(&mut isolate, inspect))
+      } else {
+        None
+      }
     };
 
     let (internal_channels, external_channels) = create_channels();
@@ -263,10 +295,12 @@ impl MainWorker {
       ops::runtime::init(&state);
       ops::runtime_compiler::init(&state);
       ops::errors::init(&state);
+      ops::dns::init(&state);
       ops::fetch::init(&state);
       ops::websocket::init(&state);
       ops::fs::init(&state);
       ops::fs_events::init(&state);
+      ops::http::init(&state);
       ops::idna::init(&state);
       ops::io::init(&state);
       ops::plugin::init(&state);
@@ -279,6 +313,7 @@ impl MainWorker {
       ops::repl::init(&state);
       ops::resources::init(&state);
       ops::signal::init(&state);
+      ops::testing::init(&state);
       ops::timers::init(&state);
       ops::tty::init(&state);
       ops::worker_host::init(&state);
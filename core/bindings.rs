@@ -14,6 +14,8 @@ use std::convert::TryFrom;
 use std::option::Option;
 use url::Url;
 use v8::MapFnTo;
+use v8::ValueDeserializerHelper;
+use v8::ValueSerializerHelper;
 
 lazy_static! {
   pub static ref EXTERNAL_REFERENCES: v8::ExternalReferences =
@@ -54,6 +56,15 @@ lazy_static! {
       v8::ExternalReference {
         function: get_proxy_details.map_fn_to()
       },
+      v8::ExternalReference {
+        function: serialize.map_fn_to()
+      },
+      v8::ExternalReference {
+        function: deserialize.map_fn_to()
+      },
+      v8::ExternalReference {
+        function: take_from_inbox.map_fn_to()
+      },
     ]);
 }
 
@@ -61,24 +72,18 @@ pub fn script_origin<'a>(
   s: &mut v8::HandleScope<'a>,
   resource_name: v8::Local<'a, v8::String>,
 ) -> v8::ScriptOrigin<'a> {
-  let resource_line_offset = v8::Integer::new(s, 0);
-  let resource_column_offset = v8::Integer::new(s, 0);
-  let resource_is_shared_cross_origin = v8::Boolean::new(s, false);
-  let script_id = v8::Integer::new(s, 123);
   let source_map_url = v8::String::new(s, "").unwrap();
-  let resource_is_opaque = v8::Boolean::new(s, true);
-  let is_wasm = v8::Boolean::new(s, false);
-  let is_module = v8::Boolean::new(s, false);
   v8::ScriptOrigin::new(
+    s,
     resource_name.into(),
-    resource_line_offset,
-    resource_column_offset,
-    resource_is_shared_cross_origin,
-    script_id,
+    0,
+    0,
+    false,
+    123,
     source_map_url.into(),
-    resource_is_opaque,
-    is_wasm,
-    is_module,
+    true,
+    false,
+    false,
   )
 }
 
@@ -86,24 +91,18 @@ pub fn module_origin<'a>(
   s: &mut v8::HandleScope<'a>,
   resource_name: v8::Local<'a, v8::String>,
 ) -> v8::ScriptOrigin<'a> {
-  let resource_line_offset = v8::Integer::new(s, 0);
-  let resource_column_offset = v8::Integer::new(s, 0);
-  let resource_is_shared_cross_origin = v8::Boolean::new(s, false);
-  let script_id = v8::Integer::new(s, 123);
   let source_map_url = v8::String::new(s, "").unwrap();
-  let resource_is_opaque = v8::Boolean::new(s, true);
-  let is_wasm = v8::Boolean::new(s, false);
-  let is_module = v8::Boolean::new(s, true);
   v8::ScriptOrigin::new(
+    s,
     resource_name.into(),
-    resource_line_offset,
-    resource_column_offset,
-    resource_is_shared_cross_origin,
-    script_id,
+    0,
+    0,
+    false,
+    123,
     source_map_url.into(),
-    resource_is_opaque,
-    is_wasm,
-    is_module,
+    true,
+    false,
+    true,
   )
 }
 
@@ -196,6 +195,25 @@ pub fn initialize_context<'s>(
     get_proxy_details_val.into(),
   );
 
+  let serialize_key = v8::String::new(scope, "serialize").unwrap();
+  let serialize_tmpl = v8::FunctionTemplate::new(scope, serialize);
+  let serialize_val = serialize_tmpl.get_function(scope).unwrap();
+  core_val.set(scope, serialize_key.into(), serialize_val.into());
+
+  let deserialize_key = v8::String::new(scope, "deserialize").unwrap();
+  let deserialize_tmpl = v8::FunctionTemplate::new(scope, deserialize);
+  let deserialize_val = deserialize_tmpl.get_function(scope).unwrap();
+  core_val.set(scope, deserialize_key.into(), deserialize_val.into());
+
+  let take_from_inbox_key = v8::String::new(scope, "takeFromInbox").unwrap();
+  let take_from_inbox_tmpl = v8::FunctionTemplate::new(scope, take_from_inbox);
+  let take_from_inbox_val = take_from_inbox_tmpl.get_function(scope).unwrap();
+  core_val.set(
+    scope,
+    take_from_inbox_key.into(),
+    take_from_inbox_val.into(),
+  );
+
   let shared_key = v8::String::new(scope, "shared").unwrap();
   core_val.set_accessor(scope, shared_key.into(), shared_getter);
 
@@ -229,6 +247,7 @@ pub extern "C" fn host_import_module_dynamically_callback(
   context: v8::Local<v8::Context>,
   referrer: v8::Local<v8::ScriptOrModule>,
   specifier: v8::Local<v8::String>,
+  _import_assertions: v8::Local<v8::FixedArray>,
 ) -> *mut v8::Promise {
   let scope = &mut unsafe { v8::CallbackScope::new(context) };
 
@@ -296,7 +315,7 @@ pub extern "C" fn promise_reject_callback(message: v8::PromiseRejectMessage) {
 
   match message.get_event() {
     v8::PromiseRejectEvent::PromiseRejectWithNoHandler => {
-      let error = message.get_value();
+      let error = message.get_value().unwrap();
       let error_global = v8::Global::new(scope, error);
       state
         .pending_promise_exceptions
@@ -723,6 +742,7 @@ fn shared_getter(
 pub fn module_resolve_callback<'s>(
   context: v8::Local<'s, v8::Context>,
   specifier: v8::Local<'s, v8::String>,
+  _import_assertions: v8::Local<'s, v8::FixedArray>,
   referrer: v8::Local<'s, v8::Module>,
 ) -> Option<v8::Local<'s, v8::Module>> {
   let scope = &mut unsafe { v8::CallbackScope::new(context) };
@@ -737,13 +757,14 @@ pub fn module_resolve_callback<'s>(
     .expect("ModuleInfo not found")
     .name
     .to_string();
-  let len_ = referrer.get_module_requests_length();
+  let module_requests = referrer.get_module_requests();
 
   let specifier_str = specifier.to_rust_string_lossy(scope);
 
-  for i in 0..len_ {
-    let req = referrer.get_module_request(i);
-    let req_str = req.to_rust_string_lossy(scope);
+  for i in 0..module_requests.length() {
+    let req: v8::Local<v8::ModuleRequest> =
+      module_requests.get(scope, i).unwrap().into();
+    let req_str = req.get_specifier().to_rust_string_lossy(scope);
 
     if req_str == specifier_str {
       let id = state.module_resolve_cb(&req_str, referrer_id);
@@ -856,6 +877,365 @@ fn get_proxy_details(
   rv.set(proxy_details.into());
 }
 
+struct SerializeDelegate<'a> {
+  shared_array_buffers: &'a mut Vec<v8::Global<v8::SharedArrayBuffer>>,
+}
+
+impl<'a> v8::ValueSerializerImpl for SerializeDelegate<'a> {
+  fn throw_data_clone_error<'s>(
+    &mut self,
+    scope: &mut v8::HandleScope<'s>,
+    message: v8::Local<'s, v8::String>,
+  ) {
+    throw_data_clone_error(scope, message);
+  }
+
+  fn get_shared_array_buffer_id<'s>(
+    &mut self,
+    scope: &mut v8::HandleScope<'s>,
+    shared_array_buffer: v8::Local<'s, v8::SharedArrayBuffer>,
+  ) -> Option<u32> {
+    let id = match self
+      .shared_array_buffers
+      .iter()
+      .position(|sab| shared_array_buffer == *sab)
+    {
+      Some(id) => id,
+      None => {
+        let sab = v8::Global::new(scope, shared_array_buffer);
+        self.shared_array_buffers.push(sab);
+        self.shared_array_buffers.len() - 1
+      }
+    };
+    Some(id as u32)
+  }
+}
+
+struct DeserializeDelegate {
+  shared_array_buffers: Vec<v8::Global<v8::SharedArrayBuffer>>,
+}
+
+impl v8::ValueDeserializerImpl for DeserializeDelegate {
+  fn get_shared_array_buffer_from_id<'s>(
+    &mut self,
+    scope: &mut v8::HandleScope<'s>,
+    id: u32,
+  ) -> Option<v8::Local<'s, v8::SharedArrayBuffer>> {
+    match self.shared_array_buffers.get(id as usize) {
+      Some(sab) => Some(v8::Local::new(scope, sab)),
+      None => {
+        throw_type_error(scope, format!("Unknown SharedArrayBuffer id {}", id));
+        None
+      }
+    }
+  }
+}
+
+/// Serializes a value with V8's ValueSerializer, following the structured
+/// clone algorithm.
+///
+/// The ArrayBuffers in the optional transfer list are detached rather than
+/// copied. Their backing stores are returned in new ArrayBuffers, which are
+/// meant to be handed to the receiving isolate and nothing else.
+/// SharedArrayBuffers are never copied. The ones referenced by the value are
+/// returned as well, for the receiving isolate to share their memory.
+///
+/// Returns `{ data, arrayBuffers, sharedArrayBuffers }`, which is what
+/// `deserialize()` takes.
+fn serialize(
+  scope: &mut v8::HandleScope,
+  args: v8::FunctionCallbackArguments,
+  mut rv: v8::ReturnValue,
+) {
+  let value = args.get(0);
+
+  let mut transferred: Vec<v8::Local<v8::ArrayBuffer>> = vec![];
+  if !args.get(1).is_undefined() {
+    let transfer = match v8::Local::<v8::Array>::try_from(args.get(1)) {
+      Ok(transfer) => transfer,
+      Err(_) => {
+        throw_type_error(scope, "Invalid argument");
+        return;
+      }
+    };
+    for i in 0..transfer.length() {
+      let item = match transfer.get_index(scope, i) {
+        Some(item) => item,
+        None => return,
+      };
+      let message = match v8::Local::<v8::ArrayBuffer>::try_from(item) {
+        Ok(ab) if !ab.is_detachable() => "ArrayBuffer is not detachable",
+        Ok(ab) if transferred.contains(&ab) => {
+          "ArrayBuffer is listed more than once in the transfer list"
+        }
+        Ok(ab) => {
+          transferred.push(ab);
+          continue;
+        }
+        Err(_) => "Value is not transferable",
+      };
+      let message = v8::String::new(scope, message).unwrap();
+      throw_data_clone_error(scope, message);
+      return;
+    }
+  }
+
+  let mut shared_array_buffers = vec![];
+  let mut serializer = v8::ValueSerializer::new(
+    scope,
+    Box::new(SerializeDelegate {
+      shared_array_buffers: &mut shared_array_buffers,
+    }),
+  );
+  for (id, ab) in transferred.iter().enumerate() {
+    serializer.transfer_array_buffer(id as u32, *ab);
+  }
+  serializer.write_header();
+  let context = scope.get_current_context();
+  if serializer.write_value(context, value) != Some(true) {
+    // The serializer has thrown already.
+    return;
+  }
+  let data = serializer.release();
+  let data = boxed_slice_to_uint8array(scope, data.into_boxed_slice());
+
+  let array_buffers = transferred
+    .into_iter()
+    .map(|ab| {
+      let backing_store = ab.get_backing_store();
+      ab.detach();
+      v8::ArrayBuffer::with_backing_store(scope, &backing_store)
+    })
+    .collect::<Vec<_>>();
+  let shared_array_buffers = shared_array_buffers
+    .iter()
+    .map(|sab| v8::Local::new(scope, sab))
+    .collect::<Vec<_>>();
+
+  let serialized =
+    new_serialized_object(scope, data, array_buffers, shared_array_buffers);
+  rv.set(serialized.into());
+}
+
+/// Deserializes an object returned by `serialize()` or `takeFromInbox()`.
+/// The ArrayBuffers in it are taken over by the deserialized value, while the
+/// SharedArrayBuffers in it are shared with it.
+fn deserialize(
+  scope: &mut v8::HandleScope,
+  args: v8::FunctionCallbackArguments,
+  mut rv: v8::ReturnValue,
+) {
+  let serialized = match v8::Local::<v8::Object>::try_from(args.get(0)) {
+    Ok(serialized) => serialized,
+    Err(_) => {
+      throw_type_error(scope, "Invalid argument");
+      return;
+    }
+  };
+
+  let data_key = v8::String::new(scope, "data").unwrap();
+  let data = match serialized.get(scope, data_key.into()) {
+    Some(data) => data,
+    None => return,
+  };
+  let data = match v8::Local::<v8::ArrayBufferView>::try_from(data) {
+    Ok(data) => data,
+    Err(_) => {
+      throw_type_error(scope, "Invalid argument");
+      return;
+    }
+  };
+
+  let array_buffers_key = v8::String::new(scope, "arrayBuffers").unwrap();
+  let array_buffers = match get_array_buffers::<v8::ArrayBuffer>(
+    scope,
+    serialized,
+    array_buffers_key,
+  ) {
+    Some(array_buffers) => array_buffers,
+    None => return,
+  };
+
+  let shared_array_buffers_key =
+    v8::String::new(scope, "sharedArrayBuffers").unwrap();
+  let shared_array_buffers = match get_array_buffers::<v8::SharedArrayBuffer>(
+    scope,
+    serialized,
+    shared_array_buffers_key,
+  ) {
+    Some(shared_array_buffers) => shared_array_buffers
+      .into_iter()
+      .map(|sab| v8::Global::new(scope, sab))
+      .collect(),
+    None => return,
+  };
+
+  let backing_store = data.buffer(scope).unwrap().get_backing_store();
+  let buf = unsafe {
+    get_backing_store_slice(
+      &backing_store,
+      data.byte_offset(),
+      data.byte_length(),
+    )
+  };
+  let mut deserializer = v8::ValueDeserializer::new(
+    scope,
+    Box::new(DeserializeDelegate {
+      shared_array_buffers,
+    }),
+    buf,
+  );
+  for (id, ab) in array_buffers.iter().enumerate() {
+    deserializer.transfer_array_buffer(id as u32, *ab);
+  }
+  let context = scope.get_current_context();
+  if deserializer.read_header(context) != Some(true) {
+    // The deserializer has thrown already.
+    return;
+  }
+  if let Some(value) = deserializer.read_value(context) {
+    rv.set(value);
+  }
+}
+
+/// Takes a value deposited into the inbox of this isolate, as an object that
+/// can be passed to `deserialize()`.
+fn take_from_inbox(
+  scope: &mut v8::HandleScope,
+  args: v8::FunctionCallbackArguments,
+  mut rv: v8::ReturnValue,
+) {
+  let id = match v8::Local::<v8::Uint32>::try_from(args.get(0)) {
+    Ok(id) => id.value() as u32,
+    Err(_) => {
+      throw_type_error(scope, "Invalid argument");
+      return;
+    }
+  };
+
+  let state_rc = JsRuntime::state(scope);
+  let value = state_rc.borrow().inbox.take(id);
+  let value = match value {
+    Some(value) => value,
+    None => {
+      throw_type_error(scope, format!("Unknown inbox id {}", id));
+      return;
+    }
+  };
+
+  let data = if value.data.is_empty() {
+    let ab = v8::ArrayBuffer::new(scope, 0);
+    v8::Uint8Array::new(scope, ab, 0, 0).expect("Failed to create UintArray8")
+  } else {
+    boxed_slice_to_uint8array(scope, value.data)
+  };
+  let array_buffers = value
+    .array_buffers
+    .iter()
+    .map(|buf| v8::ArrayBuffer::with_backing_store(scope, buf.backing_store()))
+    .collect::<Vec<_>>();
+  let shared_array_buffers = value
+    .shared_array_buffers
+    .iter()
+    .map(|buf| {
+      v8::SharedArrayBuffer::with_backing_store(scope, buf.backing_store())
+    })
+    .collect::<Vec<_>>();
+
+  let serialized =
+    new_serialized_object(scope, data, array_buffers, shared_array_buffers);
+  rv.set(serialized.into());
+}
+
+fn new_serialized_object<'s>(
+  scope: &mut v8::HandleScope<'s>,
+  data: v8::Local<v8::Uint8Array>,
+  array_buffers: Vec<v8::Local<v8::ArrayBuffer>>,
+  shared_array_buffers: Vec<v8::Local<v8::SharedArrayBuffer>>,
+) -> v8::Local<'s, v8::Object> {
+  let serialized = v8::Object::new(scope);
+
+  let data_key = v8::String::new(scope, "data").unwrap();
+  serialized.set(scope, data_key.into(), data.into());
+
+  let array_buffers_key = v8::String::new(scope, "arrayBuffers").unwrap();
+  let array_buffers_val = v8::Array::new(scope, array_buffers.len() as i32);
+  for (i, ab) in array_buffers.into_iter().enumerate() {
+    let index = v8::Integer::new(scope, i as i32);
+    array_buffers_val.set(scope, index.into(), ab.into());
+  }
+  serialized.set(scope, array_buffers_key.into(), array_buffers_val.into());
+
+  let shared_array_buffers_key =
+    v8::String::new(scope, "sharedArrayBuffers").unwrap();
+  let shared_array_buffers_val =
+    v8::Array::new(scope, shared_array_buffers.len() as i32);
+  for (i, sab) in shared_array_buffers.into_iter().enumerate() {
+    let index = v8::Integer::new(scope, i as i32);
+    shared_array_buffers_val.set(scope, index.into(), sab.into());
+  }
+  serialized.set(
+    scope,
+    shared_array_buffers_key.into(),
+    shared_array_buffers_val.into(),
+  );
+
+  serialized
+}
+
+/// Reads the array of ArrayBuffers, or SharedArrayBuffers, at `key` of
+/// `object`, which may be missing. Returns `None` if an exception has been
+/// thrown.
+fn get_array_buffers<'s, T>(
+  scope: &mut v8::HandleScope<'s>,
+  object: v8::Local<v8::Object>,
+  key: v8::Local<v8::String>,
+) -> Option<Vec<v8::Local<'s, T>>>
+where
+  v8::Local<'s, T>: TryFrom<v8::Local<'s, v8::Value>>,
+{
+  let value = object.get(scope, key.into())?;
+  if value.is_undefined() {
+    return Some(vec![]);
+  }
+  let array = match v8::Local::<v8::Array>::try_from(value) {
+    Ok(array) => array,
+    Err(_) => {
+      throw_type_error(scope, "Invalid argument");
+      return None;
+    }
+  };
+  let mut array_buffers = vec![];
+  for i in 0..array.length() {
+    let item = array.get_index(scope, i)?;
+    match v8::Local::<T>::try_from(item) {
+      Ok(ab) => array_buffers.push(ab),
+      Err(_) => {
+        throw_type_error(scope, "Invalid argument");
+        return None;
+      }
+    }
+  }
+  Some(array_buffers)
+}
+
+/// Throws an Error named "DataCloneError". The runtime turns it into a
+/// `DOMException`, which isn't available to deno_core.
+fn throw_data_clone_error<'s>(
+  scope: &mut v8::HandleScope<'s>,
+  message: v8::Local<'s, v8::String>,
+) {
+  let exception = v8::Exception::error(scope, message);
+  let name_key = v8::String::new(scope, "name").unwrap();
+  let name_val = v8::String::new(scope, "DataCloneError").unwrap();
+  exception.to_object(scope).unwrap().set(
+    scope,
+    name_key.into(),
+    name_val.into(),
+  );
+  scope.throw_exception(exception);
+}
+
 fn throw_type_error<'s>(
   scope: &mut v8::HandleScope<'s>,
   message: impl AsRef<str>,
@@ -0,0 +1,63 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+use crate::ZeroCopyBuf;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A value serialized by `Deno.core.serialize()`, on its way to another
+/// isolate.
+///
+/// `array_buffers` are the backing stores of the ArrayBuffers that were
+/// transferred along with the value. They were detached in the sending
+/// isolate, so the receiving isolate takes them over without a copy.
+/// `shared_array_buffers` are the backing stores of the SharedArrayBuffers
+/// referenced by the value, which both isolates keep using.
+///
+/// The backing stores are owned by the value: if it is never delivered,
+/// dropping it releases them.
+pub struct SerializedValue {
+  pub data: Box<[u8]>,
+  pub array_buffers: Vec<ZeroCopyBuf>,
+  pub shared_array_buffers: Vec<ZeroCopyBuf>,
+}
+
+impl From<Box<[u8]>> for SerializedValue {
+  fn from(data: Box<[u8]>) -> Self {
+    Self {
+      data,
+      array_buffers: vec![],
+      shared_array_buffers: vec![],
+    }
+  }
+}
+
+/// Values received by an isolate which haven't been materialized yet.
+///
+/// Ops can only hand bytes to JavaScript, so a value arriving from another
+/// isolate is deposited here and JavaScript picks it up, ArrayBuffers
+/// included, with `Deno.core.takeFromInbox(id)`. Each isolate has its own
+/// inbox, so an id only ever refers to values addressed to that isolate.
+#[derive(Clone, Default)]
+pub struct Inbox(Rc<RefCell<InboxState>>);
+
+#[derive(Default)]
+struct InboxState {
+  next_id: u32,
+  values: HashMap<u32, SerializedValue>,
+}
+
+impl Inbox {
+  /// Stores `value` until it is taken, and returns the id to take it with.
+  pub fn deposit(&self, value: SerializedValue) -> u32 {
+    let mut state = self.0.borrow_mut();
+    let id = state.next_id;
+    state.next_id = state.next_id.wrapping_add(1);
+    state.values.insert(id, value);
+    id
+  }
+
+  pub(crate) fn take(&self, id: u32) -> Option<SerializedValue> {
+    self.0.borrow_mut().values.remove(&id)
+  }
+}
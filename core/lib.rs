@@ -12,6 +12,7 @@ mod basic_state;
 mod bindings;
 mod errors;
 mod flags;
+mod inbox;
 mod module_specifier;
 mod modules;
 mod normalize_path;
@@ -29,6 +30,8 @@ pub use crate::errors::AnyError;
 pub use crate::errors::ErrBox;
 pub use crate::errors::JsError;
 pub use crate::flags::v8_set_flags;
+pub use crate::inbox::Inbox;
+pub use crate::inbox::SerializedValue;
 pub use crate::module_specifier::ModuleResolutionError;
 pub use crate::module_specifier::ModuleSpecifier;
 pub use crate::modules::ModuleId;
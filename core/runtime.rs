@@ -6,6 +6,7 @@ use crate::bindings;
 use crate::errors::attach_handle_to_error;
 use crate::errors::ErrWithV8Handle;
 use crate::futures::FutureExt;
+use crate::inbox::Inbox;
 use crate::module_specifier::ModuleSpecifier;
 use crate::modules::LoadState;
 use crate::modules::ModuleId;
@@ -138,6 +139,7 @@ pub struct JsRuntimeState {
   pub(crate) pending_unref_ops: FuturesUnordered<PendingOpFuture>,
   pub(crate) have_unpolled_ops: Cell<bool>,
   pub(crate) op_router: Rc<dyn OpRouter>,
+  pub(crate) inbox: Inbox,
   loader: Rc<dyn ModuleLoader>,
   pub modules: Modules,
   pub(crate) dyn_import_map:
@@ -359,6 +361,7 @@ impl JsRuntime {
       pending_unref_ops: FuturesUnordered::new(),
       have_unpolled_ops: Cell::new(false),
       op_router: options.op_router,
+      inbox: Inbox::default(),
       modules: Modules::new(),
       loader: options.loader,
       dyn_import_map: HashMap::new(),
@@ -662,6 +665,13 @@ impl JsRuntimeState {
     self.js_error_create_fn = Box::new(f);
   }
 
+  /// Sets the inbox that `Deno.core.takeFromInbox()` takes values from. Ops
+  /// delivering serialized values to this isolate deposit them into a clone
+  /// of the same inbox.
+  pub fn set_inbox(&mut self, inbox: Inbox) {
+    self.inbox = inbox;
+  }
+
   // Called by V8 during `Isolate::mod_instantiate`.
   pub fn module_resolve_cb(
     &mut self,
@@ -860,7 +870,7 @@ impl JsRuntime {
     let source_str = v8::String::new(scope, source).unwrap();
 
     let origin = bindings::module_origin(scope, name_str);
-    let source = v8::script_compiler::Source::new(source_str, &origin);
+    let source = v8::script_compiler::Source::new(source_str, Some(&origin));
 
     let tc_scope = &mut v8::TryCatch::new(scope);
 
@@ -876,9 +886,13 @@ impl JsRuntime {
     let id = module.get_identity_hash();
 
     let mut import_specifiers: Vec<ModuleSpecifier> = vec![];
-    for i in 0..module.get_module_requests_length() {
-      let import_specifier =
-        module.get_module_request(i).to_rust_string_lossy(tc_scope);
+    let module_requests = module.get_module_requests();
+    for i in 0..module_requests.length() {
+      let module_request: v8::Local<v8::ModuleRequest> =
+        module_requests.get(tc_scope, i).unwrap().into();
+      let import_specifier = module_request
+        .get_specifier()
+        .to_rust_string_lossy(tc_scope);
       let state = state_rc.borrow();
       let module_specifier =
         state.loader.resolve(&import_specifier, name, false)?;
@@ -1797,6 +1811,39 @@ pub mod tests {
     });
   }
 
+  #[test]
+  fn test_serialize_deserialize() {
+    let mut runtime =
+      JsRuntime::new(BasicState::new(), StartupData::None, false);
+    js_check(runtime.execute(
+      "serialize_deserialize.js",
+      r#"
+      const sab = new SharedArrayBuffer(4);
+      const bytes = new Uint8Array([1, 2, 3]);
+      const value = { map: new Map([["sab", sab]]), bytes, date: new Date(0) };
+      const serialized = Deno.core.serialize(value, [bytes.buffer]);
+      if (bytes.byteLength !== 0) throw Error("buffer not detached");
+      if (serialized.sharedArrayBuffers[0] !== sab) {
+        throw Error("SharedArrayBuffer not returned");
+      }
+      const clone = Deno.core.deserialize(serialized);
+      if (clone.bytes.join() !== "1,2,3") throw Error("bytes not transferred");
+      if (clone.date.getTime() !== 0) throw Error("date not cloned");
+      new Uint8Array(clone.map.get("sab"))[0] = 42;
+      if (new Uint8Array(sab)[0] !== 42) throw Error("memory not shared");
+      let caught;
+      try {
+        Deno.core.serialize({ f() {} });
+      } catch (e) {
+        caught = e;
+      }
+      if (!caught || caught.name !== "DataCloneError") {
+        throw Error("expected DataCloneError");
+      }
+      "#,
+    ));
+  }
+
   #[test]
   fn will_snapshot() {
     let snapshot = {
@@ -40,6 +40,10 @@ impl ZeroCopyBuf {
       byte_length,
     }
   }
+
+  pub(crate) fn backing_store(&self) -> &v8::SharedRef<v8::BackingStore> {
+    &self.backing_store
+  }
 }
 
 impl Deref for ZeroCopyBuf {
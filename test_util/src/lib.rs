@@ -311,6 +311,21 @@ pub async fn run_all_servers() {
         Box::new(res)
       }
     });
+  let auth_script = warp::path!("auth_script.ts")
+    .and(warp::header::optional::<String>("authorization"))
+    .map(|authorization| -> Box<dyn Reply> {
+      if authorization == Some("Bearer abcdef123456789".to_string()) {
+        let mut res = Response::new(Body::from("console.log('auth')"));
+        res.headers_mut().insert(
+          "Content-type",
+          HeaderValue::from_static("application/typescript"),
+        );
+        Box::new(res)
+      } else {
+        let r = warp::reply::with_status(warp::reply(), StatusCode::NOT_FOUND);
+        Box::new(r)
+      }
+    });
   let xtypescripttypes = warp::path!("xTypeScriptTypes.js")
     .map(|| {
       let mut res = Response::new(Body::from("export const foo = 'foo';"));
@@ -427,6 +442,7 @@ pub async fn run_all_servers() {
     .and(warp::fs::dir(root_path()))
     .map(custom_headers)
     .or(etag_script)
+    .or(auth_script)
     .or(xtypescripttypes)
     .or(echo_server)
     .or(echo_multipart_file)